@@ -4,10 +4,27 @@ pub fn run() {
     if let Err(err) = tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             get_contract_version,
+            set_error_locale,
             get_devices,
             create_device,
             update_device,
             delete_device,
+            get_credential_policy,
+            set_credential_policy,
+            renew_device_credentials,
+            list_expiring_devices,
+            rotate_device_password,
+            export_devices,
+            import_devices,
+            discover_devices,
+            scan_devices,
+            activate_device,
+            start_event_stream,
+            stop_event_stream,
+            list_event_streams,
+            list_recent_access_events,
+            fetch_access_events,
+            backfill_events_to_backend,
             test_device_connection,
             probe_device_connection,
             get_device_capabilities,
@@ -15,8 +32,20 @@ pub fn run() {
             update_device_configuration,
             get_device_webhook_config,
             sync_device_webhook_config,
+            list_device_http_hosts,
+            add_device_http_host,
+            update_device_http_host,
+            delete_device_http_host,
+            test_device_webhook,
+            start_webhook_relay,
+            stop_webhook_relay,
+            get_webhook_relay_status,
+            retry_failed_relay_events,
+            sync_device_webhook_to_relay,
             check_student_on_device,
+            check_face_quality,
             register_student,
+            import_students_from_file,
             fetch_users,
             delete_user,
             get_user_face,
@@ -26,7 +55,20 @@ pub fn run() {
             retry_provisioning,
             clone_students_to_device,
             clone_device_to_device,
+            start_clone_device_job,
+            start_clone_students_job,
+            list_clone_jobs,
+            get_clone_job,
+            pause_clone_job,
+            resume_clone_job,
+            cancel_clone_job,
         ])
+        .setup(|app| {
+            resume_interrupted_clone_jobs(app.handle());
+            settle_pending_device_passwords();
+            resume_webhook_relay();
+            Ok(())
+        })
         .run(tauri::generate_context!())
     {
         eprintln!("error while running tauri application: {}", err);
@@ -1,4 +1,4 @@
-use crate::domain::entities::DeviceConfig;
+use crate::domain::entities::{CredentialPolicy, DeviceConfig, DeviceConnectionResult};
 use chrono::{Datelike, Local, Timelike, Utc};
 
 pub fn get_max_local_devices() -> usize {
@@ -10,6 +10,38 @@ pub fn get_max_local_devices() -> usize {
         .unwrap_or(10)
 }
 
+fn env_days(name: &str, fallback: u32) -> u32 {
+    std::env::var(name)
+        .ok()
+        .or_else(|| std::env::var(format!("VITE_{}", name)).ok())
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(fallback)
+}
+
+/// Credential policy used until one is saved with `set_credential_policy`.
+pub fn default_credential_policy() -> CredentialPolicy {
+    CredentialPolicy {
+        default_lifetime_days: env_days("DEVICE_CREDENTIALS_LIFETIME_DAYS", 30),
+        warn_days: env_days("DEVICE_CREDENTIALS_WARN_DAYS", 7),
+        school_lifetime_days: Default::default(),
+    }
+}
+
+/// How many devices `register_student` provisions at the same time.
+pub fn get_device_concurrency(requested: Option<u32>) -> usize {
+    let from_env = std::env::var("DEVICE_REGISTER_CONCURRENCY")
+        .ok()
+        .or_else(|| std::env::var("VITE_DEVICE_REGISTER_CONCURRENCY").ok())
+        .and_then(|value| value.trim().parse::<usize>().ok());
+    requested
+        .map(|value| value as usize)
+        .or(from_env)
+        .filter(|value| *value > 0)
+        .unwrap_or(4)
+        .min(32)
+}
+
 pub fn generate_employee_no() -> String {
     let mut result = String::new();
     for _ in 0..10 {
@@ -18,6 +50,62 @@ pub fn generate_employee_no() -> String {
     result
 }
 
+const DEVICE_PASSWORD_LENGTH: usize = 16;
+/// Upper, lower, digits, symbols; look-alikes (0/O, 1/l/I) and XML/URL-special characters left out.
+const DEVICE_PASSWORD_CLASSES: [&str; 4] = [
+    "ABCDEFGHJKLMNPQRSTUVWXYZ",
+    "abcdefghijkmnopqrstuvwxyz",
+    "23456789",
+    "!#%*+-=?@_",
+];
+
+/// Random device admin password: 16 characters (the Hikvision maximum) with every character
+/// class present and without the user name in it, as device password rules require.
+pub fn generate_device_password(username: &str) -> String {
+    use rand::seq::SliceRandom;
+
+    let mut rng = rand::rngs::OsRng;
+    let all: Vec<char> = DEVICE_PASSWORD_CLASSES.concat().chars().collect();
+    loop {
+        let mut chars: Vec<char> = DEVICE_PASSWORD_CLASSES
+            .iter()
+            .filter_map(|class| class.chars().collect::<Vec<_>>().choose(&mut rng).copied())
+            .collect();
+        while chars.len() < DEVICE_PASSWORD_LENGTH {
+            chars.extend(all.choose(&mut rng));
+        }
+        chars.shuffle(&mut rng);
+        let password: String = chars.into_iter().collect();
+        let username = username.trim().to_lowercase();
+        if username.is_empty() || !password.to_lowercase().contains(&username) {
+            return password;
+        }
+    }
+}
+
+/// Device password rules for activation: 8-16 characters, at least two of upper, lower,
+/// digits and symbols, and not containing the user name.
+pub fn validate_device_password(username: &str, password: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if !(8..=DEVICE_PASSWORD_LENGTH).contains(&length) {
+        return Err(format!("password must be 8-{} characters", DEVICE_PASSWORD_LENGTH));
+    }
+    let classes = [
+        password.chars().any(|c| c.is_ascii_uppercase()),
+        password.chars().any(|c| c.is_ascii_lowercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_ascii_alphanumeric()),
+    ];
+    if classes.iter().filter(|present| **present).count() < 2 {
+        return Err("password must mix at least two of: upper case, lower case, digits, symbols".to_string());
+    }
+    let username = username.trim().to_lowercase();
+    if !username.is_empty() && password.to_lowercase().contains(&username) {
+        return Err("password must not contain the user name".to_string());
+    }
+    Ok(())
+}
+
 pub fn to_device_time(dt: chrono::DateTime<Local>) -> String {
     format!(
         "{}-{:02}-{:02}T{:02}:{:02}:{:02}",
@@ -30,6 +118,24 @@ pub fn to_device_time(dt: chrono::DateTime<Local>) -> String {
     )
 }
 
+/// Device-local time with offset, as the `AcsEvent` search takes it.
+pub fn to_device_event_time(dt: chrono::DateTime<Local>) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+}
+
+/// Time from the UI: RFC 3339, or `YYYY-MM-DD[T| ]HH:MM:SS` in this PC's time zone.
+pub fn parse_ui_time(value: &str) -> Result<chrono::DateTime<Local>, String> {
+    let value = value.trim();
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(parsed.with_timezone(&Local));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(|naive| naive.and_local_timezone(Local).earliest())
+        .ok_or_else(|| format!("invalid time: {}", value))
+}
+
 pub fn is_credentials_expired(device: &DeviceConfig) -> bool {
     if let Some(expires_at) = device.credentials_expires_at.as_ref() {
         if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(expires_at) {
@@ -78,3 +184,88 @@ pub fn device_match_label(device: &DeviceConfig) -> String {
     }
     format!("{}:{}", device.host, device.port)
 }
+
+/// Copy what a successful connection learned about the device (its serial and, for HTTPS,
+/// the certificate fingerprint on first use) into the stored config. Returns true if changed.
+pub fn apply_connection_identity(device: &mut DeviceConfig, result: &DeviceConnectionResult) -> bool {
+    if !result.ok {
+        return false;
+    }
+    let mut changed = false;
+    if let Some(found_id) = result.device_id.as_ref() {
+        if device.device_id.as_deref() != Some(found_id.as_str()) {
+            device.device_id = Some(found_id.clone());
+            changed = true;
+        }
+    }
+    if let Some(serial) = result.serial_number.as_ref() {
+        if device.serial_number.as_deref() != Some(serial.as_str()) {
+            device.serial_number = Some(serial.clone());
+            changed = true;
+        }
+    }
+    if device.tls_fingerprint.is_none() {
+        if let Some(fingerprint) = result.tls_fingerprint.as_ref() {
+            device.tls_fingerprint = Some(fingerprint.clone());
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Copy what `apply_connection_identity` put on `learned` (device ID, serial number, pinned
+/// certificate) onto the stored copy of the same device; other fields are left alone.
+pub fn copy_connection_identity(stored: &mut DeviceConfig, learned: &DeviceConfig) {
+    if learned.device_id.is_some() {
+        stored.device_id = learned.device_id.clone();
+    }
+    if learned.serial_number.is_some() {
+        stored.serial_number = learned.serial_number.clone();
+    }
+    if stored.tls_fingerprint.is_none() {
+        stored.tls_fingerprint = learned.tls_fingerprint.clone();
+    }
+}
+
+pub fn normalize_device_scheme(scheme: Option<&str>) -> Result<String, String> {
+    match scheme.map(|s| s.trim().to_lowercase()).as_deref() {
+        None | Some("") | Some("http") => Ok("http".to_string()),
+        Some("https") => Ok("https".to_string()),
+        _ => Err("scheme must be http|https".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_passwords_are_strong_and_distinct() {
+        let first = generate_device_password("admin");
+        let second = generate_device_password("admin");
+
+        assert_eq!(first.chars().count(), DEVICE_PASSWORD_LENGTH);
+        assert_ne!(first, second);
+        for class in DEVICE_PASSWORD_CLASSES {
+            assert!(first.chars().any(|c| class.contains(c)), "{} misses one of {}", first, class);
+        }
+        assert!(validate_device_password("admin", &first).is_ok());
+    }
+
+    #[test]
+    fn ui_times_become_device_local_times() {
+        let utc = parse_ui_time("2026-09-01T03:00:00Z").unwrap();
+        assert_eq!(utc.with_timezone(&Utc).to_rfc3339(), "2026-09-01T03:00:00+00:00");
+        let local = parse_ui_time("2026-09-01 08:30:00").unwrap();
+        assert!(to_device_event_time(local).starts_with("2026-09-01T08:30:00"));
+        assert!(parse_ui_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn weak_device_passwords_are_rejected() {
+        assert!(validate_device_password("admin", "Abc12345").is_ok());
+        assert!(validate_device_password("admin", "abcdefgh").is_err());
+        assert!(validate_device_password("admin", "Ab1").is_err());
+        assert!(validate_device_password("admin", "MyAdmin2024").is_err());
+    }
+}
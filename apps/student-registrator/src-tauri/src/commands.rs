@@ -2,24 +2,59 @@
 
 use crate::api::ApiClient;
 use crate::command_services::{
-    device_label, device_match_label, find_local_device_index, generate_employee_no,
-    get_max_local_devices, is_credentials_expired, to_device_time,
+    apply_connection_identity, copy_connection_identity, default_credential_policy, device_label, device_match_label,
+    find_local_device_index, generate_device_password, generate_employee_no, get_device_concurrency,
+    get_max_local_devices, is_credentials_expired, normalize_device_scheme, parse_ui_time,
+    to_device_event_time, to_device_time, validate_device_password,
 };
-use crate::hikvision::HikvisionClient;
-use crate::storage::{get_device_by_id, load_devices, save_devices};
+use crate::hikvision::{
+    apply_http_host, http_host_from_notification, is_already_exists_status, DeviceHttpHost, HikvisionClient,
+    HikvisionError,
+};
+use crate::infrastructure::import::student_file::{read_student_file, StudentFileRow};
+use crate::infrastructure::media::face_image::{prepare_face_image, FaceImageError};
+use crate::infrastructure::media::face_quality::assess_face_image;
+use crate::shared::error::{set_locale, CommandError, ErrorCode, Locale};
+use crate::storage::{
+    create_job, get_device_by_id, get_job, load_devices, load_job_backend_token, load_jobs, load_pending_password, load_setting, remove_device,
+    save_job, save_pending_password, save_setting, with_devices_mut,
+};
+use crate::domain::entities::{
+    events_after_mark, expiring_devices, match_discovered_devices, merge_imported_devices, next_http_host_id,
+    validate_lifetime_days,
+};
+use crate::infrastructure::discovery::sadp::discover_sadp_devices;
+use crate::infrastructure::discovery::subnet_scan::{
+    cidr_hosts, default_scan_cidr, local_ipv4_towards, parse_cidr, scan_hosts, SCAN_PORTS,
+};
+use crate::infrastructure::relay::webhook_capture::WebhookCapture;
+use crate::infrastructure::relay::webhook_relay::{RelayOptions, WebhookRelay, RELAY_PATH_PREFIX};
+use crate::infrastructure::storage::atomic_file::write_atomic;
+use crate::infrastructure::storage::device_bundle::{open_device_bundle, seal_device_bundle};
+use crate::infrastructure::storage::relay_queue::{relay_queue_counts, requeue_failed_relay_webhooks};
+use crate::infrastructure::storage::sqlite_store::app_store;
 use crate::types::{
-    DeviceConfig, DeviceConnectionResult, RegisterDeviceResult, RegisterResult,
-    UserInfoSearchResponse,
+    AccessEvent, AccessEventFilter, AccessEventPage, CapturedWebhook, CloneJob, CloneJobCursor, CloneJobProgress, CloneJobSpec, CloneJobStatus, CredentialPolicy,
+    DeviceActionResult, DeviceConfig, DeviceConnectionResult, DeviceDirection, DeviceImportMode,
+    DeviceImportReport, DiscoveredDevice, EventBackfillMark, EventBackfillReport, EventStreamState, EventStreamStatus, ExpiringDevice, FaceQualityReport, HttpHost, HttpHostProtocol, RegisterDeviceResult, RelayConfig, RelayStatus, RegisterResult, StudentImportReport,
+    StudentImportRowReport, UserInfoEntry, UserInfoSearchResponse, WebhookTestReport,
 };
 use base64::{engine::general_purpose::STANDARD, Engine as _};
+use futures::stream::{self, StreamExt};
 use chrono::{Datelike, Duration, Local, Utc};
 use reqwest::Client;
 use serde_json::Map;
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
 use uuid::Uuid;
 
 const MAX_FACE_IMAGE_BYTES: usize = 200 * 1024;
+const MAX_DOOR_COUNT: u32 = 8;
 const WEBHOOK_CANDIDATE_PATHS: [&str; 2] = [
     "ISAPI/Event/notification/httpHosts?format=json",
     "ISAPI/Event/notification/httpHosts/1?format=json",
@@ -40,11 +75,23 @@ type SuccessfulDeviceEntry = (
 include!("interfaces/tauri/commands/device_and_webhook_a.rs");
 include!("interfaces/tauri/commands/webhook_helpers_b.rs");
 include!("interfaces/tauri/commands/device_misc_b.rs");
+include!("interfaces/tauri/commands/device_credentials.rs");
+include!("interfaces/tauri/commands/device_password.rs");
+include!("interfaces/tauri/commands/device_transfer.rs");
+include!("interfaces/tauri/commands/device_discovery.rs");
+include!("interfaces/tauri/commands/device_activation.rs");
+include!("interfaces/tauri/commands/device_event_stream.rs");
+include!("interfaces/tauri/commands/device_event_history.rs");
 include!("interfaces/tauri/commands/webhook_sync_and_check.rs");
+include!("interfaces/tauri/commands/webhook_relay.rs");
+include!("interfaces/tauri/commands/webhook_hosts.rs");
+include!("interfaces/tauri/commands/webhook_test.rs");
 include!("interfaces/tauri/commands/register_student_prepare.rs");
 include!("interfaces/tauri/commands/register_student_devices.rs");
 include!("interfaces/tauri/commands/register_student.rs");
+include!("interfaces/tauri/commands/import_students.rs");
 include!("interfaces/tauri/commands/users.rs");
 include!("interfaces/tauri/commands/provisioning.rs");
+include!("interfaces/tauri/commands/clone_jobs.rs");
 include!("interfaces/tauri/commands/clone_students.rs");
 include!("interfaces/tauri/commands/clone_devices.rs");
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+/// `major` of access-control events (`MAJOR_EVENT`).
+pub const ACCESS_EVENT_MAJOR: u32 = 5;
+/// `minor` of "face authentication passed", the only event the backend records.
+pub const FACE_AUTH_PASSED_MINOR: u32 = 75;
+
+/// One access-control record from a device (`AccessControllerEvent`): a face, card or
+/// fingerprint check at a door, successful or not.
+///
+/// `major` / `minor` are the ISAPI event types, e.g. 5 / 75 for "face authentication passed".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessEvent {
+    /// Local `DeviceConfig.id` of the device that produced the event.
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    /// Per-device event counter; identifies the event together with `deviceId`.
+    #[serde(rename = "serialNo")]
+    pub serial_no: Option<u64>,
+    /// Device time as sent (ISO 8601 with offset).
+    pub time: String,
+    pub major: u32,
+    pub minor: u32,
+    #[serde(rename = "employeeNo")]
+    pub employee_no: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "doorNo")]
+    pub door_no: Option<u32>,
+    /// `currentVerifyMode`, e.g. `face`, `cardOrFace`.
+    #[serde(rename = "verifyMode")]
+    pub verify_mode: Option<String>,
+    /// Capture that came with the event, base64 JPEG.
+    #[serde(rename = "pictureBase64")]
+    pub picture_base64: Option<String>,
+    /// Capture stored on the device, when the device sends a link instead of the image.
+    #[serde(rename = "pictureUrl")]
+    pub picture_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventStreamState {
+    Connecting,
+    Connected,
+    /// Connection lost; the next attempt follows after `retryInMs`.
+    Reconnecting,
+    Stopped,
+}
+
+/// State of a device event-stream subscription, as sent to the UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventStreamStatus {
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    pub state: EventStreamState,
+    /// Why the last connection ended, or why the subscription stopped.
+    pub error: Option<String>,
+    #[serde(rename = "retryInMs")]
+    pub retry_in_ms: Option<u64>,
+    /// Events received since `start_event_stream`.
+    pub received: u64,
+}
+
+/// Search conditions of `ISAPI/AccessControl/AcsEvent`. Times are device-local with offset;
+/// `major` / `minor` 0 match every type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessEventFilter {
+    pub start_time: String,
+    pub end_time: String,
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl AccessEventFilter {
+    /// Successful face authentications in `[start_time, end_time]`: what the backend accepts.
+    pub fn face_auth_passed(start_time: String, end_time: String) -> Self {
+        Self {
+            start_time,
+            end_time,
+            major: ACCESS_EVENT_MAJOR,
+            minor: FACE_AUTH_PASSED_MINOR,
+        }
+    }
+}
+
+/// One page of access-event search results.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessEventPage {
+    pub events: Vec<AccessEvent>,
+    #[serde(rename = "totalMatches")]
+    pub total_matches: u64,
+    /// `searchResultPosition` of the next page; `None` when this was the last one.
+    #[serde(rename = "nextPosition")]
+    pub next_position: Option<u32>,
+}
+
+/// Last event of a device that reached the backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventBackfillMark {
+    #[serde(rename = "serialNo")]
+    pub serial_no: Option<u64>,
+    pub time: Option<String>,
+}
+
+impl EventBackfillMark {
+    pub fn advance(&mut self, event: &AccessEvent) {
+        if event.serial_no.is_some() {
+            self.serial_no = self.serial_no.max(event.serial_no);
+        }
+        self.time = Some(event.time.clone());
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventBackfillReport {
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    #[serde(rename = "startTime")]
+    pub start_time: String,
+    #[serde(rename = "endTime")]
+    pub end_time: String,
+    /// Events the device returned for the range.
+    pub fetched: usize,
+    /// Events at or below the mark, or repeated serial numbers.
+    pub skipped: usize,
+    pub sent: usize,
+    /// Mark after this run; the next run starts from it.
+    pub mark: EventBackfillMark,
+    /// Why sending stopped early. Sent events are kept in the mark, a re-run continues.
+    pub error: Option<String>,
+}
+
+fn event_time(time: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(time.trim()).ok()
+}
+
+/// Events not yet sent: newer than the mark (by serial number when both have one, by time
+/// otherwise), each serial number once, in device order.
+pub fn events_after_mark(events: Vec<AccessEvent>, mark: &EventBackfillMark) -> Vec<AccessEvent> {
+    let mark_time = mark.time.as_deref().and_then(event_time);
+    let mut seen = HashSet::new();
+    let mut pending: Vec<AccessEvent> = events
+        .into_iter()
+        .filter(|event| match (event.serial_no, mark.serial_no) {
+            (Some(serial), Some(last)) => serial > last,
+            _ => mark_time.is_none_or(|last| event_time(&event.time).is_some_and(|time| time > last)),
+        })
+        .filter(|event| event.serial_no.is_none_or(|serial| seen.insert(serial)))
+        .collect();
+    pending.sort_by_key(|event| (event_time(&event.time), event.serial_no));
+    pending
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(serial: Option<u64>, time: &str) -> AccessEvent {
+        AccessEvent {
+            device_id: "dev".to_string(),
+            serial_no: serial,
+            time: time.to_string(),
+            major: ACCESS_EVENT_MAJOR,
+            minor: FACE_AUTH_PASSED_MINOR,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_events_after_the_mark_are_pending_once() {
+        let mut mark = EventBackfillMark {
+            serial_no: Some(10),
+            time: Some("2026-09-01T08:00:00+05:00".to_string()),
+        };
+        let events = vec![
+            event(Some(12), "2026-09-01T08:05:00+05:00"),
+            event(Some(10), "2026-09-01T08:00:00+05:00"),
+            event(Some(11), "2026-09-01T08:01:00+05:00"),
+            // Repeated by an overlapping page.
+            event(Some(12), "2026-09-01T08:05:00+05:00"),
+            event(None, "2026-09-01T07:59:00+05:00"),
+            event(None, "2026-09-01T04:30:00Z"),
+        ];
+
+        let pending = events_after_mark(events, &mark);
+
+        let serials: Vec<_> = pending.iter().map(|e| e.serial_no).collect();
+        assert_eq!(serials, [Some(11), Some(12), None]);
+        for event in &pending {
+            mark.advance(event);
+        }
+        assert_eq!(mark.serial_no, Some(12));
+        assert_eq!(mark.time.as_deref(), Some("2026-09-01T04:30:00Z"));
+        assert!(events_after_mark(pending, &mark).is_empty());
+    }
+}
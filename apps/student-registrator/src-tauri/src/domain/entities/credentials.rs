@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::device::DeviceConfig;
+
+pub const MAX_CREDENTIAL_LIFETIME_DAYS: u32 = 3650;
+
+/// How long saved device credentials stay valid before they must be re-verified.
+///
+/// The lifetime of a device is its own `credentialsLifetimeDays`, else the value for its
+/// `schoolId`, else `defaultLifetimeDays`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialPolicy {
+    #[serde(rename = "defaultLifetimeDays")]
+    pub default_lifetime_days: u32,
+    /// `list_expiring_devices` warns this many days ahead by default.
+    #[serde(rename = "warnDays")]
+    pub warn_days: u32,
+    #[serde(default)]
+    #[serde(rename = "schoolLifetimeDays")]
+    pub school_lifetime_days: HashMap<String, u32>,
+}
+
+impl CredentialPolicy {
+    pub fn lifetime_days(&self, device: &DeviceConfig) -> u32 {
+        device
+            .credentials_lifetime_days
+            .or_else(|| {
+                device
+                    .school_id
+                    .as_ref()
+                    .and_then(|school_id| self.school_lifetime_days.get(school_id).copied())
+            })
+            .unwrap_or(self.default_lifetime_days)
+    }
+
+    /// Stamp `device` as verified at `now` and set its expiry from the policy.
+    pub fn renew(&self, device: &mut DeviceConfig, now: DateTime<Utc>) {
+        let expires = now + Duration::days(self.lifetime_days(device) as i64);
+        device.credentials_updated_at = Some(now.to_rfc3339());
+        device.credentials_expires_at = Some(expires.to_rfc3339());
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let lifetimes = std::iter::once(self.default_lifetime_days).chain(self.school_lifetime_days.values().copied());
+        for days in lifetimes {
+            validate_lifetime_days(days)?;
+        }
+        if self.warn_days > MAX_CREDENTIAL_LIFETIME_DAYS {
+            return Err(format!("warnDays must be at most {}", MAX_CREDENTIAL_LIFETIME_DAYS));
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_lifetime_days(days: u32) -> Result<u32, String> {
+    if !(1..=MAX_CREDENTIAL_LIFETIME_DAYS).contains(&days) {
+        return Err(format!("credential lifetime must be 1..{} days", MAX_CREDENTIAL_LIFETIME_DAYS));
+    }
+    Ok(days)
+}
+
+/// A device whose credentials expire within the warning window (or already have).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiringDevice {
+    pub id: String,
+    #[serde(rename = "backendId")]
+    pub backend_id: Option<String>,
+    pub host: String,
+    pub port: u16,
+    #[serde(rename = "schoolId")]
+    pub school_id: Option<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: String,
+    /// Whole days until expiry, rounded down (-1 on the first day after expiry).
+    #[serde(rename = "daysLeft")]
+    pub days_left: i64,
+    pub expired: bool,
+}
+
+/// Devices expiring before `now + within_days`, soonest first. Devices without a parsable
+/// expiry never expire and are skipped.
+pub fn expiring_devices(devices: &[DeviceConfig], now: DateTime<Utc>, within_days: u32) -> Vec<ExpiringDevice> {
+    let horizon = now + Duration::days(within_days as i64);
+    let mut expiring: Vec<(DateTime<Utc>, ExpiringDevice)> = devices
+        .iter()
+        .filter_map(|device| {
+            let expires_at = device.credentials_expires_at.as_deref()?;
+            let expires = DateTime::parse_from_rfc3339(expires_at).ok()?.with_timezone(&Utc);
+            (expires <= horizon).then(|| {
+                (
+                    expires,
+                    ExpiringDevice {
+                        id: device.id.clone(),
+                        backend_id: device.backend_id.clone(),
+                        host: device.host.clone(),
+                        port: device.port,
+                        school_id: device.school_id.clone(),
+                        expires_at: expires_at.to_string(),
+                        days_left: (expires - now).num_seconds().div_euclid(86_400),
+                        expired: expires < now,
+                    },
+                )
+            })
+        })
+        .collect();
+    expiring.sort_by_key(|(expires, _)| *expires);
+    expiring.into_iter().map(|(_, device)| device).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::device::test_device;
+
+    fn device(id: &str, school_id: Option<&str>, expires_at: Option<DateTime<Utc>>) -> DeviceConfig {
+        DeviceConfig {
+            host: "10.0.0.1".to_string(),
+            password: String::new(),
+            credentials_expires_at: expires_at.map(|at| at.to_rfc3339()),
+            scheme: Some("http".to_string()),
+            school_id: school_id.map(str::to_string),
+            ..test_device(id)
+        }
+    }
+
+    #[test]
+    fn device_lifetime_overrides_school_which_overrides_default() {
+        let policy = CredentialPolicy {
+            default_lifetime_days: 30,
+            warn_days: 7,
+            school_lifetime_days: HashMap::from([("s1".to_string(), 90)]),
+        };
+        let mut own = device("d1", Some("s1"), None);
+        own.credentials_lifetime_days = Some(5);
+
+        assert_eq!(policy.lifetime_days(&device("d0", None, None)), 30);
+        assert_eq!(policy.lifetime_days(&device("d2", Some("s1"), None)), 90);
+        assert_eq!(policy.lifetime_days(&own), 5);
+
+        let now = Utc::now();
+        policy.renew(&mut own, now);
+        assert_eq!(own.credentials_expires_at, Some((now + Duration::days(5)).to_rfc3339()));
+    }
+
+    #[test]
+    fn expiring_devices_are_listed_soonest_first() {
+        let now = Utc::now();
+        let devices = [
+            device("later", None, Some(now + Duration::days(20))),
+            device("soon", None, Some(now + Duration::days(3) + Duration::hours(1))),
+            device("expired", None, Some(now - Duration::hours(2))),
+            device("never", None, None),
+        ];
+
+        let expiring = expiring_devices(&devices, now, 7);
+
+        assert_eq!(expiring.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["expired", "soon"]);
+        assert!(expiring[0].expired);
+        assert_eq!(expiring[0].days_left, -1);
+        assert_eq!(expiring[1].days_left, 3);
+        assert!(!expiring[1].expired);
+    }
+}
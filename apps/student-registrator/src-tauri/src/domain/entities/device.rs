@@ -9,16 +9,118 @@ pub struct DeviceConfig {
     pub host: String,
     pub port: u16,
     pub username: String,
+    /// Resolved in memory only; `devices.json` keeps it empty and points at `password_ref`.
+    #[serde(default)]
     pub password: String,
+    /// Where the password is stored: `keyring:<id>` (OS secret store) or `file:<id>`
+    /// (`device_secrets.json`, AES-GCM with a machine-bound key).
+    #[serde(default)]
+    #[serde(rename = "passwordRef")]
+    pub password_ref: Option<String>,
+    /// Password a rotation is putting on the device, saved before the device is changed. While
+    /// set, either this or `password_ref` may be the one the device accepts.
+    #[serde(default)]
+    #[serde(rename = "pendingPasswordRef")]
+    pub pending_password_ref: Option<String>,
     #[serde(default)]
     #[serde(rename = "credentialsUpdatedAt")]
     pub credentials_updated_at: Option<String>,
     #[serde(default)]
     #[serde(rename = "credentialsExpiresAt")]
     pub credentials_expires_at: Option<String>,
+    /// Overrides the school / default lifetime from `CredentialPolicy`.
+    #[serde(rename = "credentialsLifetimeDays")]
+    pub credentials_lifetime_days: Option<u32>,
     #[serde(default)]
     #[serde(rename = "deviceId")]
     pub device_id: Option<String>,
+    /// `serialNumber` from `ISAPI/System/deviceInfo`; what SADP discovery reports as `DeviceSN`.
+    #[serde(default)]
+    #[serde(rename = "serialNumber")]
+    pub serial_number: Option<String>,
+    /// `http` (default) or `https`.
+    #[serde(default)]
+    pub scheme: Option<String>,
+    /// SHA-256 fingerprint of the device certificate, pinned on first successful HTTPS connect.
+    #[serde(default)]
+    #[serde(rename = "tlsFingerprint")]
+    pub tls_fingerprint: Option<String>,
+    // Fields below were added with schema v2; older files get them from the migration chain
+    // (`infrastructure/storage/device_schema.rs`), so they carry no serde defaults.
+    /// Doors (turnstile lanes) controlled by the device.
+    #[serde(rename = "doorCount")]
+    pub door_count: u32,
+    /// Free-form operator labels ("main gate", "staff").
+    pub labels: Vec<String>,
+    pub direction: DeviceDirection,
+    /// School this device belongs to, when the PC serves more than one.
+    #[serde(rename = "schoolId")]
+    pub school_id: Option<String>,
+}
+
+impl DeviceConfig {
+    /// Unsaved device with just what a client needs to reach `host`; used before a device is
+    /// added (activation), so it gets a fresh id and no stored credentials.
+    pub fn endpoint(host: &str, port: u16, username: &str, password: &str, scheme: &str) -> Self {
+        DeviceConfig {
+            id: uuid::Uuid::new_v4().to_string(),
+            backend_id: None,
+            host: host.to_string(),
+            port,
+            username: username.to_string(),
+            password: password.to_string(),
+            password_ref: None,
+            pending_password_ref: None,
+            credentials_updated_at: None,
+            credentials_expires_at: None,
+            credentials_lifetime_days: None,
+            device_id: None,
+            serial_number: None,
+            scheme: Some(scheme.to_string()),
+            tls_fingerprint: None,
+            door_count: 1,
+            labels: Vec::new(),
+            direction: DeviceDirection::Both,
+            school_id: None,
+        }
+    }
+}
+
+/// Plain HTTP device on localhost with an in-memory password; tests override what they need
+/// with struct update syntax.
+#[cfg(test)]
+pub(crate) fn test_device(id: &str) -> DeviceConfig {
+    DeviceConfig {
+        id: id.to_string(),
+        backend_id: None,
+        host: "127.0.0.1".to_string(),
+        port: 80,
+        username: "admin".to_string(),
+        password: "secret".to_string(),
+        password_ref: None,
+        pending_password_ref: None,
+        credentials_updated_at: None,
+        credentials_expires_at: None,
+        credentials_lifetime_days: None,
+        device_id: None,
+        serial_number: None,
+        scheme: None,
+        tls_fingerprint: None,
+        door_count: 1,
+        labels: Vec::new(),
+        direction: DeviceDirection::Both,
+        school_id: None,
+    }
+}
+
+/// Which way people pass the device; used to tell entries from exits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeviceDirection {
+    In,
+    Out,
+    #[default]
+    Both,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +129,12 @@ pub struct DeviceConnectionResult {
     pub message: Option<String>,
     #[serde(rename = "deviceId")]
     pub device_id: Option<String>,
+    #[serde(default)]
+    #[serde(rename = "serialNumber")]
+    pub serial_number: Option<String>,
+    #[serde(default)]
+    #[serde(rename = "tlsFingerprint")]
+    pub tls_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +144,10 @@ pub struct DeviceActionResult {
     pub status_code: Option<i32>,
     #[serde(rename = "statusString")]
     pub status_string: Option<String>,
+    /// ISAPI `subStatusCode`, e.g. `employeeNoAlreadyExist`.
+    #[serde(default)]
+    #[serde(rename = "subStatusCode")]
+    pub sub_status_code: Option<String>,
     #[serde(rename = "errorMsg")]
     pub error_msg: Option<String>,
 }
@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::device::DeviceConfig;
+use crate::domain::repositories::dedupe_key;
+
+/// What `import_devices` does with a bundle device that is already configured here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeviceImportMode {
+    /// Keep the local device and report the differences.
+    #[default]
+    Skip,
+    /// Replace the local settings with the bundle's (the local id is kept).
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceImportEntry {
+    /// Dedupe key the device was matched on (`backend:..`, `device:..` or `endpoint:..`).
+    pub key: String,
+    #[serde(rename = "backendId")]
+    pub backend_id: Option<String>,
+    pub host: String,
+    pub port: u16,
+    /// ADDED | UPDATED | UNCHANGED | SKIPPED | REJECTED
+    pub status: String,
+    /// Local device the entry was added as or matched with.
+    #[serde(rename = "localId")]
+    pub local_id: Option<String>,
+    /// Fields that differ between the bundle and the local device.
+    pub conflicts: Vec<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceImportReport {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub total: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    pub devices: Vec<DeviceImportEntry>,
+}
+
+/// Fields (by their JSON names) in which `incoming` differs from `local`. Ids, where the
+/// password is kept and credential timestamps are local bookkeeping and not compared.
+fn device_differences(local: &DeviceConfig, incoming: &DeviceConfig) -> Vec<String> {
+    let checks = [
+        ("backendId", local.backend_id != incoming.backend_id),
+        ("host", local.host != incoming.host),
+        ("port", local.port != incoming.port),
+        ("username", local.username != incoming.username),
+        ("password", local.password != incoming.password),
+        ("deviceId", local.device_id != incoming.device_id),
+        ("serialNumber", local.serial_number != incoming.serial_number),
+        ("scheme", local.scheme != incoming.scheme),
+        ("tlsFingerprint", local.tls_fingerprint != incoming.tls_fingerprint),
+        ("credentialsLifetimeDays", local.credentials_lifetime_days != incoming.credentials_lifetime_days),
+        ("doorCount", local.door_count != incoming.door_count),
+        ("labels", local.labels != incoming.labels),
+        ("direction", local.direction != incoming.direction),
+        ("schoolId", local.school_id != incoming.school_id),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(field, _)| field.to_string())
+        .collect()
+}
+
+/// Merge bundle devices into `devices` by dedupe key.
+///
+/// New devices get a fresh local id and count against `max_devices`; matched ones are left
+/// alone or overwritten according to `mode`. A key seen twice in the bundle is only taken once.
+pub fn merge_imported_devices(
+    devices: &mut Vec<DeviceConfig>,
+    incoming: Vec<DeviceConfig>,
+    mode: DeviceImportMode,
+    max_devices: usize,
+    new_id: &mut dyn FnMut() -> String,
+) -> DeviceImportReport {
+    let mut report = DeviceImportReport {
+        dry_run: false,
+        total: incoming.len(),
+        added: 0,
+        updated: 0,
+        unchanged: 0,
+        skipped: 0,
+        rejected: 0,
+        devices: Vec::with_capacity(incoming.len()),
+    };
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for mut device in incoming {
+        let key = dedupe_key(&device);
+        let mut entry = DeviceImportEntry {
+            key: key.clone(),
+            backend_id: device.backend_id.clone(),
+            host: device.host.clone(),
+            port: device.port,
+            status: "SKIPPED".to_string(),
+            local_id: None,
+            conflicts: Vec::new(),
+            reason: None,
+        };
+
+        if !seen.insert(key.clone()) {
+            entry.reason = Some("duplicate in bundle".to_string());
+            report.skipped += 1;
+        } else if let Some(local) = devices.iter_mut().find(|d| dedupe_key(d) == key) {
+            entry.local_id = Some(local.id.clone());
+            entry.conflicts = device_differences(local, &device);
+            if entry.conflicts.is_empty() {
+                entry.status = "UNCHANGED".to_string();
+                report.unchanged += 1;
+            } else if mode == DeviceImportMode::Overwrite {
+                device.id = local.id.clone();
+                device.password_ref = local.password_ref.clone();
+                device.pending_password_ref = local.pending_password_ref.clone();
+                *local = device;
+                entry.status = "UPDATED".to_string();
+                report.updated += 1;
+            } else {
+                entry.reason = Some("already configured with different settings".to_string());
+                report.skipped += 1;
+            }
+        } else if devices.len() >= max_devices {
+            entry.status = "REJECTED".to_string();
+            entry.reason = Some(format!("Maximum {} devices allowed", max_devices));
+            report.rejected += 1;
+        } else {
+            device.id = new_id();
+            device.password_ref = None;
+            device.pending_password_ref = None;
+            entry.local_id = Some(device.id.clone());
+            entry.status = "ADDED".to_string();
+            devices.push(device);
+            report.added += 1;
+        }
+        report.devices.push(entry);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::device::test_device;
+
+    fn device(id: &str, backend_id: Option<&str>, host: &str) -> DeviceConfig {
+        DeviceConfig {
+            backend_id: backend_id.map(str::to_string),
+            host: host.to_string(),
+            password_ref: Some(format!("keyring:{}", id)),
+            scheme: Some("http".to_string()),
+            ..test_device(id)
+        }
+    }
+
+    fn statuses(report: &DeviceImportReport) -> Vec<&str> {
+        report.devices.iter().map(|entry| entry.status.as_str()).collect()
+    }
+
+    #[test]
+    fn bundle_devices_are_matched_by_dedupe_key() {
+        let mut local = vec![device("l1", Some("b1"), "10.0.0.1"), device("l2", None, "10.0.0.2")];
+        let mut moved = device("x1", Some("b1"), "10.0.0.9");
+        moved.labels = vec!["gate".to_string()];
+        let incoming = vec![
+            moved,
+            device("x2", None, "10.0.0.2"),
+            device("x3", None, "10.0.0.3"),
+            device("x4", None, "10.0.0.3"),
+        ];
+        let mut ids = 0;
+        let mut new_id = || {
+            ids += 1;
+            format!("new{}", ids)
+        };
+
+        let report = merge_imported_devices(&mut local, incoming.clone(), DeviceImportMode::Skip, 10, &mut new_id);
+
+        assert_eq!(statuses(&report), ["SKIPPED", "UNCHANGED", "ADDED", "SKIPPED"]);
+        assert_eq!(report.devices[0].conflicts, ["host", "labels"]);
+        assert_eq!(report.devices[3].reason.as_deref(), Some("duplicate in bundle"));
+        assert_eq!(local.len(), 3);
+        assert_eq!(local[0].host, "10.0.0.1");
+        assert_eq!(local[2].id, "new1");
+        assert_eq!(local[2].password_ref, None);
+
+        let report = merge_imported_devices(&mut local, incoming, DeviceImportMode::Overwrite, 10, &mut new_id);
+
+        assert_eq!(statuses(&report), ["UPDATED", "UNCHANGED", "UNCHANGED", "SKIPPED"]);
+        assert_eq!(local[0].id, "l1");
+        assert_eq!(local[0].host, "10.0.0.9");
+        assert_eq!(local[0].password_ref.as_deref(), Some("keyring:l1"));
+    }
+
+    #[test]
+    fn new_devices_beyond_the_limit_are_rejected() {
+        let mut local = vec![device("l1", None, "10.0.0.1")];
+        let incoming = vec![device("x1", None, "10.0.0.2"), device("x2", None, "10.0.0.3")];
+
+        let report = merge_imported_devices(&mut local, incoming, DeviceImportMode::Skip, 2, &mut || "n".to_string());
+
+        assert_eq!(statuses(&report), ["ADDED", "REJECTED"]);
+        assert_eq!((report.added, report.rejected), (1, 1));
+        assert_eq!(local.len(), 2);
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use super::device::DeviceConfig;
+
+/// A device that answered a LAN discovery probe.
+///
+/// `ipv4Address` / `httpPort` are what `create_device` takes as `host` / `port`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredDevice {
+    pub model: Option<String>,
+    #[serde(rename = "serialNumber")]
+    pub serial_number: Option<String>,
+    pub mac: Option<String>,
+    #[serde(rename = "ipv4Address")]
+    pub ipv4_address: String,
+    #[serde(rename = "subnetMask")]
+    pub subnet_mask: Option<String>,
+    #[serde(rename = "httpPort")]
+    pub http_port: Option<u16>,
+    #[serde(rename = "commandPort")]
+    pub command_port: Option<u16>,
+    #[serde(rename = "firmwareVersion")]
+    pub firmware_version: Option<String>,
+    /// `false` for a factory-new device that still needs an admin password.
+    pub activated: Option<bool>,
+    pub dhcp: Option<bool>,
+    /// Local `DeviceConfig.id` of the same device when it is already configured.
+    #[serde(rename = "localDeviceId")]
+    pub local_device_id: Option<String>,
+}
+
+fn same_identity(a: &str, b: &str) -> bool {
+    !a.trim().is_empty() && a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Point each discovered device at the configured device it is: same serial number first,
+/// otherwise same address and HTTP port. `deviceId` is the ISAPI device ID, not the serial
+/// SADP reports, so it is not compared.
+pub fn match_discovered_devices(discovered: &mut [DiscoveredDevice], devices: &[DeviceConfig]) {
+    for found in discovered.iter_mut() {
+        let by_serial = found.serial_number.as_deref().and_then(|serial| {
+            devices
+                .iter()
+                .find(|d| d.serial_number.as_deref().is_some_and(|sn| same_identity(sn, serial)))
+        });
+        let by_endpoint = || {
+            devices.iter().find(|d| {
+                d.host.trim() == found.ipv4_address && found.http_port.is_none_or(|port| port == d.port)
+            })
+        };
+        found.local_device_id = by_serial.or_else(by_endpoint).map(|d| d.id.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::device::test_device;
+
+    fn configured(id: &str, host: &str, serial_number: Option<&str>) -> DeviceConfig {
+        DeviceConfig {
+            host: host.to_string(),
+            password: String::new(),
+            serial_number: serial_number.map(str::to_string),
+            scheme: Some("http".to_string()),
+            ..test_device(id)
+        }
+    }
+
+    #[test]
+    fn discovered_devices_match_by_serial_then_endpoint() {
+        let mut isapi_id_only = configured("other", "10.0.0.7", None);
+        isapi_id_only.device_id = Some("SN-3".to_string());
+        let devices = [
+            configured("moved", "10.0.0.1", Some("sn-1")),
+            configured("static", "10.0.0.2", None),
+            isapi_id_only,
+        ];
+        let found = |serial: &str, ip: &str| DiscoveredDevice {
+            serial_number: Some(serial.to_string()),
+            ipv4_address: ip.to_string(),
+            http_port: Some(80),
+            ..Default::default()
+        };
+        let mut discovered = [found("SN-1", "10.0.0.9"), found("SN-2", "10.0.0.2"), found("SN-3", "10.0.0.3")];
+
+        match_discovered_devices(&mut discovered, &devices);
+
+        let matched: Vec<_> = discovered.iter().map(|d| d.local_device_id.as_deref()).collect();
+        assert_eq!(matched, [Some("moved"), Some("static"), None]);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FaceQualityIssueCode {
+    LowResolution,
+    Blurry,
+    TooDark,
+    TooBright,
+    LowContrast,
+    NoFace,
+    MultipleFaces,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaceQualityIssue {
+    pub code: FaceQualityIssueCode,
+    /// Localized hint for the operator ("retake in better light", ...).
+    pub message: String,
+    /// Measured value and the limit it violated.
+    pub value: f64,
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FaceRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Verdict of the offline photo check run before a student is provisioned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaceQualityReport {
+    pub ok: bool,
+    pub width: u32,
+    pub height: u32,
+    /// Variance of the Laplacian; low means blurred.
+    pub sharpness: f64,
+    /// Mean luma (0-255) over the face, or the whole photo when no face was found.
+    pub brightness: f64,
+    /// Luma standard deviation over the whole photo.
+    pub contrast: f64,
+    #[serde(rename = "faceCount")]
+    pub face_count: usize,
+    pub face: Option<FaceRegion>,
+    /// Problems that block registration; `ok` is true when there are none.
+    pub issues: Vec<FaceQualityIssue>,
+    /// Face count findings (`NO_FACE`, `MULTIPLE_FACES`). They come from a skin-tone heuristic
+    /// that misreads dark skin, grayscale photos and warm backgrounds, so they are only shown to
+    /// the operator and never block.
+    #[serde(default)]
+    pub warnings: Vec<FaceQualityIssue>,
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpHostProtocol {
+    #[default]
+    Http,
+    Https,
+}
+
+/// Body format of the event posts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HttpHostParameterFormat {
+    #[default]
+    Json,
+    Xml,
+}
+
+/// One webhook target of a device (`HttpHostNotification`). A device has a few of these slots,
+/// e.g. entries and exits can post to separate hosts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpHost {
+    /// Slot id on the device; empty in `add_device_http_host` picks the first free one.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub protocol: HttpHostProtocol,
+    /// IP address or host name.
+    pub address: String,
+    pub port: u16,
+    /// Path and query the device posts to, e.g. `/webhook/<schoolId>/in?secret=..`.
+    pub url: String,
+    #[serde(default)]
+    #[serde(rename = "parameterFormat")]
+    pub parameter_format: HttpHostParameterFormat,
+    /// Heartbeat interval as the device keeps it (`SubscribeEvent.heartbeat`); `None` keeps the
+    /// device default.
+    #[serde(default)]
+    #[serde(rename = "heartbeatInterval")]
+    pub heartbeat_interval: Option<u32>,
+    /// Subscribed event types (e.g. `AccessControllerEvent`); empty means every event.
+    #[serde(default)]
+    #[serde(rename = "eventTypes")]
+    pub event_types: Vec<String>,
+}
+
+impl HttpHost {
+    /// Trim the text fields and drop blank or repeated event types.
+    pub fn normalized(mut self) -> Self {
+        self.id = self.id.trim().to_string();
+        self.address = self.address.trim().to_string();
+        self.url = self.url.trim().to_string();
+        let mut event_types: Vec<String> = Vec::new();
+        for event_type in self.event_types.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !event_types.iter().any(|seen| seen == event_type) {
+                event_types.push(event_type.to_string());
+            }
+        }
+        self.event_types = event_types;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.address.is_empty() || self.address.contains(['/', ' ']) {
+            return Err("address must be an IP address or host name without scheme or path".to_string());
+        }
+        if self.port == 0 {
+            return Err("port must not be 0".to_string());
+        }
+        if !self.url.starts_with('/') {
+            return Err("url must be a path starting with /".to_string());
+        }
+        if self.heartbeat_interval == Some(0) {
+            return Err("heartbeatInterval must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What the device posted to the capture listener during `test_device_webhook`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedWebhook {
+    pub method: String,
+    pub path: String,
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
+    /// Body as text (invalid UTF-8 replaced); `bodyBytes` is its real size.
+    pub body: String,
+    #[serde(rename = "bodyBytes")]
+    pub body_bytes: usize,
+    /// From the test request until the post arrived.
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookTestReport {
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    #[serde(rename = "hostId")]
+    pub host_id: String,
+    /// Where the device posted: the slot's own target, or the capture listener.
+    pub target: String,
+    /// With capture: the post arrived. Without: the device reported success.
+    pub reachable: bool,
+    /// How long the device took to answer the test request (it posts before answering).
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: Option<u64>,
+    /// The device's own verdict, e.g. `ok` or `connect server fail`.
+    #[serde(rename = "deviceResult")]
+    pub device_result: Option<String>,
+    pub captured: Option<CapturedWebhook>,
+    /// Why the test failed; empty once a captured post shows the target is reachable.
+    pub error: Option<String>,
+    /// With capture: the slot could not be put back and still points at the listener.
+    #[serde(rename = "restoreError")]
+    pub restore_error: Option<String>,
+}
+
+/// Smallest numeric slot id not taken by `hosts`.
+pub fn next_http_host_id(hosts: &[HttpHost]) -> String {
+    (1u32..)
+        .find(|id| !hosts.iter().any(|host| host.id == id.to_string()))
+        .unwrap_or(1)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_are_normalized_validated_and_get_free_ids() {
+        let host = HttpHost {
+            address: " 10.0.0.5 ".to_string(),
+            port: 8080,
+            url: " /webhook/s1/in ".to_string(),
+            event_types: vec![" AccessControllerEvent".to_string(), String::new(), "AccessControllerEvent".to_string()],
+            ..Default::default()
+        }
+        .normalized();
+        assert_eq!(host.address, "10.0.0.5");
+        assert_eq!(host.event_types, ["AccessControllerEvent"]);
+        assert!(host.validate().is_ok());
+
+        assert!(HttpHost { url: "webhook".to_string(), ..host.clone() }.validate().is_err());
+        assert!(HttpHost { address: "http://x".to_string(), ..host.clone() }.validate().is_err());
+
+        let taken = |id: &str| HttpHost { id: id.to_string(), ..Default::default() };
+        assert_eq!(next_http_host_id(&[]), "1");
+        assert_eq!(next_http_host_id(&[taken("1"), taken("3")]), "2");
+    }
+}
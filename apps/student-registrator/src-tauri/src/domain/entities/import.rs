@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudentImportRowReport {
+    pub sheet: String,
+    pub row: u32,
+    pub name: String,
+    #[serde(rename = "className")]
+    pub class_name: Option<String>,
+    /// VALID (dry run) | INVALID | REGISTERED | FAILED
+    pub status: String,
+    /// Validation problems found in the row.
+    pub errors: Vec<String>,
+    /// Photo findings that do not block the row (e.g. no face detected).
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(rename = "employeeNo")]
+    pub employee_no: Option<String>,
+    #[serde(rename = "provisioningId")]
+    pub provisioning_id: Option<String>,
+    /// Command error (`{code, message, details}`) when registration failed.
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudentImportReport {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    pub registered: usize,
+    pub failed: usize,
+    pub rows: Vec<StudentImportRowReport>,
+}
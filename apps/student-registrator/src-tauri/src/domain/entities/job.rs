@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CloneJobStatus {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+impl CloneJobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Cancelled | Self::Completed | Self::Failed)
+    }
+}
+
+/// What a clone job copies and with which inputs; kept on disk so the job can be resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum CloneJobSpec {
+    #[serde(rename = "DEVICE_TO_DEVICE")]
+    DeviceToDevice {
+        #[serde(rename = "sourceDeviceId")]
+        source_device_id: String,
+        #[serde(rename = "targetDeviceId")]
+        target_device_id: String,
+        limit: u32,
+    },
+    #[serde(rename = "BACKEND_TO_DEVICE")]
+    BackendToDevice {
+        #[serde(rename = "backendDeviceId")]
+        backend_device_id: String,
+        #[serde(rename = "backendUrl")]
+        backend_url: String,
+        /// Only in memory; on disk the token is kept in the secret store (`backendTokenRef`).
+        /// Older job files still carry it here and are moved over on the next read.
+        #[serde(rename = "backendToken", default, skip_serializing)]
+        backend_token: Option<String>,
+        #[serde(rename = "backendTokenRef", default, skip_serializing_if = "Option::is_none")]
+        backend_token_ref: Option<String>,
+        #[serde(rename = "schoolId")]
+        school_id: String,
+        limit: u32,
+    },
+}
+
+/// Position in the source list. Device sources page by absolute `offset`; the backend pages
+/// by `page` number and `offset` is the index inside that page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloneJobCursor {
+    pub page: u32,
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneJob {
+    pub id: String,
+    pub spec: CloneJobSpec,
+    pub status: CloneJobStatus,
+    pub cursor: CloneJobCursor,
+    pub processed: u32,
+    pub success: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    #[serde(default)]
+    pub errors: Vec<Value>,
+    /// Student being processed right now (name or employeeNo).
+    #[serde(default)]
+    pub current: Option<String>,
+    /// Source/target labels for the summary.
+    #[serde(default)]
+    pub labels: Value,
+    /// Command error (`{code, message, details}`) if the job failed.
+    #[serde(default)]
+    pub error: Option<Value>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+/// Payload of the `clone-job-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloneJobProgress {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub status: CloneJobStatus,
+    pub processed: u32,
+    pub success: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub current: Option<String>,
+}
+
+impl From<&CloneJob> for CloneJobProgress {
+    fn from(job: &CloneJob) -> Self {
+        Self {
+            job_id: job.id.clone(),
+            status: job.status,
+            processed: job.processed,
+            success: job.success,
+            failed: job.failed,
+            skipped: job.skipped,
+            current: job.current.clone(),
+        }
+    }
+}
+
+/// Device-to-device job with empty counters.
+#[cfg(test)]
+pub(crate) fn test_clone_job(id: &str, status: CloneJobStatus) -> CloneJob {
+    CloneJob {
+        id: id.to_string(),
+        spec: CloneJobSpec::DeviceToDevice {
+            source_device_id: "src".to_string(),
+            target_device_id: "tgt".to_string(),
+            limit: 100,
+        },
+        status,
+        cursor: CloneJobCursor::default(),
+        processed: 0,
+        success: 0,
+        failed: 0,
+        skipped: 0,
+        errors: Vec::new(),
+        current: None,
+        labels: serde_json::Value::Null,
+        error: None,
+        created_at: String::new(),
+        updated_at: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_job_round_trips_through_json() {
+        let mut job = test_clone_job("j1", CloneJobStatus::Paused);
+        job.cursor = CloneJobCursor { page: 3, offset: 7 };
+        let value = serde_json::to_value(&job).unwrap();
+        assert_eq!(value["spec"]["kind"], "DEVICE_TO_DEVICE");
+        assert_eq!(value["status"], "PAUSED");
+        let parsed: CloneJob = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.cursor, CloneJobCursor { page: 3, offset: 7 });
+    }
+}
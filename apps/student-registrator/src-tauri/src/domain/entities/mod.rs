@@ -1,9 +1,32 @@
+pub mod access_event;
+pub mod credentials;
 pub mod device;
+pub mod device_transfer;
+pub mod discovery;
+pub mod face_quality;
+pub mod http_host;
+pub mod import;
+pub mod job;
 pub mod provisioning;
 pub mod register;
+pub mod relay;
 pub mod user;
 
-pub use device::{DeviceActionResult, DeviceConfig, DeviceConnectionResult};
+pub use access_event::{
+    events_after_mark, AccessEvent, AccessEventFilter, AccessEventPage, EventBackfillMark, EventBackfillReport,
+    EventStreamState, EventStreamStatus,
+};
+pub use credentials::{expiring_devices, validate_lifetime_days, CredentialPolicy, ExpiringDevice};
+pub use device::{DeviceActionResult, DeviceConfig, DeviceConnectionResult, DeviceDirection};
+pub use device_transfer::{merge_imported_devices, DeviceImportEntry, DeviceImportMode, DeviceImportReport};
+pub use discovery::{match_discovered_devices, DiscoveredDevice};
+pub use face_quality::{FaceQualityIssue, FaceQualityIssueCode, FaceQualityReport, FaceRegion};
+pub use http_host::{
+    next_http_host_id, CapturedWebhook, HttpHost, HttpHostParameterFormat, HttpHostProtocol, WebhookTestReport,
+};
+pub use import::{StudentImportReport, StudentImportRowReport};
+pub use job::{CloneJob, CloneJobCursor, CloneJobProgress, CloneJobSpec, CloneJobStatus};
 pub use provisioning::{ProvisioningStartResponse, ProvisioningTargetDevice};
 pub use register::{RegisterDeviceResult, RegisterResult};
+pub use relay::{is_permanent_rejection, relay_retry_delay_ms, RelayConfig, RelayStatus};
 pub use user::{UserInfoEntry, UserInfoSearch, UserInfoSearchResponse};
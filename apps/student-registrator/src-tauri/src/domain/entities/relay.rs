@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Webhook relay settings (`webhookRelay` setting). `backendUrl` is the backend base URL the
+/// queued webhooks are forwarded to, the request path is kept as the device sent it. `routes`
+/// are the webhook paths (with query) devices were synced to; the relay accepts only those.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayConfig {
+    pub enabled: bool,
+    #[serde(rename = "listenPort")]
+    pub listen_port: u16,
+    #[serde(rename = "backendUrl")]
+    pub backend_url: String,
+    #[serde(default)]
+    pub routes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayStatus {
+    pub running: bool,
+    #[serde(rename = "listenPort")]
+    pub listen_port: Option<u16>,
+    #[serde(rename = "backendUrl")]
+    pub backend_url: Option<String>,
+    /// Events waiting in the local queue.
+    pub pending: u64,
+    /// Events the backend refused for good (kept until `retry_failed_relay_events`).
+    pub failed: u64,
+    /// Events forwarded since the relay started.
+    pub delivered: u64,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    /// Set while forwarding is backing off after a failed attempt.
+    #[serde(rename = "retryInMs")]
+    pub retry_in_ms: Option<u64>,
+}
+
+/// Delay before the next attempt after `failures` failed ones in a row: doubles from `base_ms`
+/// up to `max_ms`.
+pub fn relay_retry_delay_ms(failures: u32, base_ms: u64, max_ms: u64) -> u64 {
+    let exponent = failures.saturating_sub(1).min(20);
+    base_ms.saturating_mul(1u64 << exponent).min(max_ms)
+}
+
+/// A backend answer that will not change on retry (bad payload, unknown school, wrong secret).
+/// Timeouts, rate limits and 5xx are retried.
+pub fn is_permanent_rejection(status: u16) -> bool {
+    (400..500).contains(&status) && !matches!(status, 408 | 425 | 429)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let delays: Vec<u64> = (1..=6).map(|n| relay_retry_delay_ms(n, 1000, 10_000)).collect();
+        assert_eq!(delays, [1000, 2000, 4000, 8000, 10_000, 10_000]);
+        assert_eq!(relay_retry_delay_ms(200, 1000, 600_000), 600_000);
+        assert!(is_permanent_rejection(403));
+        assert!(!is_permanent_rejection(429));
+        assert!(!is_permanent_rejection(503));
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::domain::entities::DeviceConfig;
+
+/// Local device storage. Passwords come back resolved and go in as plain text; where they are
+/// actually kept (`password_ref`) is up to the implementation.
+pub trait DeviceRepository {
+    /// All devices in the order they were added.
+    fn list_devices(&self) -> Result<Vec<DeviceConfig>, String>;
+
+    fn get_device(&self, id: &str) -> Result<Option<DeviceConfig>, String>;
+
+    /// Returns whether a device was removed.
+    fn delete_device(&self, id: &str) -> Result<bool, String>;
+
+    /// Run `update` on the full list and save the result if it changed; fails rather than
+    /// overwrite a change made by someone else in the meantime.
+    /// Devices that end up with the same dedupe key are merged (the later one wins).
+    fn update_devices(&self, update: &mut dyn FnMut(&mut Vec<DeviceConfig>)) -> Result<(), String>;
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Identity of a physical device: backend id, else the ISAPI device ID (`device_id`; the
+/// serial number is a separate field), else endpoint + user.
+pub fn dedupe_key(device: &DeviceConfig) -> String {
+    if let Some(backend_id) = device.backend_id.as_ref() {
+        if !backend_id.trim().is_empty() {
+            return format!("backend:{}", normalize(backend_id));
+        }
+    }
+    if let Some(device_id) = device.device_id.as_ref() {
+        if !device_id.trim().is_empty() {
+            return format!("device:{}", normalize(device_id));
+        }
+    }
+    format!(
+        "endpoint:{}:{}:{}",
+        normalize(&device.host),
+        device.port,
+        normalize(&device.username),
+    )
+}
+
+/// Keep one device per dedupe key: the later entry replaces the earlier one in place.
+pub fn dedupe_devices(devices: Vec<DeviceConfig>) -> Vec<DeviceConfig> {
+    let mut deduped: Vec<DeviceConfig> = Vec::new();
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+    for device in devices {
+        let key = dedupe_key(&device);
+        if let Some(index) = index_by_key.get(&key).copied() {
+            deduped[index] = device;
+        } else {
+            index_by_key.insert(key, deduped.len());
+            deduped.push(device);
+        }
+    }
+
+    deduped
+}
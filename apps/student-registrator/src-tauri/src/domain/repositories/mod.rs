@@ -0,0 +1,3 @@
+pub mod device;
+
+pub use device::{dedupe_devices, dedupe_key, DeviceRepository};
@@ -1,13 +1,20 @@
 // Hikvision ISAPI client - simplified with basic auth fallback
 // Note: Hikvision devices may require Digest auth; this client tries Basic first,
-// then falls back to Digest when it receives a 401 challenge.
+// then falls back to Digest when it receives a 401 challenge. The last digest challenge is
+// cached per device so later requests authenticate on the first round-trip.
 
+use crate::infrastructure::media::face_image::{prepare_face_image, FaceImageError};
 use crate::types::{
-    DeviceActionResult, DeviceConfig, DeviceConnectionResult, UserInfoEntry, UserInfoSearchResponse,
+    AccessEvent, AccessEventFilter, AccessEventPage, DeviceActionResult, DeviceConfig,
+    DeviceConnectionResult, HttpHost, HttpHostParameterFormat, HttpHostProtocol, UserInfoEntry,
+    UserInfoSearchResponse,
 };
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use reqwest::{Client, Response};
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
 const DEFAULT_TIMEOUT_SECS: u64 = 8;
@@ -30,17 +37,123 @@ struct DigestChallenge {
     qop: Option<String>,
     opaque: Option<String>,
     algorithm: Option<String>,
+    stale: bool,
 }
 
+/// Digest algorithms from RFC 7616 that the client can answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    fn from_challenge(challenge: &DigestChallenge) -> Result<Self, String> {
+        let raw = challenge.algorithm.as_deref().unwrap_or("MD5").trim();
+        match raw.to_ascii_uppercase().as_str() {
+            "MD5" => Ok(Self::Md5),
+            "MD5-SESS" => Ok(Self::Md5Sess),
+            "SHA-256" => Ok(Self::Sha256),
+            "SHA-256-SESS" => Ok(Self::Sha256Sess),
+            _ => Err(format!("Unsupported digest algorithm: {}", raw)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn strength(self) -> u8 {
+        match self {
+            Self::Md5 => 1,
+            Self::Md5Sess => 2,
+            Self::Sha256 => 3,
+            Self::Sha256Sess => 4,
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    fn hash(self, input: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => format!("{:x}", md5::compute(input)),
+            Self::Sha256 | Self::Sha256Sess => format!("{:x}", Sha256::digest(input.as_bytes())),
+        }
+    }
+
+    /// HA1 per RFC 7616 section 3.4.2; `-sess` variants mix in the nonce and cnonce.
+    fn ha1(self, username: &str, realm: &str, password: &str, nonce: &str, cnonce: &str) -> String {
+        let ha1 = self.hash(&format!("{}:{}:{}", username, realm, password));
+        if self.is_session() {
+            self.hash(&format!("{}:{}:{}", ha1, nonce, cnonce))
+        } else {
+            ha1
+        }
+    }
+
+    fn response(
+        self,
+        ha1: &str,
+        nonce: &str,
+        nc: &str,
+        cnonce: &str,
+        qop: Option<&str>,
+        ha2: &str,
+    ) -> String {
+        match qop {
+            Some(qop) => self.hash(&format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2)),
+            None => self.hash(&format!("{}:{}:{}", ha1, nonce, ha2)),
+        }
+    }
+}
+
+/// Last digest challenge seen for a device plus the nonce count already used with it.
+/// Shared across `HikvisionClient` instances so bulk jobs skip the unauthenticated probe.
+#[derive(Debug, Clone)]
+struct DigestSession {
+    challenge: DigestChallenge,
+    nonce_count: u32,
+}
+
+fn digest_sessions() -> &'static Mutex<HashMap<String, DigestSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, DigestSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Rebuilds a multipart body on demand; `reqwest::multipart::Form` cannot be cloned and
+/// may have to be sent again after a digest re-challenge.
+type MultipartFactory<'a> =
+    &'a (dyn Fn() -> Result<reqwest::multipart::Form, HikvisionError> + Send + Sync);
+
 pub struct HikvisionClient {
     device: DeviceConfig,
     client: Client,
+    /// Shared with the pinning verifier of `client`.
+    tls_pin: Arc<Mutex<TlsPinState>>,
 }
 
+include!("infrastructure/hikvision/error.rs");
+
 include!("infrastructure/hikvision/client_chunk_1.rs");
 include!("infrastructure/hikvision/client_chunk_2.rs");
 include!("infrastructure/hikvision/client_chunk_3.rs");
 include!("infrastructure/hikvision/client_chunk_4.rs");
 include!("infrastructure/hikvision/client_chunk_5.rs");
+include!("infrastructure/hikvision/client_chunk_6.rs");
+include!("infrastructure/hikvision/client_chunk_7.rs");
+include!("infrastructure/hikvision/client_chunk_8.rs");
+include!("infrastructure/hikvision/activation.rs");
+include!("infrastructure/hikvision/alert_stream.rs");
+include!("infrastructure/hikvision/http_hosts.rs");
+include!("infrastructure/hikvision/tls_pin.rs");
 
 include!("infrastructure/hikvision/helpers.rs");
@@ -0,0 +1,2 @@
+pub mod sadp;
+pub mod subnet_scan;
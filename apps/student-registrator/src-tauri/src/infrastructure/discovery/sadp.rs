@@ -0,0 +1,213 @@
+// SADP discovery (Hikvision "Search Active Devices Protocol")
+//
+// An XML `<Probe>` goes to the multicast group 239.255.255.250:37020; every Hikvision device on
+// the segment answers with a `<ProbeMatch>` describing itself. Most firmware answers to the group
+// rather than to the sender, so the socket is bound to port 37020 (shared with SADP Tool and
+// other listeners) and joins the group. Probes only leave through the default multicast
+// interface; devices behind routers need the CIDR scan instead.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+use crate::domain::entities::DiscoveredDevice;
+
+pub const SADP_MULTICAST_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(239, 255, 255, 250), 37020);
+/// UDP is lossy and some devices miss the first probe; it is repeated this often.
+const PROBE_REPEATS: u32 = 3;
+const PROBE_INTERVAL: Duration = Duration::from_millis(400);
+
+fn probe_xml(probe_id: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><Probe><Uuid>{}</Uuid><Types>inquiry</Types></Probe>",
+        probe_id
+    )
+}
+
+/// Child elements of a `<ProbeMatch>` by name; `None` for any other document.
+fn probe_match_fields(xml: &str) -> Option<HashMap<String, String>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut fields = HashMap::new();
+    loop {
+        match reader.read_event().ok()? {
+            quick_xml::events::Event::Start(tag) => {
+                let local = tag.local_name().as_ref().to_vec();
+                if path.is_empty() && local != b"ProbeMatch" {
+                    return None;
+                }
+                path.push(local);
+            }
+            quick_xml::events::Event::Text(text) if path.len() == 2 => {
+                let name = String::from_utf8_lossy(&path[1]).into_owned();
+                fields.insert(name, text.unescape().ok()?.trim().to_string());
+            }
+            quick_xml::events::Event::End(_) => {
+                path.pop();
+            }
+            quick_xml::events::Event::Eof => break,
+            _ => {}
+        }
+    }
+    (!fields.is_empty()).then_some(fields)
+}
+
+fn parse_probe_match(xml: &str) -> Option<DiscoveredDevice> {
+    let fields = probe_match_fields(xml)?;
+    let text = |name: &str| fields.get(name).filter(|v| !v.is_empty()).cloned();
+    let flag = |name: &str| text(name).map(|v| v.eq_ignore_ascii_case("true"));
+    let port = |name: &str| text(name).and_then(|v| v.parse::<u16>().ok()).filter(|p| *p != 0);
+    Some(DiscoveredDevice {
+        model: text("DeviceDescription").or_else(|| text("DeviceType")),
+        serial_number: text("DeviceSN"),
+        mac: text("MAC").map(|mac| mac.replace('-', ":").to_lowercase()),
+        ipv4_address: text("IPv4Address").filter(|ip| ip.parse::<Ipv4Addr>().is_ok())?,
+        subnet_mask: text("IPv4SubnetMask"),
+        http_port: port("HttpPort"),
+        command_port: port("CommandPort"),
+        firmware_version: text("SoftwareVersion"),
+        activated: flag("Activated"),
+        dhcp: flag("DHCP"),
+        local_device_id: None,
+    })
+}
+
+fn discovery_key(device: &DiscoveredDevice) -> String {
+    device
+        .serial_number
+        .clone()
+        .or_else(|| device.mac.clone())
+        .unwrap_or_else(|| device.ipv4_address.clone())
+}
+
+/// Socket for real discovery: port 37020 with the group joined, or any free port (unicast
+/// replies only) when 37020 cannot be shared.
+pub fn open_sadp_socket() -> Result<UdpSocket, String> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).map_err(|e| e.to_string())?;
+    socket.set_reuse_address(true).map_err(|e| e.to_string())?;
+    let group_port = SocketAddr::from((Ipv4Addr::UNSPECIFIED, SADP_MULTICAST_ADDR.port()));
+    if let Err(err) = socket.bind(&SockAddr::from(group_port)) {
+        eprintln!("SADP port {} unavailable ({}), listening for unicast replies only", group_port, err);
+        socket
+            .bind(&SockAddr::from(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))))
+            .map_err(|e| e.to_string())?;
+    } else {
+        socket
+            .join_multicast_v4(SADP_MULTICAST_ADDR.ip(), &Ipv4Addr::UNSPECIFIED)
+            .map_err(|e| e.to_string())?;
+    }
+    socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+    UdpSocket::from_std(socket.into()).map_err(|e| e.to_string())
+}
+
+/// Probe `target` from `socket` and collect answers until `timeout`, one entry per device.
+pub async fn sadp_probe(socket: &UdpSocket, target: SocketAddr, timeout: Duration) -> Result<Vec<DiscoveredDevice>, String> {
+    let probe = probe_xml(&uuid::Uuid::new_v4().to_string().to_uppercase());
+    let deadline = Instant::now() + timeout;
+    let mut next_probe = Instant::now();
+    let mut probes_sent = 0;
+    let mut found: Vec<DiscoveredDevice> = Vec::new();
+    let mut buf = vec![0u8; 8192];
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        if probes_sent < PROBE_REPEATS && now >= next_probe {
+            socket
+                .send_to(probe.as_bytes(), target)
+                .await
+                .map_err(|e| format!("SADP probe not sent: {}", e))?;
+            probes_sent += 1;
+            next_probe = now + PROBE_INTERVAL;
+        }
+        let wait_until = if probes_sent < PROBE_REPEATS { next_probe.min(deadline) } else { deadline };
+        let Ok(received) = tokio::time::timeout_at(wait_until, socket.recv_from(&mut buf)).await else {
+            continue;
+        };
+        // ICMP errors from a previous send surface here on some platforms; keep listening.
+        let Ok((len, _)) = received else {
+            continue;
+        };
+        let Some(device) = parse_probe_match(&String::from_utf8_lossy(&buf[..len])) else {
+            continue;
+        };
+        let key = discovery_key(&device);
+        match found.iter_mut().find(|d| discovery_key(d) == key) {
+            Some(existing) => *existing = device,
+            None => found.push(device),
+        }
+    }
+
+    found.sort_by_key(|d| d.ipv4_address.parse::<Ipv4Addr>().ok());
+    Ok(found)
+}
+
+/// Discover Hikvision devices on the local segment.
+pub async fn discover_sadp_devices(timeout: Duration) -> Result<Vec<DiscoveredDevice>, String> {
+    let socket = open_sadp_socket()?;
+    sadp_probe(&socket, SocketAddr::V4(SADP_MULTICAST_ADDR), timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe_match(serial: &str, ip: &str, activated: bool) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <ProbeMatch><Uuid>X</Uuid><Types>inquiry</Types><DeviceType>199681</DeviceType>\
+             <DeviceDescription>DS-K1T671MF</DeviceDescription><DeviceSN>{}</DeviceSN>\
+             <CommandPort>8000</CommandPort><HttpPort>80</HttpPort><MAC>44-47-CC-0A-0B-0C</MAC>\
+             <IPv4Address>{}</IPv4Address><IPv4SubnetMask>255.255.255.0</IPv4SubnetMask>\
+             <DHCP>false</DHCP><SoftwareVersion>V3.2.30build 220101</SoftwareVersion>\
+             <Activated>{}</Activated></ProbeMatch>",
+            serial, ip, activated
+        )
+    }
+
+    #[test]
+    fn probe_match_fields_are_parsed() {
+        let device = parse_probe_match(&probe_match("DS-K1T671MF20220101V030230ENJ12345678", "192.168.1.64", false)).unwrap();
+
+        assert_eq!(device.model.as_deref(), Some("DS-K1T671MF"));
+        assert_eq!(device.mac.as_deref(), Some("44:47:cc:0a:0b:0c"));
+        assert_eq!(device.ipv4_address, "192.168.1.64");
+        assert_eq!((device.http_port, device.command_port), (Some(80), Some(8000)));
+        assert_eq!(device.firmware_version.as_deref(), Some("V3.2.30build 220101"));
+        assert_eq!(device.activated, Some(false));
+        assert!(parse_probe_match(&probe_xml("X")).is_none());
+    }
+
+    #[tokio::test]
+    async fn fake_responder_on_loopback_is_discovered_once() {
+        let responder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let responder_addr = responder.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            while let Ok((len, from)) = responder.recv_from(&mut buf).await {
+                if !String::from_utf8_lossy(&buf[..len]).contains("<Types>inquiry</Types>") {
+                    continue;
+                }
+                // Each probe is answered, so repeats must not produce duplicates.
+                for (serial, ip) in [("SN-B", "10.0.0.20"), ("SN-A", "10.0.0.3")] {
+                    let _ = responder.send_to(probe_match(serial, ip, true).as_bytes(), from).await;
+                }
+                let _ = responder.send_to(b"not xml", from).await;
+            }
+        });
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let found = sadp_probe(&socket, responder_addr, Duration::from_millis(1000)).await.unwrap();
+
+        let serials: Vec<_> = found.iter().map(|d| d.serial_number.as_deref().unwrap()).collect();
+        assert_eq!(serials, ["SN-A", "SN-B"]);
+        assert_eq!(found[0].activated, Some(true));
+    }
+}
@@ -0,0 +1,270 @@
+// Subnet scan (fallback for networks that drop SADP multicast)
+//
+// Every address of a CIDR is probed on the usual Hikvision ports with bounded concurrency; open
+// HTTP(S) ports are then asked for `ISAPI/System/deviceInfo` without credentials. A Hikvision
+// device answers 401 with a Digest challenge whose realm or `Server` header gives it away.
+// Port 8000 (SDK command port) is not HTTP; when open it is only reported as `commandPort`.
+// Only private, loopback and link-local ranges are scanned (see the desktop agent concept doc,
+// "SSRF / scanning abuse").
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use reqwest::header::{SERVER, WWW_AUTHENTICATE};
+use reqwest::Client;
+use tokio::net::TcpStream;
+
+use crate::domain::entities::DiscoveredDevice;
+
+/// HTTP first, so a device answering on several ports is reported with its plain web port.
+pub const SCAN_PORTS: [u16; 4] = [80, 8080, 443, 8000];
+const SDK_COMMAND_PORT: u16 = 8000;
+pub const MAX_SCAN_HOSTS: usize = 1024;
+/// Prefix used for the default subnet when the interface netmask cannot be read.
+const DEFAULT_SCAN_PREFIX: u8 = 24;
+/// Narrowest default prefix: a wider interface network is cut down to the `/22` around this PC
+/// so that it stays under `MAX_SCAN_HOSTS`.
+const MIN_DEFAULT_SCAN_PREFIX: u8 = 22;
+/// Tokens of Hikvision `Server` headers (`App-webs/`, `DNVRS-Webs`, `DVRDVS-Webs`,
+/// `Hikvision-Webs`); compared whole so that e.g. `webserver` does not match.
+const SERVER_HINTS: [&str; 5] = ["webs", "hikvision", "hikweb", "dnvrs", "dvrdvs"];
+const REALM_HINTS: [&str; 6] = ["ds-", "hikvision", "ip camera", "idvr", "dvrdvs", "dnvrs"];
+
+pub fn parse_cidr(cidr: &str) -> Result<(Ipv4Addr, u8), String> {
+    let cidr = cidr.trim();
+    let (ip, prefix) = cidr.split_once('/').unwrap_or((cidr, "32"));
+    let ip: Ipv4Addr = ip.trim().parse().map_err(|_| format!("invalid IPv4 address in {}", cidr))?;
+    let prefix: u8 = prefix
+        .trim()
+        .parse()
+        .ok()
+        .filter(|p| *p <= 32)
+        .ok_or_else(|| format!("invalid prefix length in {}", cidr))?;
+    Ok((ip, prefix))
+}
+
+fn is_scannable(ip: Ipv4Addr) -> bool {
+    ip.is_private() || ip.is_loopback() || ip.is_link_local()
+}
+
+/// Host addresses of `ip/prefix` (without network and broadcast address below /31).
+pub fn cidr_hosts(ip: Ipv4Addr, prefix: u8) -> Result<Vec<Ipv4Addr>, String> {
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    let network = u32::from(ip) & mask;
+    let broadcast = network | !mask;
+    let (first, last) = if prefix >= 31 { (network, broadcast) } else { (network + 1, broadcast - 1) };
+    let count = (last - first) as usize + 1;
+    if count > MAX_SCAN_HOSTS {
+        return Err(format!("subnet has {} hosts, at most {} can be scanned", count, MAX_SCAN_HOSTS));
+    }
+    if !is_scannable(Ipv4Addr::from(network)) || !is_scannable(Ipv4Addr::from(broadcast)) {
+        return Err("only private, loopback or link-local networks can be scanned".to_string());
+    }
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+/// Address this PC uses to reach `target`. No packet is sent: connecting a UDP socket only picks
+/// the route.
+pub fn local_ipv4_towards(target: Ipv4Addr) -> Result<Ipv4Addr, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| e.to_string())?;
+    socket
+        .connect((target, 9))
+        .map_err(|e| format!("no network route: {}", e))?;
+    match socket.local_addr().map_err(|e| e.to_string())?.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => Ok(ip),
+        _ => Err("no IPv4 address on this PC".to_string()),
+    }
+}
+
+fn netmask_prefix(netmask: Ipv4Addr) -> u8 {
+    u32::from(netmask).leading_ones() as u8
+}
+
+fn scan_cidr_around(ip: Ipv4Addr, prefix: u8) -> String {
+    let prefix = prefix.clamp(MIN_DEFAULT_SCAN_PREFIX, 32);
+    let mask = u32::MAX << (32 - prefix as u32);
+    format!("{}/{}", Ipv4Addr::from(u32::from(ip) & mask), prefix)
+}
+
+/// Network of the interface this PC uses for outgoing traffic, from its netmask (`/24` when the
+/// interface is not found).
+pub fn default_scan_cidr() -> Result<String, String> {
+    let ip = local_ipv4_towards(Ipv4Addr::new(10, 255, 255, 255))?;
+    let prefix = if_addrs::get_if_addrs()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find_map(|interface| match interface.addr {
+            if_addrs::IfAddr::V4(addr) if addr.ip == ip => Some(netmask_prefix(addr.netmask)),
+            _ => None,
+        })
+        .unwrap_or(DEFAULT_SCAN_PREFIX);
+    Ok(scan_cidr_around(ip, prefix))
+}
+
+fn auth_realm(www_authenticate: &str) -> Option<String> {
+    let start = www_authenticate.to_ascii_lowercase().find("realm=\"")? + "realm=\"".len();
+    let rest = &www_authenticate[start..];
+    Some(rest[..rest.find('"')?].to_string())
+}
+
+/// Whether an unauthenticated `deviceInfo` answer came from Hikvision ISAPI, and the model
+/// when the realm carries one (older firmware uses the model, e.g. `DS-K1T671MF`).
+fn isapi_fingerprint(status: u16, server: Option<&str>, www_authenticate: Option<&str>) -> Option<Option<String>> {
+    let server_hint = server.is_some_and(|server| {
+        server
+            .to_ascii_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|token| SERVER_HINTS.contains(&token))
+    });
+    let realm = www_authenticate.and_then(auth_realm);
+    let realm_hint = realm.as_deref().is_some_and(|realm| {
+        let realm = realm.to_ascii_lowercase();
+        REALM_HINTS.iter().any(|hint| realm.contains(hint))
+    });
+    let isapi_answer = status == 401 || status == 200;
+    if !isapi_answer || !(server_hint || realm_hint) {
+        return None;
+    }
+    Some(realm.filter(|realm| realm.to_ascii_uppercase().starts_with("DS-")))
+}
+
+async fn port_open(addr: SocketAddr, timeout: Duration) -> bool {
+    matches!(tokio::time::timeout(timeout, TcpStream::connect(addr)).await, Ok(Ok(_)))
+}
+
+async fn fingerprint_port(client: &Client, ip: Ipv4Addr, port: u16) -> Option<Option<String>> {
+    let scheme = if port == 443 { "https" } else { "http" };
+    let response = client
+        .get(format!("{}://{}:{}/ISAPI/System/deviceInfo", scheme, ip, port))
+        .send()
+        .await
+        .ok()?;
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+    isapi_fingerprint(response.status().as_u16(), header(SERVER), header(WWW_AUTHENTICATE))
+}
+
+/// Probe `ports` on every host and return the hosts that fingerprint as Hikvision ISAPI.
+pub async fn scan_hosts(
+    hosts: Vec<Ipv4Addr>,
+    ports: &[u16],
+    concurrency: usize,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredDevice>, String> {
+    let client = Client::builder()
+        .timeout(timeout * 2)
+        .no_proxy()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let targets: Vec<(Ipv4Addr, u16)> = hosts
+        .iter()
+        .flat_map(|ip| ports.iter().map(move |port| (*ip, *port)))
+        .collect();
+    let open: Vec<(Ipv4Addr, u16)> = stream::iter(targets)
+        .map(|(ip, port)| async move { port_open(SocketAddr::from((ip, port)), timeout).await.then_some((ip, port)) })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|open| async move { open })
+        .collect()
+        .await;
+
+    let mut open_by_host: BTreeMap<Ipv4Addr, Vec<u16>> = BTreeMap::new();
+    for (ip, port) in open {
+        open_by_host.entry(ip).or_default().push(port);
+    }
+    let client = &client;
+    let mut found: Vec<DiscoveredDevice> = stream::iter(open_by_host)
+        .map(|(ip, open_ports)| async move {
+            for port in ports.iter().filter(|p| **p != SDK_COMMAND_PORT && open_ports.contains(p)) {
+                if let Some(model) = fingerprint_port(client, ip, *port).await {
+                    return Some(DiscoveredDevice {
+                        model,
+                        ipv4_address: ip.to_string(),
+                        http_port: Some(*port),
+                        command_port: open_ports.contains(&SDK_COMMAND_PORT).then_some(SDK_COMMAND_PORT),
+                        ..Default::default()
+                    });
+                }
+            }
+            None
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|device| async move { device })
+        .collect()
+        .await;
+    found.sort_by_key(|d| d.ipv4_address.parse::<Ipv4Addr>().ok());
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    async fn spawn_http(response: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn cidr_hosts_skip_network_and_broadcast_and_stay_private() {
+        let (ip, prefix) = parse_cidr("192.168.5.77/30").unwrap();
+        assert_eq!(cidr_hosts(ip, prefix).unwrap(), [Ipv4Addr::new(192, 168, 5, 77), Ipv4Addr::new(192, 168, 5, 78)]);
+        assert_eq!(cidr_hosts(Ipv4Addr::new(10, 0, 0, 9), 32).unwrap().len(), 1);
+        assert!(cidr_hosts(Ipv4Addr::new(10, 0, 0, 0), 16).unwrap_err().contains("at most"));
+        assert!(cidr_hosts(Ipv4Addr::new(8, 8, 8, 0), 24).is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn default_cidr_follows_the_netmask_within_the_scan_cap() {
+        let ip = Ipv4Addr::new(192, 168, 5, 77);
+        assert_eq!(netmask_prefix(Ipv4Addr::new(255, 255, 255, 128)), 25);
+        assert_eq!(scan_cidr_around(ip, 25), "192.168.5.0/25");
+        assert_eq!(scan_cidr_around(ip, 23), "192.168.4.0/23");
+        assert_eq!(scan_cidr_around(ip, 16), "192.168.4.0/22");
+        let (network, prefix) = parse_cidr(&scan_cidr_around(ip, 8)).unwrap();
+        assert!(cidr_hosts(network, prefix).is_ok());
+    }
+
+    #[test]
+    fn realm_or_server_header_identifies_isapi() {
+        let digest = "Digest qop=\"auth\", realm=\"DS-K1T671MF\", nonce=\"abc\"";
+        assert_eq!(isapi_fingerprint(401, None, Some(digest)), Some(Some("DS-K1T671MF".to_string())));
+        assert_eq!(isapi_fingerprint(401, Some("App-webs/"), Some("Digest realm=\"4c1a\"")), Some(None));
+        assert_eq!(isapi_fingerprint(401, Some("DNVRS-Webs"), None), Some(None));
+        assert_eq!(isapi_fingerprint(401, Some("webserver"), Some("Digest realm=\"4c1a\"")), None);
+        assert_eq!(isapi_fingerprint(401, Some("nginx"), Some("Basic realm=\"router\"")), None);
+        assert_eq!(isapi_fingerprint(404, Some("App-webs/"), None), None);
+    }
+
+    #[tokio::test]
+    async fn scan_reports_only_fingerprinted_hosts() {
+        let hikvision = spawn_http(
+            "HTTP/1.1 401 Unauthorized\r\nServer: App-webs/\r\n\
+             WWW-Authenticate: Digest realm=\"DS-K1T343EWX\", nonce=\"n\", qop=\"auth\"\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        let other = spawn_http("HTTP/1.1 404 Not Found\r\nServer: nginx\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+
+        let found = scan_hosts(vec![Ipv4Addr::LOCALHOST], &[other, hikvision], 8, Duration::from_millis(500))
+            .await
+            .unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].http_port, Some(hikvision));
+        assert_eq!(found[0].model.as_deref(), Some("DS-K1T343EWX"));
+        assert_eq!(found[0].command_port, None);
+    }
+}
@@ -0,0 +1,131 @@
+// Activation handshake for factory-new devices
+//
+// An inactive device only answers a few unauthenticated endpoints. To activate it, the client
+// sends an RSA public key to `ISAPI/Security/challenge` (modulus as hex, base64-encoded, e = 65537).
+// The device replies with a random key encrypted to that public key (PKCS#1 v1.5, hex, base64).
+// The new admin password is then sent to `ISAPI/System/activate`: the first 16 characters of the
+// random key followed by the password, AES-128-ECB with the random key, zero padded, as hex in
+// base64. This mirrors the device web UI.
+
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+
+const ACTIVATION_RSA_BITS: usize = 1024;
+
+/// Throwaway RSA key for one activation.
+struct ActivationKey {
+    key: RsaPrivateKey,
+}
+
+impl ActivationKey {
+    fn generate() -> Result<Self, String> {
+        RsaPrivateKey::new(&mut rand::rngs::OsRng, ACTIVATION_RSA_BITS)
+            .map(|key| Self { key })
+            .map_err(|e| format!("activation key: {}", e))
+    }
+
+    /// `<key>` of the `ISAPI/Security/challenge` request.
+    fn public_key_b64(&self) -> String {
+        STANDARD.encode(self.key.n().to_str_radix(16))
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        self.key.decrypt(Pkcs1v15Encrypt, ciphertext).ok()
+    }
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Random key from the `ISAPI/Security/challenge` answer, as the hex text the device sent.
+fn activation_challenge_key(key: &ActivationKey, challenge_xml: &str) -> Result<String, String> {
+    let encoded = xml_tag_text(challenge_xml, "key").ok_or("challenge has no <key>")?;
+    let decoded = STANDARD.decode(encoded.trim()).map_err(|e| format!("challenge key: {}", e))?;
+    let ciphertext = std::str::from_utf8(&decoded).ok().and_then(decode_hex).unwrap_or(decoded);
+    let plaintext = key.decrypt(&ciphertext).ok_or("challenge key cannot be decrypted")?;
+    let random_key = String::from_utf8(plaintext).map_err(|_| "challenge key is not text")?;
+    if random_key.get(..32).and_then(decode_hex).is_none() {
+        return Err("challenge key has an unexpected format".to_string());
+    }
+    Ok(random_key)
+}
+
+/// AES-128-ECB, zero padded to whole blocks (no extra block for an exact multiple).
+fn aes128_ecb_encrypt(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    use ecb::cipher::{block_padding::ZeroPadding, BlockEncryptMut, KeyInit};
+    ecb::Encryptor::<aes::Aes128>::new(key.into()).encrypt_padded_vec_mut::<ZeroPadding>(data)
+}
+
+/// `<password>` of the `ISAPI/System/activate` request.
+fn activation_password_b64(random_key: &str, password: &str) -> Result<String, String> {
+    let key: [u8; 16] = random_key
+        .get(..32)
+        .and_then(decode_hex)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("challenge key has an unexpected format")?;
+    let plaintext = format!("{}{}", &random_key[..16], password);
+    Ok(STANDARD.encode(encode_hex(&aes128_ecb_encrypt(&key, plaintext.as_bytes()))))
+}
+
+fn activation_challenge_xml(public_key_b64: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><PublicKey><key>{}</key></PublicKey>",
+        public_key_b64
+    )
+}
+
+fn activate_info_xml(password_b64: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ActivateInfo><password>{}</password></ActivateInfo>",
+        password_b64
+    )
+}
+
+#[cfg(test)]
+mod activation_tests {
+    use super::*;
+    use rsa::{BigUint, RsaPublicKey};
+
+    #[test]
+    fn activation_handshake_matches_device_side() {
+        use ecb::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyInit};
+
+        let key = ActivationKey::generate().unwrap();
+        // Device side: parse our public key and encrypt its random key to it (PKCS#1 v1.5).
+        let modulus_hex = String::from_utf8(STANDARD.decode(key.public_key_b64()).unwrap()).unwrap();
+        let modulus = BigUint::parse_bytes(modulus_hex.as_bytes(), 16).unwrap();
+        assert_eq!(modulus.bits(), 1024);
+        let device_key = RsaPublicKey::new(modulus, BigUint::from(65537u32)).unwrap();
+        let random_key = "00112233445566778899aabbccddeeff";
+        let encrypted = device_key
+            .encrypt(&mut rand::rngs::OsRng, Pkcs1v15Encrypt, random_key.as_bytes())
+            .unwrap();
+        let challenge = format!("<Challenge><key>{}</key></Challenge>", STANDARD.encode(encode_hex(&encrypted)));
+
+        let decrypted = activation_challenge_key(&key, &challenge).unwrap();
+        assert_eq!(decrypted, random_key);
+
+        let sealed = STANDARD.decode(activation_password_b64(&decrypted, "Abc12345!").unwrap()).unwrap();
+        let data = decode_hex(std::str::from_utf8(&sealed).unwrap()).unwrap();
+        let aes_key: [u8; 16] = decode_hex(random_key).unwrap().try_into().unwrap();
+        let data = ecb::Decryptor::<aes::Aes128>::new(&aes_key.into())
+            .decrypt_padded_vec_mut::<NoPadding>(&data)
+            .unwrap();
+        assert_eq!(data.len(), 32);
+        assert_eq!(&data[..25], b"0011223344556677Abc12345!");
+        assert!(data[25..].iter().all(|b| *b == 0));
+        assert!(activation_challenge_key(&key, "<Challenge><key>AAAA</key></Challenge>").is_err());
+    }
+}
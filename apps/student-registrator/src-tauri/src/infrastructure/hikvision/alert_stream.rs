@@ -0,0 +1,314 @@
+// Event stream (`ISAPI/Event/notification/alertStream`)
+//
+// The device keeps the response open and writes one `multipart/mixed` part per event: a JSON
+// `EventNotificationAlert`, followed by `image/jpeg` parts when the event has captures
+// (`picturesNumber`). Idle streams carry heartbeat alerts, so a silent stream means a dead
+// connection. Parts normally have `Content-Length`; without it a part ends at the next boundary.
+// Only `AccessControllerEvent` alerts are turned into events, XML alerts of older firmware and
+// every other event type are skipped.
+
+use std::collections::VecDeque;
+
+/// A part bigger than this is treated as a broken stream rather than buffered forever.
+const MAX_ALERT_PART_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MultipartPart {
+    content_type: String,
+    body: Vec<u8>,
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// `boundary` parameter of a `multipart/*` content type.
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    content_type.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        let value = value.trim().trim_matches('"');
+        (name.trim().eq_ignore_ascii_case("boundary") && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Incremental `multipart/mixed` reader: bytes go in as they arrive, whole parts come out.
+struct MultipartReader {
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl MultipartReader {
+    fn new(boundary: &str) -> Self {
+        Self {
+            delimiter: format!("--{}", boundary.trim_start_matches("--")).into_bytes(),
+            buf: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.buf.extend_from_slice(bytes);
+        if self.buf.len() > MAX_ALERT_PART_BYTES {
+            return Err(format!("alert stream part exceeds {} bytes", MAX_ALERT_PART_BYTES));
+        }
+        Ok(())
+    }
+
+    fn next_part(&mut self) -> Option<MultipartPart> {
+        let Some(start) = find_bytes(&self.buf, &self.delimiter) else {
+            // Keep a possible partial delimiter at the end, drop everything before it.
+            let keep = self.delimiter.len().saturating_sub(1).min(self.buf.len());
+            self.buf.drain(..self.buf.len() - keep);
+            return None;
+        };
+        self.buf.drain(..start);
+        let header_end = find_bytes(&self.buf, b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&self.buf[self.delimiter.len()..header_end]).into_owned();
+        let header = |name: &str| {
+            head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+            })
+        };
+        let body_start = header_end + 4;
+        let body_end = match header("Content-Length").and_then(|len| len.parse::<usize>().ok()) {
+            Some(len) if self.buf.len() >= body_start + len => body_start + len,
+            Some(_) => return None,
+            None => body_start + find_bytes(&self.buf[body_start..], &self.delimiter)?,
+        };
+        let mut body = self.buf[body_start..body_end].to_vec();
+        while body.last().is_some_and(|b| *b == b'\r' || *b == b'\n') {
+            body.pop();
+        }
+        self.buf.drain(..body_end);
+        Some(MultipartPart {
+            content_type: header("Content-Type").unwrap_or_default().to_ascii_lowercase(),
+            body,
+        })
+    }
+}
+
+/// Access event from an `AccessControllerEvent` object (alert stream) or an `AcsEvent`
+/// `InfoList` entry; the two differ only in a few key names. `None` for other records.
+fn access_event_from_record(device_id: &str, record: &Value, fallback_time: Option<&str>) -> Option<AccessEvent> {
+    let number = |keys: &[&str]| {
+        keys.iter().find_map(|key| {
+            let value = record.get(*key)?;
+            value.as_u64().or_else(|| value.as_str()?.trim().parse().ok())
+        })
+    };
+    let text = |key: &str| {
+        record
+            .get(key)
+            .and_then(|v| match v {
+                Value::String(s) => Some(s.trim().to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|s| !s.is_empty())
+    };
+    let major = number(&["majorEventType", "major"])?;
+    let minor = number(&["subEventType", "minor"])?;
+    let time = text("time").or_else(|| fallback_time.map(str::to_string))?;
+    Some(AccessEvent {
+        device_id: device_id.to_string(),
+        serial_no: number(&["serialNo"]),
+        time,
+        major: major as u32,
+        minor: minor as u32,
+        employee_no: text("employeeNoString").or_else(|| text("employeeNo")),
+        name: text("name"),
+        door_no: number(&["doorNo"]).map(|n| n as u32),
+        verify_mode: text("currentVerifyMode"),
+        picture_base64: None,
+        picture_url: text("pictureURL"),
+    })
+}
+
+/// Access event of an alert-stream JSON part and how many image parts follow it.
+fn parse_alert_json(device_id: &str, body: &[u8]) -> Option<(AccessEvent, u64)> {
+    let alert: Value = serde_json::from_slice(body).ok()?;
+    if alert.get("eventType").and_then(|v| v.as_str()) != Some("AccessControllerEvent") {
+        return None;
+    }
+    let record = alert.get("AccessControllerEvent")?;
+    let event = access_event_from_record(device_id, record, alert.get("dateTime").and_then(|v| v.as_str()))?;
+    let pictures = record.get("picturesNumber").and_then(|v| v.as_u64()).unwrap_or(0);
+    Some((event, pictures))
+}
+
+/// Turns alert-stream parts into access events, attaching the capture that follows an event.
+struct AlertEventAssembler {
+    device_id: String,
+    /// Event still waiting for its image part.
+    pending: Option<AccessEvent>,
+    ready: VecDeque<AccessEvent>,
+}
+
+impl AlertEventAssembler {
+    fn new(device_id: &str) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            pending: None,
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn add_part(&mut self, part: MultipartPart) {
+        if part.content_type.starts_with("image/") {
+            if let Some(mut event) = self.pending.take() {
+                event.picture_base64 = Some(STANDARD.encode(&part.body));
+                self.ready.push_back(event);
+            }
+            return;
+        }
+        // Any other part ends the wait: the device sent no image for the pending event.
+        self.ready.extend(self.pending.take());
+        if let Some((event, pictures)) = parse_alert_json(&self.device_id, &part.body) {
+            if pictures > 0 {
+                self.pending = Some(event);
+            } else {
+                self.ready.push_back(event);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.ready.extend(self.pending.take());
+    }
+}
+
+/// Open alert stream of one device. See `HikvisionClient::open_alert_stream`.
+pub struct AlertStream {
+    response: Response,
+    reader: MultipartReader,
+    events: AlertEventAssembler,
+    closed: bool,
+}
+
+impl AlertStream {
+    /// Next access event. `Ok(None)` when the device closed the stream; `Err(Timeout)` when
+    /// nothing (not even a heartbeat) arrived for `idle_timeout`.
+    pub async fn next_event(&mut self, idle_timeout: Duration) -> Result<Option<AccessEvent>, HikvisionError> {
+        loop {
+            if let Some(event) = self.events.ready.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.closed {
+                return Ok(None);
+            }
+            let chunk = tokio::time::timeout(idle_timeout, self.response.chunk())
+                .await
+                .map_err(|_| HikvisionError::Timeout)?
+                .map_err(HikvisionError::from_reqwest)?;
+            match chunk {
+                Some(bytes) => {
+                    self.reader.push(&bytes).map_err(HikvisionError::Parse)?;
+                    while let Some(part) = self.reader.next_part() {
+                        self.events.add_part(part);
+                    }
+                }
+                None => {
+                    self.closed = true;
+                    self.events.finish();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod alert_stream_tests {
+    use super::tests::{read_request, test_device};
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    fn access_alert(serial: u64, pictures: u32) -> String {
+        format!(
+            r#"{{"ipAddress":"10.0.0.5","dateTime":"2026-09-01T08:00:0{}+05:00","eventType":"AccessControllerEvent",
+               "AccessControllerEvent":{{"majorEventType":5,"subEventType":75,"name":"Ali","employeeNoString":"1001",
+               "doorNo":1,"currentVerifyMode":"cardOrFace","serialNo":{},"picturesNumber":{}}}}}"#,
+            serial, serial, pictures
+        )
+    }
+
+    #[test]
+    fn alert_stream_parts_survive_arbitrary_chunking() {
+        let heartbeat = r#"{"ipAddress":"10.0.0.5","eventType":"videoloss","eventState":"inactive"}"#;
+        let (first, second) = (access_alert(1, 1), access_alert(2, 0));
+        let jpeg = [0xffu8, 0xd8, 0x0d, 0x0a, 0xff, 0xd9];
+        let mut stream = Vec::new();
+        for (content_type, body, with_length) in [
+            ("application/json", heartbeat.as_bytes(), true),
+            ("application/json", first.as_bytes(), true),
+            ("image/jpeg", &jpeg[..], true),
+            ("application/json", second.as_bytes(), false),
+        ] {
+            stream.extend_from_slice(format!("--MIME_boundary\r\nContent-Type: {}\r\n", content_type).as_bytes());
+            if with_length {
+                stream.extend_from_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes());
+            }
+            stream.extend_from_slice(b"\r\n");
+            stream.extend_from_slice(body);
+            stream.extend_from_slice(b"\r\n");
+        }
+        stream.extend_from_slice(b"--MIME_boundary\r\n");
+
+        assert_eq!(
+            multipart_boundary("multipart/mixed; boundary=\"MIME_boundary\"").as_deref(),
+            Some("MIME_boundary")
+        );
+        for chunk_size in [1, 7, stream.len()] {
+            let mut reader = MultipartReader::new("MIME_boundary");
+            let mut events = AlertEventAssembler::new("dev-1");
+            for chunk in stream.chunks(chunk_size) {
+                reader.push(chunk).unwrap();
+                while let Some(part) = reader.next_part() {
+                    events.add_part(part);
+                }
+            }
+            let events: Vec<AccessEvent> = events.ready.into_iter().collect();
+            assert_eq!(events.len(), 2, "chunk size {}", chunk_size);
+            assert_eq!(events[0].serial_no, Some(1));
+            assert_eq!(events[0].employee_no.as_deref(), Some("1001"));
+            assert_eq!(events[0].time, "2026-09-01T08:00:01+05:00");
+            assert_eq!((events[0].major, events[0].minor, events[0].door_no), (5, 75, Some(1)));
+            assert_eq!(events[0].picture_base64.as_deref(), Some(STANDARD.encode(jpeg).as_str()));
+            assert_eq!(events[1].serial_no, Some(2));
+            assert_eq!(events[1].picture_base64, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn alert_stream_yields_events_then_times_out_when_idle() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_request(&mut stream).await.unwrap();
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=boundary\r\nConnection: keep-alive\r\n\r\n";
+            stream.write_all(head.as_bytes()).await.unwrap();
+            for serial in [7, 8] {
+                let body = access_alert(serial, 0);
+                let part = format!(
+                    "--boundary\r\nContent-Type: application/json; charset=\"UTF-8\"\r\nContent-Length: {}\r\n\r\n{}\r\n",
+                    body.len(),
+                    body
+                );
+                stream.write_all(part.as_bytes()).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            // Keep the connection open without sending anything.
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let client = HikvisionClient::for_event_stream(test_device(port));
+        let mut alerts = client.open_alert_stream().await.unwrap();
+        let idle = Duration::from_millis(500);
+
+        assert_eq!(alerts.next_event(idle).await.unwrap().unwrap().serial_no, Some(7));
+        let second = alerts.next_event(idle).await.unwrap().unwrap();
+        assert_eq!((second.device_id.as_str(), second.serial_no), ("test", Some(8)));
+        assert_eq!(alerts.next_event(idle).await.unwrap_err(), HikvisionError::Timeout);
+    }
+}
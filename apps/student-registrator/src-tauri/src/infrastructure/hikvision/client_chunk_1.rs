@@ -1,31 +1,52 @@
 impl HikvisionClient {
     pub fn new(device: DeviceConfig) -> Self {
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
-            .build()
-        {
+        Self::with_builder(device, Client::builder().timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS)))
+    }
+
+    fn with_builder(device: DeviceConfig, mut builder: reqwest::ClientBuilder) -> Self {
+        let tls_pin = Arc::new(Mutex::new(TlsPinState::default()));
+        if is_https_device(&device) {
+            // Devices ship self-signed certificates; trust comes from the pinned fingerprint,
+            // checked during the handshake.
+            builder = builder.use_preconfigured_tls(pinned_tls_config(device.tls_fingerprint.clone(), tls_pin.clone()));
+        }
+        let client = match builder.build() {
             Ok(client) => client,
             Err(_) => Client::new(),
         };
         Self {
             device,
             client,
+            tls_pin,
         }
     }
 
     fn base_url(&self) -> String {
-        format!("http://{}:{}", self.device.host, self.device.port)
+        let scheme = if is_https_device(&self.device) { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, self.device.host, self.device.port)
     }
 
-    fn parse_digest_challenge(header_value: &str) -> Option<DigestChallenge> {
-        let trimmed = header_value.trim();
-        let rest = trimmed.strip_prefix("Digest")?.trim();
+    /// Fingerprint of the certificate the device presented, once an HTTPS handshake was made.
+    pub fn observed_tls_fingerprint(&self) -> Option<String> {
+        self.tls_pin.lock().ok().and_then(|state| state.observed.clone())
+    }
 
-        // Split by comma, but keep quoted values intact.
+    async fn send_request(&self, req: reqwest::RequestBuilder) -> Result<Response, HikvisionError> {
+        req.send().await.map_err(|err| {
+            let rejection = self.tls_pin.lock().ok().and_then(|mut state| state.rejection.take());
+            match rejection {
+                Some(reason) => HikvisionError::Tls(reason),
+                None => HikvisionError::from_reqwest(err),
+            }
+        })
+    }
+
+    /// Split a header value by commas, keeping quoted values intact.
+    fn split_header_params(value: &str) -> Vec<String> {
         let mut parts: Vec<String> = Vec::new();
         let mut current = String::new();
         let mut in_quotes = false;
-        for ch in rest.chars() {
+        for ch in value.chars() {
             match ch {
                 '"' => {
                     in_quotes = !in_quotes;
@@ -41,26 +62,33 @@ impl HikvisionClient {
         if !current.trim().is_empty() {
             parts.push(current.trim().to_string());
         }
+        parts
+    }
 
+    fn digest_challenge_from_params(params: &[String]) -> Option<DigestChallenge> {
         let mut realm: Option<String> = None;
         let mut nonce: Option<String> = None;
         let mut qop: Option<String> = None;
         let mut opaque: Option<String> = None;
         let mut algorithm: Option<String> = None;
+        let mut stale = false;
 
-        for item in parts {
-            let (k, v) = item.split_once('=')?;
-            let key = k.trim();
+        for item in params {
+            let Some((k, v)) = item.split_once('=') else {
+                continue;
+            };
+            let key = k.trim().to_ascii_lowercase();
             let mut value = v.trim().to_string();
             if value.starts_with('"') && value.ends_with('"') && value.len() >= 2 {
                 value = value[1..value.len() - 1].to_string();
             }
-            match key {
+            match key.as_str() {
                 "realm" => realm = Some(value),
                 "nonce" => nonce = Some(value),
                 "qop" => qop = Some(value),
                 "opaque" => opaque = Some(value),
                 "algorithm" => algorithm = Some(value),
+                "stale" => stale = value.eq_ignore_ascii_case("true"),
                 _ => {}
             }
         }
@@ -71,11 +99,105 @@ impl HikvisionClient {
             qop,
             opaque,
             algorithm,
+            stale,
         })
     }
 
-    fn md5_hex(input: &str) -> String {
-        format!("{:x}", md5::compute(input))
+    /// Parse every `Digest` challenge in the given `WWW-Authenticate` values. One header may
+    /// carry several comma-separated challenges, possibly mixed with other schemes like `Basic`.
+    fn parse_digest_challenges(header_values: &[&str]) -> Vec<DigestChallenge> {
+        let mut challenges = Vec::new();
+        for header_value in header_values {
+            let mut scheme: Option<String> = None;
+            let mut params: Vec<String> = Vec::new();
+            for part in Self::split_header_params(header_value) {
+                // `Digest realm="x"` (scheme, whitespace, first param) opens a new challenge.
+                let (head, tail) = part
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((part.as_str(), ""));
+                if head.contains('=') {
+                    params.push(part.clone());
+                    continue;
+                }
+                if scheme
+                    .as_deref()
+                    .is_some_and(|s| s.eq_ignore_ascii_case("Digest"))
+                {
+                    challenges.extend(Self::digest_challenge_from_params(&params));
+                }
+                scheme = Some(head.to_string());
+                params.clear();
+                if !tail.trim().is_empty() {
+                    params.push(tail.trim().to_string());
+                }
+            }
+            if scheme
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case("Digest"))
+            {
+                challenges.extend(Self::digest_challenge_from_params(&params));
+            }
+        }
+        challenges
+    }
+
+    /// Pick the strongest challenge we can answer. Falls back to the first one so that an
+    /// unsupported algorithm still surfaces as an error instead of silently using Basic auth.
+    fn select_digest_challenge(challenges: Vec<DigestChallenge>) -> Option<DigestChallenge> {
+        let strongest = challenges
+            .iter()
+            .filter_map(|challenge| {
+                DigestAlgorithm::from_challenge(challenge)
+                    .ok()
+                    .map(|algorithm| (algorithm.strength(), challenge))
+            })
+            .max_by_key(|(strength, _)| *strength)
+            .map(|(_, challenge)| challenge.clone());
+        strongest.or_else(|| challenges.into_iter().next())
+    }
+
+    fn digest_session_key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.device.host.trim().to_lowercase(),
+            self.device.port,
+            self.device.username
+        )
+    }
+
+    /// Remember a fresh challenge for this device; the nonce count restarts from zero.
+    fn store_digest_challenge(&self, challenge: DigestChallenge) {
+        if let Ok(mut sessions) = digest_sessions().lock() {
+            sessions.insert(
+                self.digest_session_key(),
+                DigestSession {
+                    challenge,
+                    nonce_count: 0,
+                },
+            );
+        }
+    }
+
+    fn clear_digest_challenge(&self) {
+        if let Ok(mut sessions) = digest_sessions().lock() {
+            sessions.remove(&self.digest_session_key());
+        }
+    }
+
+    /// Build an Authorization header from the cached challenge, bumping `nc`.
+    /// Returns `None` when no challenge has been seen for this device yet.
+    fn cached_digest_authorization(
+        &self,
+        method: &str,
+        url: &str,
+    ) -> Option<Result<String, HikvisionError>> {
+        let (challenge, nonce_count) = {
+            let mut sessions = digest_sessions().lock().ok()?;
+            let session = sessions.get_mut(&self.digest_session_key())?;
+            session.nonce_count = session.nonce_count.wrapping_add(1);
+            (session.challenge.clone(), session.nonce_count)
+        };
+        Some(self.build_digest_authorization(method, url, &challenge, nonce_count))
     }
 
     fn build_digest_authorization(
@@ -83,8 +205,9 @@ impl HikvisionClient {
         method: &str,
         url: &str,
         challenge: &DigestChallenge,
-    ) -> Result<String, String> {
-        let parsed = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+        nonce_count: u32,
+    ) -> Result<String, HikvisionError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| HikvisionError::Request(e.to_string()))?;
         let uri = match parsed.query() {
             Some(q) => format!("{}?{}", parsed.path(), q),
             None => parsed.path().to_string(),
@@ -95,17 +218,8 @@ impl HikvisionClient {
         let realm = &challenge.realm;
         let nonce = &challenge.nonce;
 
-        let algorithm = challenge
-            .algorithm
-            .as_deref()
-            .unwrap_or("MD5")
-            .to_string();
-        if algorithm.to_uppercase() != "MD5" {
-            return Err(format!("Unsupported digest algorithm: {}", algorithm));
-        }
-
-        let ha1 = Self::md5_hex(&format!("{}:{}:{}", username, realm, password));
-        let ha2 = Self::md5_hex(&format!("{}:{}", method, uri));
+        let algorithm =
+            DigestAlgorithm::from_challenge(challenge).map_err(HikvisionError::Unauthorized)?;
 
         // Prefer qop=auth when available.
         let qop_value = challenge
@@ -117,16 +231,12 @@ impl HikvisionClient {
                     .find(|s| *s == "auth")
                     .map(|s| s.to_string())
             });
+        let nc = format!("{:08x}", nonce_count.max(1));
+        let cnonce = format!("{:x}", rand::random::<u64>());
 
-        let (response, nc, cnonce, qop) = if let Some(qop) = qop_value {
-            let nc = "00000001".to_string();
-            let cnonce = format!("{:x}", rand::random::<u64>());
-            let resp = Self::md5_hex(&format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2));
-            (resp, Some(nc), Some(cnonce), Some(qop))
-        } else {
-            let resp = Self::md5_hex(&format!("{}:{}:{}", ha1, nonce, ha2));
-            (resp, None, None, None)
-        };
+        let ha1 = algorithm.ha1(username, realm, password, nonce, &cnonce);
+        let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+        let response = algorithm.response(&ha1, nonce, &nc, &cnonce, qop_value.as_deref(), &ha2);
 
         let mut header = format!(
             "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", response=\"{}\"",
@@ -135,9 +245,11 @@ impl HikvisionClient {
         if let Some(opaque) = &challenge.opaque {
             header.push_str(&format!(", opaque=\"{}\"", opaque));
         }
-        header.push_str(", algorithm=MD5");
-        if let (Some(qop), Some(nc), Some(cnonce)) = (qop, nc, cnonce) {
+        header.push_str(&format!(", algorithm={}", algorithm.name()));
+        if let Some(qop) = qop_value {
             header.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+        } else if algorithm.is_session() {
+            header.push_str(&format!(", cnonce=\"{}\"", cnonce));
         }
         Ok(header)
     }
@@ -1,13 +1,116 @@
 impl HikvisionClient {
-    async fn response_error(res: Response) -> String {
+    async fn response_error(res: Response) -> HikvisionError {
         let status = res.status();
-        let reason = status.canonical_reason().unwrap_or("");
         let text = res.text().await.unwrap_or_default();
-        if text.trim().is_empty() {
-            format!("HTTP {}: {}", status, reason)
-        } else {
-            format!("HTTP {}: {}: {}", status, reason, text)
+        HikvisionError::from_status_body(status, &text)
+    }
+
+    fn build_request(
+        &self,
+        method: &reqwest::Method,
+        url: &str,
+        body: Option<&Vec<u8>>,
+        content_type: Option<&str>,
+        multipart: Option<MultipartFactory<'_>>,
+    ) -> Result<reqwest::RequestBuilder, HikvisionError> {
+        let mut req = self.client.request(method.clone(), url);
+        if let Some(ct) = content_type {
+            req = req.header("Content-Type", ct);
+        }
+        if let Some(b) = body {
+            req = req.body(b.clone());
+        }
+        if let Some(build_form) = multipart {
+            req = req.multipart(build_form()?);
+        }
+        Ok(req)
+    }
+
+    /// Strongest digest challenge across all `WWW-Authenticate` headers of a response.
+    fn response_digest_challenge(res: &Response) -> Option<DigestChallenge> {
+        let values: Vec<&str> = res
+            .headers()
+            .get_all(reqwest::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        Self::select_digest_challenge(Self::parse_digest_challenges(&values))
+    }
+
+    /// Cache `challenge` for this device and send the request with a digest header built from it.
+    async fn send_with_new_digest(
+        &self,
+        method: &reqwest::Method,
+        url: &str,
+        body: Option<&Vec<u8>>,
+        content_type: Option<&str>,
+        multipart: Option<MultipartFactory<'_>>,
+        challenge: DigestChallenge,
+    ) -> Result<Response, HikvisionError> {
+        self.store_digest_challenge(challenge);
+        let digest_header = self
+            .cached_digest_authorization(method.as_str(), url)
+            .unwrap_or_else(|| {
+                Err(HikvisionError::Request("digest session is unavailable".to_string()))
+            })?;
+        let req = self
+            .build_request(method, url, body, content_type, multipart)?
+            .header(reqwest::header::AUTHORIZATION, digest_header);
+        self.send_request(req).await
+    }
+
+    /// Reuse the cached digest challenge for this device. Returns `Ok(None)` when there is
+    /// no usable session and the caller has to negotiate from scratch.
+    async fn send_with_cached_digest(
+        &self,
+        method: &reqwest::Method,
+        url: &str,
+        body: Option<&Vec<u8>>,
+        content_type: Option<&str>,
+        multipart: Option<MultipartFactory<'_>>,
+    ) -> Result<Option<Response>, HikvisionError> {
+        let Some(digest_header) = self.cached_digest_authorization(method.as_str(), url) else {
+            return Ok(None);
+        };
+        let req = self
+            .build_request(method, url, body, content_type, multipart)?
+            .header(reqwest::header::AUTHORIZATION, digest_header?);
+        let res = self.send_request(req).await?;
+        if res.status().is_success() {
+            if DEBUG_HIKVISION {
+                println!("[HIKVISION][send_with_auth] cached digest ok");
+            }
+            return Ok(Some(res));
+        }
+        if res.status() != reqwest::StatusCode::UNAUTHORIZED {
+            let err = Self::response_error(res).await;
+            if DEBUG_HIKVISION {
+                println!("[HIKVISION][send_with_auth] cached digest error: {}", redact(&err.to_string()));
+            }
+            return Err(err);
+        }
+
+        // The nonce went stale (or the device rebooted): take the fresh challenge and retry once.
+        let Some(challenge) = Self::response_digest_challenge(&res) else {
+            self.clear_digest_challenge();
+            return Ok(None);
+        };
+        if DEBUG_HIKVISION {
+            println!(
+                "[HIKVISION][send_with_auth] cached digest rejected, stale={}",
+                challenge.stale
+            );
+        }
+        let retry = self
+            .send_with_new_digest(method, url, body, content_type, multipart, challenge)
+            .await?;
+        if retry.status().is_success() {
+            return Ok(Some(retry));
         }
+        if retry.status() == reqwest::StatusCode::UNAUTHORIZED {
+            self.clear_digest_challenge();
+        }
+        Err(Self::response_error(retry).await)
     }
 
     async fn send_with_auth(
@@ -16,8 +119,8 @@ impl HikvisionClient {
         url: &str,
         body: Option<Vec<u8>>,
         content_type: Option<&str>,
-        multipart: Option<reqwest::multipart::Form>,
-    ) -> Result<Response, String> {
+        multipart: Option<MultipartFactory<'_>>,
+    ) -> Result<Response, HikvisionError> {
         if DEBUG_HIKVISION {
             println!(
                 "[HIKVISION][send_with_auth] method={} url={} multipart={}",
@@ -26,16 +129,20 @@ impl HikvisionClient {
                 multipart.is_some()
             );
         }
-        // For multipart requests, we need to get digest challenge first with a simple request
-        // because Form cannot be cloned and will be consumed.
+
+        if let Some(res) = self
+            .send_with_cached_digest(&method, url, body.as_ref(), content_type, multipart)
+            .await?
+        {
+            return Ok(res);
+        }
+
+        // For multipart requests, get the digest challenge with a simple GET first
+        // so the form is only sent once we can authenticate it.
         if multipart.is_some() {
-            // First, try to get digest challenge with a simple GET to the same endpoint (no auth)
             let probe = self
-                .client
-                .request(reqwest::Method::GET, url)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
+                .send_request(self.client.request(reqwest::Method::GET, url))
+                .await?;
 
             if DEBUG_HIKVISION {
                 let www = probe
@@ -51,27 +158,12 @@ impl HikvisionClient {
             }
 
             if probe.status() == reqwest::StatusCode::UNAUTHORIZED {
-                let www = probe
-                    .headers()
-                    .get(reqwest::header::WWW_AUTHENTICATE)
-                    .and_then(|v| v.to_str().ok())
-                    .unwrap_or("");
-
-                if let Some(challenge) = Self::parse_digest_challenge(www) {
-                    let digest_header =
-                        self.build_digest_authorization(method.as_str(), url, &challenge)?;
-                    let mut req = self.client.request(method.clone(), url);
-                    req = req.header(reqwest::header::AUTHORIZATION, digest_header);
-                    if let Some(form) = multipart {
-                        req = req.multipart(form);
-                    }
-                    let res = req.send().await.map_err(|e| e.to_string())?;
+                if let Some(challenge) = Self::response_digest_challenge(&probe) {
+                    let res = self
+                        .send_with_new_digest(&method, url, None, None, multipart, challenge)
+                        .await?;
                     if !res.status().is_success() {
-                        let err = format!(
-                            "HTTP {}: {}",
-                            res.status(),
-                            res.status().canonical_reason().unwrap_or("")
-                        );
+                        let err = Self::response_error(res).await;
                         if DEBUG_HIKVISION {
                             println!("[HIKVISION][send_with_auth] multipart digest failed: {}", err);
                         }
@@ -82,12 +174,10 @@ impl HikvisionClient {
             }
 
             // Fallback to basic auth for multipart
-            let mut req = self.client.request(method, url);
-            req = req.basic_auth(&self.device.username, Some(&self.device.password));
-            if let Some(form) = multipart {
-                req = req.multipart(form);
-            }
-            let res = req.send().await.map_err(|e| e.to_string())?;
+            let req = self
+                .build_request(&method, url, None, None, multipart)?
+                .basic_auth(&self.device.username, Some(&self.device.password));
+            let res = self.send_request(req).await?;
             if res.status().is_success() {
                 if DEBUG_HIKVISION {
                     println!("[HIKVISION][send_with_auth] multipart basic ok");
@@ -101,21 +191,17 @@ impl HikvisionClient {
                     .get(reqwest::header::WWW_AUTHENTICATE)
                     .and_then(|v| v.to_str().ok())
                     .unwrap_or("");
-                let err = format!(
-                    "Unauthorized (no digest challenge). WWW-Authenticate: {}",
+                let err = HikvisionError::Unauthorized(format!(
+                    "no digest challenge. WWW-Authenticate: {}",
                     www
-                );
+                ));
                 if DEBUG_HIKVISION {
-                    println!("[HIKVISION][send_with_auth] multipart basic 401: {}", redact(&err));
+                    println!("[HIKVISION][send_with_auth] multipart basic 401: {}", redact(&err.to_string()));
                 }
                 return Err(err);
             }
 
-            let err = format!(
-                "HTTP {}: {}",
-                res.status(),
-                res.status().canonical_reason().unwrap_or("")
-            );
+            let err = Self::response_error(res).await;
             if DEBUG_HIKVISION {
                 println!("[HIKVISION][send_with_auth] multipart basic failed: {}", err);
             }
@@ -123,15 +209,9 @@ impl HikvisionClient {
         }
 
         // Non-multipart requests - try unauthenticated first to get digest challenge
-        let mut req = self.client.request(method.clone(), url);
-        if let Some(ct) = content_type {
-            req = req.header("Content-Type", ct);
-        }
-        if let Some(b) = body.as_ref() {
-            req = req.body(b.clone());
-        }
-
-        let first = req.send().await.map_err(|e| e.to_string())?;
+        let first = self
+            .send_request(self.build_request(&method, url, body.as_ref(), content_type, None)?)
+            .await?;
         if first.status().is_success() {
             if DEBUG_HIKVISION {
                 println!("[HIKVISION][send_with_auth] unauth success status={}", first.status());
@@ -140,13 +220,12 @@ impl HikvisionClient {
         }
 
         let status = first.status();
-        let www = first
-            .headers()
-            .get(reqwest::header::WWW_AUTHENTICATE)
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
-
         if DEBUG_HIKVISION {
+            let www = first
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
             println!(
                 "[HIKVISION][send_with_auth] unauth status={} www_auth={}",
                 status,
@@ -163,27 +242,22 @@ impl HikvisionClient {
         ) {
             let err = Self::response_error(first).await;
             if DEBUG_HIKVISION {
-                println!("[HIKVISION][send_with_auth] unauth error: {}", redact(&err));
+                println!("[HIKVISION][send_with_auth] unauth error: {}", redact(&err.to_string()));
             }
             return Err(err);
         }
 
-        if let Some(challenge) = Self::parse_digest_challenge(www) {
-            let digest_header = self.build_digest_authorization(method.as_str(), url, &challenge)?;
-            let mut req2 = self.client.request(method.clone(), url);
-            req2 = req2.header(reqwest::header::AUTHORIZATION, digest_header);
-            if let Some(ct) = content_type {
-                req2 = req2.header("Content-Type", ct);
-            }
-            if let Some(b) = body.as_ref() {
-                req2 = req2.body(b.clone());
-            }
-
-            let second = req2.send().await.map_err(|e| e.to_string())?;
+        if let Some(challenge) = Self::response_digest_challenge(&first) {
+            let second = self
+                .send_with_new_digest(&method, url, body.as_ref(), content_type, None, challenge)
+                .await?;
             if !second.status().is_success() {
+                if second.status() == reqwest::StatusCode::UNAUTHORIZED {
+                    self.clear_digest_challenge();
+                }
                 let err = Self::response_error(second).await;
                 if DEBUG_HIKVISION {
-                    println!("[HIKVISION][send_with_auth] digest error: {}", redact(&err));
+                    println!("[HIKVISION][send_with_auth] digest error: {}", redact(&err.to_string()));
                 }
                 return Err(err);
             }
@@ -194,16 +268,10 @@ impl HikvisionClient {
         }
 
         // If no digest challenge, try basic auth as fallback
-        let mut req2 = self.client.request(method.clone(), url);
-        req2 = req2.basic_auth(&self.device.username, Some(&self.device.password));
-        if let Some(ct) = content_type {
-            req2 = req2.header("Content-Type", ct);
-        }
-        if let Some(b) = body.as_ref() {
-            req2 = req2.body(b.clone());
-        }
-
-        let second = req2.send().await.map_err(|e| e.to_string())?;
+        let req = self
+            .build_request(&method, url, body.as_ref(), content_type, None)?
+            .basic_auth(&self.device.username, Some(&self.device.password));
+        let second = self.send_request(req).await?;
         if second.status().is_success() {
             if DEBUG_HIKVISION {
                 println!("[HIKVISION][send_with_auth] basic ok");
@@ -215,31 +283,28 @@ impl HikvisionClient {
             .headers()
             .get(reqwest::header::WWW_AUTHENTICATE)
             .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
+            .unwrap_or("")
+            .to_string();
 
         if DEBUG_HIKVISION {
             println!(
                 "[HIKVISION][send_with_auth] basic status={} www_auth={}",
                 second.status(),
-                redact(www2)
+                redact(&www2)
             );
         }
 
-        if let Some(challenge) = Self::parse_digest_challenge(www2) {
-            let digest_header = self.build_digest_authorization(method.as_str(), url, &challenge)?;
-            let mut req3 = self.client.request(method, url);
-            req3 = req3.header(reqwest::header::AUTHORIZATION, digest_header);
-            if let Some(ct) = content_type {
-                req3 = req3.header("Content-Type", ct);
-            }
-            if let Some(b) = body.as_ref() {
-                req3 = req3.body(b.clone());
-            }
-            let third = req3.send().await.map_err(|e| e.to_string())?;
+        if let Some(challenge) = Self::response_digest_challenge(&second) {
+            let third = self
+                .send_with_new_digest(&method, url, body.as_ref(), content_type, None, challenge)
+                .await?;
             if !third.status().is_success() {
+                if third.status() == reqwest::StatusCode::UNAUTHORIZED {
+                    self.clear_digest_challenge();
+                }
                 let err = Self::response_error(third).await;
                 if DEBUG_HIKVISION {
-                    println!("[HIKVISION][send_with_auth] basic->digest error: {}", redact(&err));
+                    println!("[HIKVISION][send_with_auth] basic->digest error: {}", redact(&err.to_string()));
                 }
                 return Err(err);
             }
@@ -250,19 +315,19 @@ impl HikvisionClient {
         }
 
         if second.status() == reqwest::StatusCode::UNAUTHORIZED {
-            let err = format!(
-                "Unauthorized (no digest challenge). WWW-Authenticate: {}",
+            let err = HikvisionError::Unauthorized(format!(
+                "no digest challenge. WWW-Authenticate: {}",
                 www2
-            );
+            ));
             if DEBUG_HIKVISION {
-                println!("[HIKVISION][send_with_auth] basic 401: {}", redact(&err));
+                println!("[HIKVISION][send_with_auth] basic 401: {}", redact(&err.to_string()));
             }
             return Err(err);
         }
 
         let err = Self::response_error(second).await;
         if DEBUG_HIKVISION {
-            println!("[HIKVISION][send_with_auth] basic error: {}", redact(&err));
+            println!("[HIKVISION][send_with_auth] basic error: {}", redact(&err.to_string()));
         }
         Err(err)
     }
@@ -273,7 +338,7 @@ impl HikvisionClient {
         method: reqwest::Method,
         url: &str,
         body: Option<Value>,
-    ) -> Result<String, String> {
+    ) -> Result<String, HikvisionError> {
         let body_string = body.map(|b| b.to_string());
         let response = self
             .send_with_auth(
@@ -284,7 +349,7 @@ impl HikvisionClient {
                 None,
             )
             .await?;
-        response.text().await.map_err(|e| e.to_string())
+        response.text().await.map_err(HikvisionError::from_reqwest)
     }
 }
 
@@ -8,6 +8,7 @@ impl HikvisionClient {
         {
             Ok(text) => {
                 let device_id = extract_device_id(&text);
+                let serial_number = extract_serial_number(&text);
                 if DEBUG_HIKVISION {
                     let preview: String = text.chars().take(400).collect();
                     println!(
@@ -19,6 +20,8 @@ impl HikvisionClient {
                     ok: true,
                     message: None,
                     device_id,
+                    serial_number,
+                    tls_fingerprint: self.observed_tls_fingerprint(),
                 }
             }
             Err(e) => {
@@ -30,8 +33,10 @@ impl HikvisionClient {
                 }
                 DeviceConnectionResult {
                     ok: false,
-                    message: Some(e),
+                    message: Some(e.to_string()),
                     device_id: None,
+                    serial_number: None,
+                    tls_fingerprint: None,
                 }
             }
         }
@@ -72,12 +77,7 @@ impl HikvisionClient {
             .await
         {
             Ok(text) => parse_action_result(&text),
-            Err(e) => DeviceActionResult {
-                ok: false,
-                status_code: None,
-                status_string: Some("RequestFailed".to_string()),
-                error_msg: Some(e),
-            },
+            Err(e) => e.into_action_result("RequestFailed"),
         }
     }
 
@@ -102,46 +102,45 @@ impl HikvisionClient {
         let image_bytes = match STANDARD.decode(image_base64) {
             Ok(bytes) => bytes,
             Err(e) => {
-                return DeviceActionResult {
-                    ok: false,
-                    status_code: None,
-                    status_string: Some("InvalidImage".to_string()),
-                    error_msg: Some(e.to_string()),
-                };
+                return HikvisionError::Parse(format!("invalid face image base64: {}", e))
+                    .into_action_result("InvalidImage");
             }
         };
 
-        if image_bytes.len() > MAX_FACE_IMAGE_BYTES {
-            return DeviceActionResult {
-                ok: false,
-                status_code: None,
-                status_string: Some("ImageTooLarge".to_string()),
-                error_msg: Some(format!(
-                    "Face image too large: {} bytes (max {} bytes)",
-                    image_bytes.len(),
-                    MAX_FACE_IMAGE_BYTES
-                )),
-            };
-        }
-
-        // Build multipart form
-        let face_image_part = match reqwest::multipart::Part::bytes(image_bytes)
-            .file_name("face.jpg")
-            .mime_str("image/jpeg")
-        {
-            Ok(part) => part,
+        // Phones hand us PNG/WebP, rotated or multi-megabyte photos; the device wants a small JPEG.
+        let image_bytes = match prepare_face_image(&image_bytes, MAX_FACE_IMAGE_BYTES) {
+            Ok(face) => {
+                if DEBUG_HIKVISION {
+                    println!(
+                        "[HIKVISION][upload_face] face image {}x{} quality={:?} bytes={}",
+                        face.width,
+                        face.height,
+                        face.quality,
+                        face.bytes.len()
+                    );
+                }
+                face.bytes
+            }
+            Err(FaceImageError::TooLarge { size, max }) => {
+                return HikvisionError::PayloadTooLarge { size, max }.into_action_result("ImageTooLarge");
+            }
             Err(e) => {
-                return DeviceActionResult {
-                    ok: false,
-                    status_code: None,
-                    status_string: Some("UploadFailed".to_string()),
-                    error_msg: Some(e.to_string()),
-                };
+                return HikvisionError::Parse(format!("invalid face image: {}", e))
+                    .into_action_result("InvalidImage");
             }
         };
-        let form = reqwest::multipart::Form::new()
-            .text("FaceDataRecord", face_record.to_string())
-            .part("FaceImage", face_image_part);
+
+        // Build multipart form (rebuilt if the device asks for a new digest nonce)
+        let face_record = face_record.to_string();
+        let build_form = || -> Result<reqwest::multipart::Form, HikvisionError> {
+            let face_image_part = reqwest::multipart::Part::bytes(image_bytes.clone())
+                .file_name("face.jpg")
+                .mime_str("image/jpeg")
+                .map_err(HikvisionError::from_reqwest)?;
+            Ok(reqwest::multipart::Form::new()
+                .text("FaceDataRecord", face_record.clone())
+                .part("FaceImage", face_image_part))
+        };
 
         match self
             .send_with_auth(
@@ -149,17 +148,12 @@ impl HikvisionClient {
                 &url,
                 None,
                 None,
-                Some(form),
+                Some(&build_form),
             )
             .await
         {
             Ok(res) => parse_action_result(&res.text().await.unwrap_or_default()),
-            Err(e) => DeviceActionResult {
-                ok: false,
-                status_code: None,
-                status_string: Some("UploadFailed".to_string()),
-                error_msg: Some(e),
-            },
+            Err(e) => e.into_action_result("UploadFailed"),
         }
     }
 }
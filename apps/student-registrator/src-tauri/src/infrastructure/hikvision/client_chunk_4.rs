@@ -57,40 +57,35 @@ impl HikvisionClient {
             .await
         {
             Ok(text) => parse_action_result(&text),
-            Err(e) => DeviceActionResult {
-                ok: false,
-                status_code: None,
-                status_string: Some("DeleteFailed".to_string()),
-                error_msg: Some(e),
-            },
+            Err(e) => e.into_action_result("DeleteFailed"),
         }
     }
 
-    pub async fn get_isapi_json(&self, path: &str) -> Result<Value, String> {
+    pub async fn get_isapi_json(&self, path: &str) -> Result<Value, HikvisionError> {
         let clean = path.trim().trim_start_matches('/');
         let url = format!("{}/{}{}", self.base_url(), clean, if clean.contains('?') { "" } else { "?format=json" });
         let text = self
             .auth_request_json(reqwest::Method::GET, &url, None)
             .await?;
-        serde_json::from_str::<Value>(&text).map_err(|e| e.to_string())
+        serde_json::from_str::<Value>(&text).map_err(|e| HikvisionError::Parse(e.to_string()))
     }
 
-    pub async fn put_isapi_json(&self, path: &str, payload: Value) -> Result<Value, String> {
+    pub async fn put_isapi_json(&self, path: &str, payload: Value) -> Result<Value, HikvisionError> {
         let clean = path.trim().trim_start_matches('/');
         let url = format!("{}/{}{}", self.base_url(), clean, if clean.contains('?') { "" } else { "?format=json" });
         let text = self
             .auth_request_json(reqwest::Method::PUT, &url, Some(payload))
             .await?;
-        serde_json::from_str::<Value>(&text).map_err(|e| e.to_string())
+        serde_json::from_str::<Value>(&text).map_err(|e| HikvisionError::Parse(e.to_string()))
     }
 
-    pub async fn get_isapi_raw(&self, path: &str) -> Result<String, String> {
+    pub async fn get_isapi_raw(&self, path: &str) -> Result<String, HikvisionError> {
         let clean = path.trim().trim_start_matches('/');
         let url = format!("{}/{}", self.base_url(), clean);
         let res = self
             .send_with_auth(reqwest::Method::GET, &url, None, None, None)
             .await?;
-        res.text().await.map_err(|e| e.to_string())
+        res.text().await.map_err(HikvisionError::from_reqwest)
     }
 
     pub async fn put_isapi_raw(
@@ -98,7 +93,7 @@ impl HikvisionClient {
         path: &str,
         payload: String,
         content_type: Option<&str>,
-    ) -> Result<String, String> {
+    ) -> Result<String, HikvisionError> {
         let clean = path.trim().trim_start_matches('/');
         let url = format!("{}/{}", self.base_url(), clean);
         let res = self
@@ -110,7 +105,7 @@ impl HikvisionClient {
                 None,
             )
             .await?;
-        res.text().await.map_err(|e| e.to_string())
+        res.text().await.map_err(HikvisionError::from_reqwest)
     }
 }
 
@@ -22,7 +22,7 @@ impl HikvisionClient {
                     supported.insert(key.to_string(), Value::Bool(false));
                     details.insert(
                         format!("{}_error", key),
-                        Value::String(err),
+                        Value::String(err.to_string()),
                     );
                 }
             }
@@ -35,9 +35,20 @@ impl HikvisionClient {
     }
 
     /// Fetch face image from device to reuse it
-    pub async fn fetch_face_image(&self, face_url: &str) -> Result<Vec<u8>, String> {
+    pub async fn fetch_face_image(&self, face_url: &str) -> Result<Vec<u8>, HikvisionError> {
         let full_url = if face_url.starts_with("http") {
-            face_url.to_string()
+            match reqwest::Url::parse(face_url) {
+                // Devices report absolute http:// face URLs; keep HTTPS devices on the pinned channel.
+                Ok(parsed) if is_https_device(&self.device) && parsed.scheme() == "http" => {
+                    let mut path = parsed.path().to_string();
+                    if let Some(query) = parsed.query() {
+                        path.push('?');
+                        path.push_str(query);
+                    }
+                    format!("{}{}", self.base_url(), path)
+                }
+                _ => face_url.to_string(),
+            }
         } else {
             format!("{}/{}", self.base_url(), face_url.trim_start_matches('/'))
         };
@@ -46,7 +57,7 @@ impl HikvisionClient {
             .send_with_auth(reqwest::Method::GET, &full_url, None, None, None)
             .await?;
 
-        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        let bytes = response.bytes().await.map_err(HikvisionError::from_reqwest)?;
         Ok(bytes.to_vec())
     }
 }
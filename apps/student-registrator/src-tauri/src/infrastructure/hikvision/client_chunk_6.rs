@@ -0,0 +1,27 @@
+impl HikvisionClient {
+    /// Id of the device account this client logs in with (`ISAPI/Security/users`).
+    pub async fn find_security_user_id(&self) -> Result<String, HikvisionError> {
+        let xml = self.get_isapi_raw("ISAPI/Security/users").await?;
+        parse_security_user_id(&xml, &self.device.username).ok_or_else(|| {
+            HikvisionError::Parse(format!("user {} not found in Security/users", self.device.username))
+        })
+    }
+
+    /// Set a new password for the login account. The device may close sessions right after,
+    /// so verify with a fresh client built with the new password.
+    pub async fn change_login_password(&self, user_id: &str, new_password: &str) -> Result<(), HikvisionError> {
+        let body = security_user_xml(user_id, &self.device.username, new_password, &self.device.password);
+        let text = self
+            .put_isapi_raw(
+                &format!("ISAPI/Security/users/{}", user_id),
+                body,
+                Some("application/xml"),
+            )
+            .await?;
+        // Some firmware answers 200 with a failing ResponseStatus.
+        match HikvisionError::parse_response_status(Some(200), &text) {
+            Some(HikvisionError::DeviceStatus { status_code: Some(1), .. }) | None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+impl HikvisionClient {
+    /// Whether the device is activated, from the unauthenticated `SDK/activateStatus`.
+    /// `None` when the firmware does not answer it.
+    pub async fn activation_status(&self) -> Result<Option<bool>, HikvisionError> {
+        let url = format!("{}/SDK/activateStatus", self.base_url());
+        let req = self.build_request(&reqwest::Method::GET, &url, None, None, None)?;
+        let res = self.send_request(req).await?;
+        if !res.status().is_success() {
+            return Ok(None);
+        }
+        let text = res.text().await.map_err(HikvisionError::from_reqwest)?;
+        Ok(xml_tag_text(&text, "Activated").map(|value| value.eq_ignore_ascii_case("true")))
+    }
+
+    /// Run the activation handshake, setting `password` for the `admin` account. Needs no
+    /// credentials; a device that is already active rejects it.
+    pub async fn activate(&self, password: &str) -> Result<(), HikvisionError> {
+        // Prime search takes a moment in debug builds; keep it off the runtime threads.
+        let key = tokio::task::spawn_blocking(ActivationKey::generate)
+            .await
+            .map_err(|e| HikvisionError::Request(e.to_string()))?
+            .map_err(HikvisionError::Request)?;
+
+        let url = format!("{}/ISAPI/Security/challenge", self.base_url());
+        let body = activation_challenge_xml(&key.public_key_b64()).into_bytes();
+        let req = self.build_request(&reqwest::Method::POST, &url, Some(&body), Some("application/xml"), None)?;
+        let res = self.send_request(req).await?;
+        if !res.status().is_success() {
+            return Err(Self::response_error(res).await);
+        }
+        let challenge = res.text().await.map_err(HikvisionError::from_reqwest)?;
+        let random_key = activation_challenge_key(&key, &challenge).map_err(HikvisionError::Parse)?;
+
+        let url = format!("{}/ISAPI/System/activate", self.base_url());
+        let password_b64 = activation_password_b64(&random_key, password).map_err(HikvisionError::Parse)?;
+        let body = activate_info_xml(&password_b64).into_bytes();
+        let req = self.build_request(&reqwest::Method::PUT, &url, Some(&body), Some("application/xml"), None)?;
+        let res = self.send_request(req).await?;
+        let status = res.status();
+        let text = res.text().await.map_err(HikvisionError::from_reqwest)?;
+        if !status.is_success() {
+            return Err(HikvisionError::from_status_body(status, &text));
+        }
+        match HikvisionError::parse_response_status(Some(status.as_u16()), &text) {
+            Some(HikvisionError::DeviceStatus { status_code: Some(1), .. }) | None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+impl HikvisionClient {
+    /// Client for responses that stay open: same device settings, but only the connect step is
+    /// bounded. Stalls have to be caught by the reader (see `AlertStream::next_event`).
+    pub fn for_event_stream(device: DeviceConfig) -> Self {
+        Self::with_builder(device, Client::builder().connect_timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS)))
+    }
+
+    /// Subscribe to `ISAPI/Event/notification/alertStream`. Use a client from
+    /// `for_event_stream`; with `new` the request timeout ends the stream after a few seconds.
+    pub async fn open_alert_stream(&self) -> Result<AlertStream, HikvisionError> {
+        let url = format!("{}/ISAPI/Event/notification/alertStream", self.base_url());
+        let response = tokio::time::timeout(
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            self.send_with_auth(reqwest::Method::GET, &url, None, None, None),
+        )
+        .await
+        .map_err(|_| HikvisionError::Timeout)??;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let boundary = multipart_boundary(&content_type)
+            .ok_or_else(|| HikvisionError::Parse(format!("alert stream is not multipart: {}", content_type)))?;
+        Ok(AlertStream {
+            response,
+            reader: MultipartReader::new(&boundary),
+            events: AlertEventAssembler::new(&self.device.id),
+            closed: false,
+        })
+    }
+
+    /// One page of `ISAPI/AccessControl/AcsEvent`, oldest first. Later pages of the same search
+    /// reuse `search_id` and start at the previous `next_position`.
+    pub async fn search_access_events(
+        &self,
+        filter: &AccessEventFilter,
+        search_id: &str,
+        position: u32,
+        max_results: u32,
+    ) -> Result<AccessEventPage, HikvisionError> {
+        let url = format!("{}/ISAPI/AccessControl/AcsEvent?format=json", self.base_url());
+        let payload = json!({
+            "AcsEventCond": {
+                "searchID": search_id,
+                "searchResultPosition": position,
+                "maxResults": max_results,
+                "major": filter.major,
+                "minor": filter.minor,
+                "startTime": filter.start_time,
+                "endTime": filter.end_time,
+                "timeReverseOrder": false,
+            }
+        });
+        let text = self.auth_request_json(reqwest::Method::POST, &url, Some(payload)).await?;
+        let value = serde_json::from_str::<Value>(&text).map_err(|e| HikvisionError::Parse(e.to_string()))?;
+        parse_acs_event_page(&self.device.id, &value, position)
+    }
+}
+
+/// `AcsEvent` search answer; `position` is the `searchResultPosition` that was asked for.
+fn parse_acs_event_page(device_id: &str, value: &Value, position: u32) -> Result<AccessEventPage, HikvisionError> {
+    let result = value
+        .get("AcsEvent")
+        .ok_or_else(|| HikvisionError::Parse("response has no AcsEvent".to_string()))?;
+    let events: Vec<AccessEvent> = result
+        .get("InfoList")
+        .and_then(|list| list.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|record| access_event_from_record(device_id, record, None))
+                .collect()
+        })
+        .unwrap_or_default();
+    let returned = result
+        .get("numOfMatches")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(events.len() as u64) as u32;
+    let more = result.get("responseStatusStrg").and_then(|v| v.as_str()) == Some("MORE");
+    Ok(AccessEventPage {
+        total_matches: result.get("totalMatches").and_then(|v| v.as_u64()).unwrap_or(events.len() as u64),
+        next_position: (more && returned > 0).then_some(position + returned),
+        events,
+    })
+}
+
+#[cfg(test)]
+mod acs_event_tests {
+    use super::*;
+
+    #[test]
+    fn acs_event_pages_report_the_next_position() {
+        let page = json!({"AcsEvent": {
+            "searchID": "s", "responseStatusStrg": "MORE", "numOfMatches": 2, "totalMatches": 5,
+            "InfoList": [
+                {"major": 5, "minor": 75, "time": "2026-09-01T08:00:00+05:00", "employeeNoString": "1001",
+                 "name": "Ali", "doorNo": 1, "serialNo": 40, "currentVerifyMode": "face",
+                 "pictureURL": "http://10.0.0.5/LOCALS/pic/acsLinkCap/1.jpg@WEB000000000001"},
+                {"major": 5, "minor": 76, "time": "2026-09-01T08:00:05+05:00", "serialNo": 41}
+            ]
+        }});
+
+        let parsed = parse_acs_event_page("dev-1", &page, 30).unwrap();
+
+        assert_eq!((parsed.total_matches, parsed.next_position), (5, Some(32)));
+        assert_eq!(parsed.events.len(), 2);
+        assert_eq!(parsed.events[0].employee_no.as_deref(), Some("1001"));
+        assert!(parsed.events[0].picture_url.as_deref().unwrap().contains("acsLinkCap"));
+        assert_eq!((parsed.events[1].minor, parsed.events[1].employee_no.as_deref()), (76, None));
+        let last = json!({"AcsEvent": {"responseStatusStrg": "OK", "numOfMatches": 1, "totalMatches": 5, "InfoList": []}});
+        assert_eq!(parse_acs_event_page("dev-1", &last, 32).unwrap().next_position, None);
+        assert!(parse_acs_event_page("dev-1", &json!({}), 0).is_err());
+    }
+}
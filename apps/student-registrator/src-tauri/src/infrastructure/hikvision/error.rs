@@ -0,0 +1,235 @@
+/// Failure of an ISAPI call, classified so callers can branch on the cause instead of
+/// matching on message text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HikvisionError {
+    /// Credentials were rejected (HTTP 401 after authenticating).
+    Unauthorized(String),
+    Timeout,
+    /// The device could not be reached (refused, unreachable host, DNS failure).
+    ConnectionRefused(String),
+    /// The device answered with an ISAPI `ResponseStatus` describing the failure.
+    DeviceStatus {
+        http_status: Option<u16>,
+        status_code: Option<i32>,
+        status_string: Option<String>,
+        sub_status_code: Option<String>,
+        error_msg: Option<String>,
+    },
+    /// Non-2xx response without a parseable `ResponseStatus`.
+    Http { status: u16, body: String },
+    /// The response body was not what the endpoint should return.
+    Parse(String),
+    PayloadTooLarge { size: usize, max: usize },
+    /// TLS setup or certificate pinning failed.
+    Tls(String),
+    /// Any other transport or request-building failure.
+    Request(String),
+}
+
+/// `subStatusCode` values meaning the record is already on the device.
+const ALREADY_EXISTS_SUB_STATUS: [&str; 5] = [
+    "employeeNoAlreadyExist",
+    "deviceUserAlreadyExist",
+    "deviceUserAlreadyExistFace",
+    "faceAlreadyExist",
+    "cardNoAlreadyExist",
+];
+
+pub fn is_already_exists_status(sub_status_code: Option<&str>) -> bool {
+    sub_status_code.is_some_and(|code| {
+        ALREADY_EXISTS_SUB_STATUS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(code.trim()))
+    })
+}
+
+impl HikvisionError {
+    /// Classify a non-2xx response from its status and body (JSON or XML `ResponseStatus`).
+    fn from_status_body(status: reqwest::StatusCode, body: &str) -> Self {
+        let device_status = Self::parse_response_status(Some(status.as_u16()), body);
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            let detail = match &device_status {
+                Some(Self::DeviceStatus { error_msg: Some(msg), .. }) => msg.clone(),
+                _ => body.trim().to_string(),
+            };
+            return Self::Unauthorized(detail);
+        }
+        if let Some(device_status) = device_status {
+            return device_status;
+        }
+        match status {
+            reqwest::StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge {
+                size: 0,
+                max: MAX_FACE_IMAGE_BYTES,
+            },
+            _ => Self::Http {
+                status: status.as_u16(),
+                body: body.trim().to_string(),
+            },
+        }
+    }
+
+    fn parse_response_status(http_status: Option<u16>, body: &str) -> Option<Self> {
+        let trimmed = body.trim();
+        let (status_code, status_string, sub_status_code, error_msg) =
+            if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+                let status = value.get("ResponseStatus").unwrap_or(&value);
+                let text = |key: &str| status.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+                (
+                    status
+                        .get("statusCode")
+                        .and_then(|v| v.as_i64().or_else(|| v.as_str()?.parse().ok()))
+                        .map(|v| v as i32),
+                    text("statusString"),
+                    text("subStatusCode"),
+                    text("errorMsg"),
+                )
+            } else if trimmed.contains("<ResponseStatus") {
+                (
+                    xml_tag_text(trimmed, "statusCode").and_then(|v| v.parse().ok()),
+                    xml_tag_text(trimmed, "statusString"),
+                    xml_tag_text(trimmed, "subStatusCode"),
+                    xml_tag_text(trimmed, "errorMsg"),
+                )
+            } else {
+                return None;
+            };
+        if status_code.is_none() && sub_status_code.is_none() {
+            return None;
+        }
+        Some(Self::DeviceStatus {
+            http_status,
+            status_code,
+            status_string,
+            sub_status_code,
+            error_msg,
+        })
+    }
+
+    fn from_reqwest(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::ConnectionRefused(err.to_string())
+        } else if err.is_decode() || err.is_body() {
+            Self::Parse(err.to_string())
+        } else {
+            Self::Request(err.to_string())
+        }
+    }
+
+    pub fn sub_status_code(&self) -> Option<&str> {
+        match self {
+            Self::DeviceStatus { sub_status_code, .. } => sub_status_code.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_already_exists(&self) -> bool {
+        is_already_exists_status(self.sub_status_code())
+    }
+
+    /// Fold a failed call into the `DeviceActionResult` shape returned to the UI.
+    fn into_action_result(self, status_string: &str) -> DeviceActionResult {
+        match &self {
+            Self::DeviceStatus {
+                status_code,
+                status_string: device_status,
+                sub_status_code,
+                ..
+            } => DeviceActionResult {
+                ok: false,
+                status_code: *status_code,
+                status_string: device_status.clone().or_else(|| Some(status_string.to_string())),
+                sub_status_code: sub_status_code.clone(),
+                error_msg: Some(self.to_string()),
+            },
+            Self::PayloadTooLarge { .. } => DeviceActionResult {
+                ok: false,
+                status_code: None,
+                status_string: Some("ImageTooLarge".to_string()),
+                sub_status_code: None,
+                error_msg: Some(self.to_string()),
+            },
+            _ => DeviceActionResult {
+                ok: false,
+                status_code: None,
+                status_string: Some(status_string.to_string()),
+                sub_status_code: None,
+                error_msg: Some(self.to_string()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for HikvisionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unauthorized(detail) if detail.is_empty() => write!(f, "Unauthorized"),
+            Self::Unauthorized(detail) => write!(f, "Unauthorized: {}", detail),
+            Self::Timeout => write!(f, "Request timed out"),
+            Self::ConnectionRefused(detail) => write!(f, "Connection failed: {}", detail),
+            Self::DeviceStatus {
+                http_status,
+                status_code,
+                status_string,
+                sub_status_code,
+                error_msg,
+            } => {
+                write!(f, "Device error")?;
+                if let Some(status) = http_status {
+                    write!(f, " (HTTP {})", status)?;
+                }
+                let parts: Vec<String> = [
+                    status_code.map(|c| format!("statusCode={}", c)),
+                    status_string.as_ref().map(|s| format!("statusString={}", s)),
+                    sub_status_code.as_ref().map(|s| format!("subStatusCode={}", s)),
+                    error_msg.as_ref().map(|s| format!("errorMsg={}", s)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                write!(f, ": {}", parts.join(", "))
+            }
+            Self::Http { status, body } => {
+                let reason = reqwest::StatusCode::from_u16(*status)
+                    .ok()
+                    .and_then(|s| s.canonical_reason())
+                    .unwrap_or("");
+                if body.is_empty() {
+                    write!(f, "HTTP {}: {}", status, reason)
+                } else {
+                    write!(f, "HTTP {}: {}: {}", status, reason, body)
+                }
+            }
+            Self::Parse(detail) => write!(f, "Invalid device response: {}", detail),
+            Self::PayloadTooLarge { size, max } if *size > 0 => write!(
+                f,
+                "Face image too large: {} bytes (max {} bytes)",
+                size, max
+            ),
+            Self::PayloadTooLarge { max, .. } => {
+                write!(f, "Payload too large for device (max {} bytes)", max)
+            }
+            Self::Tls(detail) => write!(f, "TLS error: {}", detail),
+            Self::Request(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl std::error::Error for HikvisionError {}
+
+impl From<HikvisionError> for String {
+    fn from(err: HikvisionError) -> Self {
+        err.to_string()
+    }
+}
+
+fn xml_tag_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    let value = xml[start..end].trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -3,15 +3,17 @@ fn parse_action_result(text: &str) -> DeviceActionResult {
         Ok(data) => {
             let status_code = data.get("statusCode").and_then(|v| v.as_i64()).map(|v| v as i32);
             let status_string = data.get("statusString").and_then(|v| v.as_str()).map(|s| s.to_string());
+            let sub_status_code = data.get("subStatusCode").and_then(|v| v.as_str()).map(|s| s.to_string());
             let error_msg = data.get("errorMsg").and_then(|v| v.as_str()).map(|s| s.to_string());
             let ok = status_code == Some(1) || status_string.as_deref() == Some("OK");
             
-            DeviceActionResult { ok, status_code, status_string, error_msg }
+            DeviceActionResult { ok, status_code, status_string, sub_status_code, error_msg }
         }
         Err(_) => DeviceActionResult {
             ok: false,
             status_code: None,
             status_string: Some("ParseError".to_string()),
+            sub_status_code: None,
             error_msg: Some(text.to_string()),
         },
     }
@@ -34,3 +36,379 @@ fn extract_device_id(text: &str) -> Option<String> {
         .and_then(|v| v.as_str())
         .map(|s| s.to_string())
 }
+
+/// `DeviceInfo.serialNumber` of a `deviceInfo?format=json` answer.
+fn extract_serial_number(text: &str) -> Option<String> {
+    let data: Value = serde_json::from_str(text).ok()?;
+    data.get("DeviceInfo")
+        .unwrap_or(&data)
+        .get("serialNumber")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// `<id>` of the `ISAPI/Security/users` entry whose `<userName>` is `username`.
+fn parse_security_user_id(xml: &str, username: &str) -> Option<String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.trim_text(true);
+    let (mut in_user, mut field) = (false, None::<Vec<u8>>);
+    let (mut id, mut name) = (None::<String>, None::<String>);
+    loop {
+        match reader.read_event().ok()? {
+            quick_xml::events::Event::Start(tag) => {
+                let local = tag.local_name().as_ref().to_vec();
+                if local == b"User" {
+                    in_user = true;
+                    id = None;
+                    name = None;
+                }
+                field = Some(local);
+            }
+            quick_xml::events::Event::Text(text) if in_user => {
+                let value = text.unescape().ok()?.trim().to_string();
+                match field.as_deref() {
+                    Some(b"id") => id = Some(value),
+                    Some(b"userName") => name = Some(value),
+                    _ => {}
+                }
+            }
+            quick_xml::events::Event::End(tag) => {
+                if tag.local_name().as_ref() == b"User" {
+                    in_user = false;
+                    if name.as_deref() == Some(username) && id.is_some() {
+                        return id;
+                    }
+                }
+                field = None;
+            }
+            quick_xml::events::Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
+
+/// `ISAPI/Security/users/<id>` body setting `password`. `loginPassword` is the current
+/// password; newer firmware requires it to change a password, older firmware ignores it.
+fn security_user_xml(id: &str, username: &str, password: &str, login_password: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <User version=\"2.0\" xmlns=\"http://www.isapi.org/ver20/XMLSchema\">\
+         <id>{}</id><userName>{}</userName><password>{}</password><loginPassword>{}</loginPassword>\
+         </User>",
+        xml_escape(id),
+        xml_escape(username),
+        xml_escape(password),
+        xml_escape(login_password)
+    )
+}
+
+fn is_https_device(device: &DeviceConfig) -> bool {
+    device
+        .scheme
+        .as_deref()
+        .is_some_and(|scheme| scheme.trim().eq_ignore_ascii_case("https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    pub(super) fn test_device(port: u16) -> DeviceConfig {
+        DeviceConfig {
+            port,
+            ..crate::domain::entities::device::test_device("test")
+        }
+    }
+
+    /// Device double that only accepts digest auth with the current nonce generation.
+    struct FakeDevice {
+        port: u16,
+        nonce_generation: Arc<AtomicUsize>,
+        authorizations: Arc<Mutex<Vec<String>>>,
+        requests: Arc<AtomicUsize>,
+    }
+
+    pub(super) async fn read_request(stream: &mut TcpStream) -> Option<String> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&buf[..end]).to_string();
+                let content_length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                while buf.len() < end + 4 + content_length {
+                    let n = stream.read(&mut chunk).await.ok()?;
+                    if n == 0 {
+                        return None;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                return Some(head);
+            }
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    async fn spawn_fake_device() -> FakeDevice {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let nonce_generation = Arc::new(AtomicUsize::new(1));
+        let authorizations = Arc::new(Mutex::new(Vec::new()));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (generation, auths, count) = (
+            nonce_generation.clone(),
+            authorizations.clone(),
+            requests.clone(),
+        );
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (generation, auths, count) = (generation.clone(), auths.clone(), count.clone());
+                tokio::spawn(async move {
+                    while let Some(head) = read_request(&mut stream).await {
+                        count.fetch_add(1, Ordering::SeqCst);
+                        let current = format!("nonce-{}", generation.load(Ordering::SeqCst));
+                        let auth = head.lines().find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("authorization")
+                                .then(|| value.trim().to_string())
+                        });
+                        let response = match auth {
+                            Some(auth) if auth.contains(&format!("nonce=\"{}\"", current)) => {
+                                auths.lock().unwrap().push(auth);
+                                let body = r#"{"statusCode":1,"statusString":"OK"}"#;
+                                format!(
+                                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                                    body.len(),
+                                    body
+                                )
+                            }
+                            auth => format!(
+                                "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"test\", qop=\"auth\", nonce=\"{}\", stale=\"{}\"\r\nContent-Length: 0\r\n\r\n",
+                                current,
+                                auth.is_some()
+                            ),
+                        };
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        FakeDevice {
+            port,
+            nonce_generation,
+            authorizations,
+            requests,
+        }
+    }
+
+    #[test]
+    fn security_user_is_found_by_name() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <UserList version="2.0" xmlns="http://www.isapi.org/ver20/XMLSchema">
+              <User><id>1</id><userName>admin</userName><userLevel>Administrator</userLevel></User>
+              <User><id>7</id><userName>operator</userName><userLevel>Operator</userLevel></User>
+            </UserList>"#;
+
+        assert_eq!(parse_security_user_id(xml, "operator").as_deref(), Some("7"));
+        assert_eq!(parse_security_user_id(xml, "admin").as_deref(), Some("1"));
+        assert_eq!(parse_security_user_id(xml, "guest"), None);
+    }
+
+    #[test]
+    fn device_id_and_serial_number_are_read_separately() {
+        let text = r#"{"DeviceInfo": {"deviceID": "a1b2c3d4-0000", "serialNumber": "DS-K1T671MF20210101AAWRE1234 "}}"#;
+        assert_eq!(extract_device_id(text).as_deref(), Some("a1b2c3d4-0000"));
+        assert_eq!(extract_serial_number(text).as_deref(), Some("DS-K1T671MF20210101AAWRE1234"));
+        assert_eq!(extract_serial_number(r#"{"DeviceInfo": {"deviceID": "x"}}"#), None);
+    }
+
+    #[test]
+    fn security_user_body_escapes_values() {
+        let body = security_user_xml("1", "admin", "a<b&c", "old");
+
+        assert!(body.contains("<password>a&lt;b&amp;c</password>"));
+        assert!(body.contains("<loginPassword>old</loginPassword>"));
+    }
+
+    #[test]
+    fn parse_digest_challenge_reads_stale_flag() {
+        let challenges = HikvisionClient::parse_digest_challenges(&[
+            r#"Digest qop="auth", realm="DS-K1T", nonce="abc", stale="TRUE""#,
+            r#"Digest realm="DS-K1T", nonce="abc""#,
+        ]);
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].nonce, "abc");
+        assert!(challenges[0].stale);
+        assert!(!challenges[1].stale);
+    }
+
+    #[test]
+    fn strongest_digest_challenge_is_selected() {
+        let challenges = HikvisionClient::parse_digest_challenges(&[
+            r#"Basic realm="DS-K1T", Digest realm="DS-K1T", nonce="n1", algorithm=MD5, qop="auth""#,
+            r#"Digest realm="DS-K1T", nonce="n2", algorithm=SHA-256, qop="auth", Digest realm="DS-K1T", nonce="n3", algorithm=SHA-512-256"#,
+        ]);
+        assert_eq!(challenges.len(), 3);
+        let selected = HikvisionClient::select_digest_challenge(challenges).unwrap();
+        assert_eq!(selected.nonce, "n2");
+        assert_eq!(
+            DigestAlgorithm::from_challenge(&selected),
+            Ok(DigestAlgorithm::Sha256)
+        );
+
+        let unsupported = HikvisionClient::parse_digest_challenges(&[
+            r#"Digest realm="r", nonce="n", algorithm=SHA-512-256"#,
+        ]);
+        let selected = HikvisionClient::select_digest_challenge(unsupported).unwrap();
+        assert!(DigestAlgorithm::from_challenge(&selected).is_err());
+    }
+
+    #[test]
+    fn digest_responses_match_rfc7616_example_with_errata() {
+        let nonce = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let expected = [
+            (DigestAlgorithm::Md5, "8ca523f5e9506fed4657c9700eebdbec"),
+            (
+                DigestAlgorithm::Sha256,
+                "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1",
+            ),
+        ];
+        for (algorithm, response) in expected {
+            let ha1 = algorithm.ha1("Mufasa", "http-auth@example.org", "Circle of Life", nonce, cnonce);
+            let ha2 = algorithm.hash("GET:/dir/index.html");
+            assert_eq!(
+                algorithm.response(&ha1, nonce, "00000001", cnonce, Some("auth"), &ha2),
+                response
+            );
+        }
+    }
+
+    #[test]
+    fn session_algorithms_mix_nonce_into_ha1() {
+        let plain = DigestAlgorithm::Sha256.ha1("u", "r", "p", "n", "c");
+        let sess = DigestAlgorithm::Sha256Sess.ha1("u", "r", "p", "n", "c");
+        assert_eq!(sess, DigestAlgorithm::Sha256.hash(&format!("{}:n:c", plain)));
+        assert_eq!(DigestAlgorithm::Md5Sess.name(), "MD5-sess");
+    }
+
+    #[tokio::test]
+    async fn cached_digest_skips_challenge_and_increments_nonce_count() {
+        let device = spawn_fake_device().await;
+        let client = HikvisionClient::new(test_device(device.port));
+
+        client.get_isapi_json("ISAPI/System/deviceInfo").await.unwrap();
+        client.get_isapi_json("ISAPI/System/deviceInfo").await.unwrap();
+        // A fresh client for the same device shares the session.
+        HikvisionClient::new(test_device(device.port))
+            .get_isapi_json("ISAPI/System/status")
+            .await
+            .unwrap();
+
+        assert_eq!(device.requests.load(Ordering::SeqCst), 4);
+        let auths = device.authorizations.lock().unwrap().clone();
+        assert!(auths[0].contains("nc=00000001"));
+        assert!(auths[1].contains("nc=00000002"));
+        assert!(auths[2].contains("nc=00000003"));
+    }
+
+    #[tokio::test]
+    async fn stale_nonce_is_rechallenged_once() {
+        let device = spawn_fake_device().await;
+        let client = HikvisionClient::new(test_device(device.port));
+
+        client.get_isapi_json("ISAPI/System/deviceInfo").await.unwrap();
+        device.nonce_generation.store(2, Ordering::SeqCst);
+        client.get_isapi_json("ISAPI/System/deviceInfo").await.unwrap();
+
+        // unauth + digest, then stale digest + fresh digest
+        assert_eq!(device.requests.load(Ordering::SeqCst), 4);
+        let auths = device.authorizations.lock().unwrap().clone();
+        assert!(auths[1].contains("nonce=\"nonce-2\""));
+        assert!(auths[1].contains("nc=00000001"));
+    }
+
+    #[test]
+    fn https_scheme_changes_base_url() {
+        let mut device = test_device(443);
+        assert_eq!(HikvisionClient::new(device.clone()).base_url(), "http://127.0.0.1:443");
+        device.scheme = Some("HTTPS".to_string());
+        assert_eq!(HikvisionClient::new(device).base_url(), "https://127.0.0.1:443");
+    }
+
+    #[test]
+    fn device_status_is_parsed_from_json_and_xml_bodies() {
+        let json_err = HikvisionError::from_status_body(
+            reqwest::StatusCode::BAD_REQUEST,
+            r#"{"statusCode":6,"statusString":"Invalid Content","subStatusCode":"employeeNoAlreadyExist","errorMsg":"employeeNo"}"#,
+        );
+        assert_eq!(json_err.sub_status_code(), Some("employeeNoAlreadyExist"));
+        assert!(json_err.is_already_exists());
+
+        let xml_err = HikvisionError::from_status_body(
+            reqwest::StatusCode::BAD_REQUEST,
+            "<ResponseStatus><statusCode>6</statusCode><subStatusCode>deviceUserAlreadyExistFace</subStatusCode></ResponseStatus>",
+        );
+        assert!(matches!(
+            xml_err,
+            HikvisionError::DeviceStatus { status_code: Some(6), .. }
+        ));
+        assert!(xml_err.is_already_exists());
+
+        let action = json_err.into_action_result("RequestFailed");
+        assert!(!action.ok);
+        assert_eq!(action.status_code, Some(6));
+        assert_eq!(action.sub_status_code.as_deref(), Some("employeeNoAlreadyExist"));
+    }
+
+    #[test]
+    fn status_without_response_body_is_classified() {
+        assert!(matches!(
+            HikvisionError::from_status_body(reqwest::StatusCode::UNAUTHORIZED, ""),
+            HikvisionError::Unauthorized(_)
+        ));
+        assert!(matches!(
+            HikvisionError::from_status_body(reqwest::StatusCode::PAYLOAD_TOO_LARGE, ""),
+            HikvisionError::PayloadTooLarge { .. }
+        ));
+        assert_eq!(
+            HikvisionError::from_status_body(reqwest::StatusCode::NOT_FOUND, "").to_string(),
+            "HTTP 404: Not Found"
+        );
+        assert!(!is_already_exists_status(Some("badParameters")));
+    }
+
+    #[tokio::test]
+    async fn unreachable_device_reports_connection_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let err = HikvisionClient::new(test_device(port))
+            .get_isapi_json("ISAPI/System/deviceInfo")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HikvisionError::ConnectionRefused(_)));
+    }
+}
@@ -0,0 +1,320 @@
+// Webhook targets (`ISAPI/Event/notification/httpHosts`)
+//
+// Firmware lists the `HttpHostNotification` slots in different JSON shapes:
+// `HttpHostNotificationList: [{ HttpHostNotification: {..} }]`,
+// `HttpHostNotificationList: { HttpHostNotification: [..] | {..} }` or one bare
+// `HttpHostNotification`. Typed `HttpHost` fields are written over the device's own object, so
+// fields this app does not model (authentication, ANR, ...) keep their values.
+
+use std::net::IpAddr;
+
+const HTTP_HOSTS_PATH: &str = "ISAPI/Event/notification/httpHosts";
+
+/// A webhook slot as read from the device: typed fields plus the object they came from.
+#[derive(Debug, Clone)]
+pub struct DeviceHttpHost {
+    pub host: HttpHost,
+    raw: Value,
+}
+
+fn json_field_text(value: &Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn http_host_notification_objects(raw: &Value) -> Vec<Value> {
+    fn unwrap(item: &Value) -> Value {
+        item.get("HttpHostNotification").cloned().unwrap_or_else(|| item.clone())
+    }
+    fn flatten(value: &Value) -> Vec<Value> {
+        match value {
+            Value::Array(items) => items.iter().map(unwrap).collect(),
+            Value::Object(_) => vec![unwrap(value)],
+            _ => Vec::new(),
+        }
+    }
+    if let Some(list) = raw.get("HttpHostNotificationList") {
+        return flatten(list.get("HttpHostNotification").unwrap_or(list));
+    }
+    raw.get("HttpHostNotification").map(flatten).unwrap_or_default()
+}
+
+fn subscribed_event_types(subscribe: &Value) -> Vec<String> {
+    if !json_field_text(subscribe, "eventMode").is_some_and(|mode| mode.eq_ignore_ascii_case("list")) {
+        return Vec::new();
+    }
+    let items = match subscribe.get("EventList") {
+        Some(Value::Array(items)) => items.clone(),
+        Some(list) => match list.get("Event") {
+            Some(Value::Array(items)) => items.clone(),
+            Some(item) => vec![item.clone()],
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    items
+        .iter()
+        .filter_map(|item| json_field_text(item.get("Event").unwrap_or(item), "type"))
+        .collect()
+}
+
+pub fn http_host_from_notification(notification: &Value) -> Option<HttpHost> {
+    let text = |key: &str| json_field_text(notification, key);
+    let protocol = if text("protocolType").is_some_and(|p| p.eq_ignore_ascii_case("HTTPS")) {
+        HttpHostProtocol::Https
+    } else {
+        HttpHostProtocol::Http
+    };
+    let address_keys = if text("addressingFormatType").is_some_and(|f| f.eq_ignore_ascii_case("hostname")) {
+        ["hostName", "ipAddress", "ipv6Address"]
+    } else {
+        ["ipAddress", "ipv6Address", "hostName"]
+    };
+    let subscribe = notification.get("SubscribeEvent");
+    Some(HttpHost {
+        id: text("id")?,
+        protocol,
+        address: address_keys.iter().find_map(|key| text(key)).unwrap_or_default(),
+        port: text("portNo")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(if protocol == HttpHostProtocol::Https { 443 } else { 80 }),
+        url: text("url").unwrap_or_else(|| "/".to_string()),
+        parameter_format: if text("parameterFormatType").is_some_and(|f| f.eq_ignore_ascii_case("XML")) {
+            HttpHostParameterFormat::Xml
+        } else {
+            HttpHostParameterFormat::Json
+        },
+        heartbeat_interval: subscribe
+            .and_then(|s| json_field_text(s, "heartbeat"))
+            .and_then(|heartbeat| heartbeat.parse().ok()),
+        event_types: subscribe.map(subscribed_event_types).unwrap_or_default(),
+    })
+}
+
+/// Write `host` into a device `HttpHostNotification` object.
+pub fn apply_http_host(notification: &mut Value, host: &HttpHost) {
+    if !notification.is_object() {
+        *notification = json!({});
+    }
+    let Value::Object(map) = notification else {
+        return;
+    };
+    map.insert("id".to_string(), json!(host.id));
+    map.insert("url".to_string(), json!(host.url));
+    let protocol = match host.protocol {
+        HttpHostProtocol::Http => "HTTP",
+        HttpHostProtocol::Https => "HTTPS",
+    };
+    map.insert("protocolType".to_string(), json!(protocol));
+    let format = match host.parameter_format {
+        HttpHostParameterFormat::Json => "JSON",
+        HttpHostParameterFormat::Xml => "XML",
+    };
+    map.insert("parameterFormatType".to_string(), json!(format));
+    let (addressing, address_key) = match host.address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(_)) => ("ipaddress", "ipAddress"),
+        Ok(IpAddr::V6(_)) => ("ipaddress", "ipv6Address"),
+        Err(_) => ("hostname", "hostName"),
+    };
+    map.insert("addressingFormatType".to_string(), json!(addressing));
+    map.insert(address_key.to_string(), json!(host.address));
+    map.insert("portNo".to_string(), json!(host.port));
+    map.entry("httpAuthenticationMethod").or_insert_with(|| json!("none"));
+
+    // Left out when there is nothing to say: not every firmware accepts `SubscribeEvent`.
+    if host.heartbeat_interval.is_none() && host.event_types.is_empty() && !map.contains_key("SubscribeEvent") {
+        return;
+    }
+    let subscribe = map.entry("SubscribeEvent").or_insert_with(|| json!({}));
+    if !subscribe.is_object() {
+        *subscribe = json!({});
+    }
+    let Value::Object(subscribe) = subscribe else {
+        return;
+    };
+    if let Some(heartbeat) = host.heartbeat_interval {
+        subscribe.insert("heartbeat".to_string(), json!(heartbeat));
+    }
+    if host.event_types.is_empty() {
+        subscribe.insert("eventMode".to_string(), json!("all"));
+        subscribe.remove("EventList");
+    } else {
+        subscribe.insert("eventMode".to_string(), json!("list"));
+        let events: Vec<Value> = host.event_types.iter().map(|t| json!({ "type": t })).collect();
+        subscribe.insert("EventList".to_string(), Value::Array(events));
+    }
+}
+
+/// A `200` answer can still carry a failed `ResponseStatus`.
+fn ensure_status_ok(text: &str) -> Result<(), HikvisionError> {
+    match HikvisionError::parse_response_status(Some(200), text) {
+        Some(err @ HikvisionError::DeviceStatus { status_code: Some(code), .. }) if code != 1 => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// The device's verdict on a `httpHosts/<id>/test` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpHostTestOutcome {
+    pub ok: bool,
+    /// `HttpHostTestResult.errorDescription`, or the failed status text.
+    pub description: Option<String>,
+}
+
+fn http_host_test_outcome(text: &str) -> HttpHostTestOutcome {
+    if let Some(HikvisionError::DeviceStatus {
+        status_code: Some(code),
+        status_string,
+        sub_status_code,
+        error_msg,
+        ..
+    }) = HikvisionError::parse_response_status(Some(200), text)
+    {
+        if code != 1 {
+            return HttpHostTestOutcome {
+                ok: false,
+                description: error_msg.or(sub_status_code).or(status_string),
+            };
+        }
+    }
+    let description = serde_json::from_str::<Value>(text)
+        .ok()
+        .and_then(|value| value.get("HttpHostTestResult").and_then(|r| json_field_text(r, "errorDescription")))
+        .or_else(|| xml_tag_text(text, "errorDescription"));
+    HttpHostTestOutcome {
+        ok: description
+            .as_deref()
+            .is_none_or(|d| d.eq_ignore_ascii_case("ok") || d.eq_ignore_ascii_case("success")),
+        description,
+    }
+}
+
+impl HikvisionClient {
+    pub async fn list_http_hosts(&self) -> Result<Vec<DeviceHttpHost>, HikvisionError> {
+        let raw = self.get_isapi_json(&format!("{}?format=json", HTTP_HOSTS_PATH)).await?;
+        Ok(http_host_notification_objects(&raw)
+            .into_iter()
+            .filter_map(|raw| {
+                http_host_from_notification(&raw).map(|host| DeviceHttpHost { host, raw })
+            })
+            .collect())
+    }
+
+    /// Save `host` in its slot (`current` is the slot as read, `None` for a new one) and return
+    /// the slot as the device keeps it. Firmware that does not create slots with `PUT` gets a
+    /// `POST` to the list.
+    pub async fn save_http_host(
+        &self,
+        host: &HttpHost,
+        current: Option<&DeviceHttpHost>,
+    ) -> Result<DeviceHttpHost, HikvisionError> {
+        let mut notification = current.map(|c| c.raw.clone()).unwrap_or_else(|| json!({}));
+        apply_http_host(&mut notification, host);
+        let payload = json!({ "HttpHostNotification": notification });
+        let slot_url = format!("{}/{}/{}?format=json", self.base_url(), HTTP_HOSTS_PATH, host.id);
+        let text = match self.auth_request_json(reqwest::Method::PUT, &slot_url, Some(payload.clone())).await {
+            Err(HikvisionError::DeviceStatus { .. } | HikvisionError::Http { .. }) if current.is_none() => {
+                let list_url = format!("{}/{}?format=json", self.base_url(), HTTP_HOSTS_PATH);
+                self.auth_request_json(reqwest::Method::POST, &list_url, Some(payload)).await?
+            }
+            other => other?,
+        };
+        ensure_status_ok(&text)?;
+        self.list_http_hosts()
+            .await?
+            .into_iter()
+            .find(|saved| saved.host.id == host.id)
+            .ok_or_else(|| HikvisionError::Parse(format!("http host {} is missing after saving", host.id)))
+    }
+
+    /// Put a slot back exactly as it was read.
+    pub async fn restore_http_host(&self, slot: &DeviceHttpHost) -> Result<(), HikvisionError> {
+        let url = format!("{}/{}/{}?format=json", self.base_url(), HTTP_HOSTS_PATH, slot.host.id);
+        let payload = json!({ "HttpHostNotification": slot.raw });
+        let text = self.auth_request_json(reqwest::Method::PUT, &url, Some(payload)).await?;
+        ensure_status_ok(&text)
+    }
+
+    /// Ask the device to post a test message to `slot` (`httpHosts/<id>/test`).
+    pub async fn test_http_host(&self, slot: &DeviceHttpHost) -> Result<HttpHostTestOutcome, HikvisionError> {
+        let url = format!("{}/{}/{}/test?format=json", self.base_url(), HTTP_HOSTS_PATH, slot.host.id);
+        let payload = json!({ "HttpHostNotification": slot.raw });
+        let text = self.auth_request_json(reqwest::Method::POST, &url, Some(payload)).await?;
+        Ok(http_host_test_outcome(&text))
+    }
+
+    pub async fn delete_http_host(&self, id: &str) -> Result<(), HikvisionError> {
+        let url = format!("{}/{}/{}?format=json", self.base_url(), HTTP_HOSTS_PATH, id);
+        let text = self.auth_request_json(reqwest::Method::DELETE, &url, None).await?;
+        ensure_status_ok(&text)
+    }
+}
+
+#[cfg(test)]
+mod http_hosts_tests {
+    use super::*;
+
+    #[test]
+    fn http_hosts_are_read_from_every_list_shape_and_written_back_in_place() {
+        let slot = json!({
+            "id": 2, "url": "/webhook/s1/out", "protocolType": "HTTP", "parameterFormatType": "XML",
+            "addressingFormatType": "hostname", "hostName": "vps.example.uz", "portNo": "8080",
+            "httpAuthenticationMethod": "digest",
+            "SubscribeEvent": {"heartbeat": 30, "eventMode": "list", "EventList": {"Event": [{"type": "AccessControllerEvent"}]}}
+        });
+        for raw in [
+            json!({"HttpHostNotificationList": [{"HttpHostNotification": slot.clone()}]}),
+            json!({"HttpHostNotificationList": {"HttpHostNotification": [slot.clone()]}}),
+            json!({"HttpHostNotification": slot.clone()}),
+        ] {
+            let hosts: Vec<HttpHost> = http_host_notification_objects(&raw)
+                .iter()
+                .filter_map(http_host_from_notification)
+                .collect();
+            assert_eq!(hosts.len(), 1);
+            assert_eq!(
+                (hosts[0].id.as_str(), hosts[0].address.as_str(), hosts[0].port, hosts[0].parameter_format),
+                ("2", "vps.example.uz", 8080, HttpHostParameterFormat::Xml)
+            );
+            assert_eq!((hosts[0].heartbeat_interval, hosts[0].event_types.clone()), (Some(30), vec!["AccessControllerEvent".to_string()]));
+        }
+
+        let mut notification = slot.clone();
+        let host = HttpHost {
+            id: "2".to_string(),
+            protocol: HttpHostProtocol::Http,
+            address: "192.168.1.20".to_string(),
+            port: 18090,
+            url: "/webhook/s1/in".to_string(),
+            parameter_format: HttpHostParameterFormat::Json,
+            heartbeat_interval: None,
+            event_types: Vec::new(),
+        };
+        apply_http_host(&mut notification, &host);
+        assert_eq!(notification["addressingFormatType"], "ipaddress");
+        assert_eq!(notification["ipAddress"], "192.168.1.20");
+        assert_eq!(notification["httpAuthenticationMethod"], "digest");
+        assert_eq!(notification["SubscribeEvent"], json!({"heartbeat": 30, "eventMode": "all"}));
+        assert_eq!(http_host_from_notification(&notification), Some(HttpHost { heartbeat_interval: Some(30), ..host.clone() }));
+
+        let mut fresh = json!({});
+        apply_http_host(&mut fresh, &host);
+        assert!(fresh.get("SubscribeEvent").is_none());
+    }
+
+    #[test]
+    fn http_host_test_outcome_reads_device_verdicts() {
+        let ok = http_host_test_outcome(r#"{"HttpHostTestResult": {"errorDescription": "ok"}}"#);
+        assert_eq!(ok, HttpHostTestOutcome { ok: true, description: Some("ok".to_string()) });
+        assert!(http_host_test_outcome(r#"{"statusCode": 1, "statusString": "OK"}"#).ok);
+
+        let refused = http_host_test_outcome("<HttpHostTestResult><errorDescription>connect server fail</errorDescription></HttpHostTestResult>");
+        assert_eq!(refused.description.as_deref(), Some("connect server fail"));
+        assert!(!refused.ok);
+        let failed = http_host_test_outcome(r#"{"statusCode": 4, "statusString": "Invalid Operation", "subStatusCode": "notSupport"}"#);
+        assert_eq!((failed.ok, failed.description.as_deref()), (false, Some("notSupport")));
+    }
+}
@@ -0,0 +1,132 @@
+// Certificate pinning for HTTPS devices
+//
+// Devices ship self-signed certificates, so trust comes from the SHA-256 of the leaf
+// certificate instead of a CA chain. The check runs inside the TLS handshake: on a mismatch the
+// connection is dropped before the request (and the digest credentials with it) is written.
+
+#[derive(Debug, Default)]
+struct TlsPinState {
+    /// Fingerprint seen on the first handshake, enforced for the rest when nothing is pinned.
+    observed: Option<String>,
+    /// Why the last handshake was refused; turned into `HikvisionError::Tls` by `send_request`.
+    rejection: Option<String>,
+}
+
+struct PinnedCertVerifier {
+    pinned: Option<String>,
+    state: Arc<Mutex<TlsPinState>>,
+}
+
+impl rustls::client::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        let observed = certificate_fingerprint(&end_entity.0);
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| rustls::Error::General("fingerprint state is unavailable".to_string()))?;
+        let expected = self.pinned.clone().or_else(|| state.observed.clone());
+        if let Err(reason) = check_pinned_fingerprint(expected.as_deref(), &observed) {
+            state.rejection = Some(reason.clone());
+            return Err(rustls::Error::General(reason));
+        }
+        state.observed = Some(observed);
+        state.rejection = None;
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// TLS settings for one client: only the pinned (or first seen) leaf certificate is accepted.
+/// Handshake signatures are still checked against that certificate's key.
+fn pinned_tls_config(pinned: Option<String>, state: Arc<Mutex<TlsPinState>>) -> rustls::ClientConfig {
+    rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { pinned, state }))
+        .with_no_client_auth()
+}
+
+/// SHA-256 of a DER certificate as colon-separated uppercase hex.
+fn certificate_fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn normalize_fingerprint(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn check_pinned_fingerprint(expected: Option<&str>, observed: &str) -> Result<(), String> {
+    match expected {
+        Some(expected) if normalize_fingerprint(expected) != normalize_fingerprint(observed) => {
+            Err(format!(
+                "TLS certificate fingerprint mismatch: expected {}, got {}",
+                expected, observed
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tls_pin_tests {
+    use super::*;
+    use rustls::client::ServerCertVerifier;
+
+    fn verify(verifier: &PinnedCertVerifier, der: &[u8]) -> Result<(), rustls::Error> {
+        let server_name = rustls::ServerName::try_from("device.local").unwrap();
+        verifier
+            .verify_server_cert(
+                &rustls::Certificate(der.to_vec()),
+                &[],
+                &server_name,
+                &mut std::iter::empty(),
+                &[],
+                std::time::SystemTime::now(),
+            )
+            .map(|_| ())
+    }
+
+    #[test]
+    fn pinned_fingerprint_must_match_ignoring_format() {
+        let observed = certificate_fingerprint(b"device-cert");
+        assert_eq!(observed.len(), 32 * 3 - 1);
+        assert!(check_pinned_fingerprint(None, &observed).is_ok());
+        let relaxed = observed.replace(':', "").to_lowercase();
+        assert!(check_pinned_fingerprint(Some(&relaxed), &observed).is_ok());
+        let other = certificate_fingerprint(b"replaced-cert");
+        assert!(check_pinned_fingerprint(Some(&other), &observed).is_err());
+    }
+
+    #[test]
+    fn handshake_refuses_a_certificate_other_than_the_pin() {
+        let state = Arc::new(Mutex::new(TlsPinState::default()));
+        let pinned = PinnedCertVerifier {
+            pinned: Some(certificate_fingerprint(b"device-cert")),
+            state: state.clone(),
+        };
+        assert!(verify(&pinned, b"device-cert").is_ok());
+        assert!(verify(&pinned, b"mitm-cert").is_err());
+        assert!(state.lock().unwrap().rejection.as_deref().is_some_and(|r| r.contains("mismatch")));
+
+        // Nothing pinned: the first certificate is trusted and then enforced.
+        let state = Arc::new(Mutex::new(TlsPinState::default()));
+        let first_use = PinnedCertVerifier { pinned: None, state: state.clone() };
+        assert!(verify(&first_use, b"device-cert").is_ok());
+        assert_eq!(state.lock().unwrap().observed, Some(certificate_fingerprint(b"device-cert")));
+        assert!(verify(&first_use, b"mitm-cert").is_err());
+    }
+}
@@ -0,0 +1 @@
+pub mod student_file;
@@ -0,0 +1,559 @@
+// Student spreadsheet reader (XLSX / CSV)
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// One student row as found in the file, before validation.
+#[derive(Debug, Clone, Default)]
+pub struct StudentFileRow {
+    pub sheet: String,
+    /// 1-based row number as the user sees it in Excel (line number for CSV).
+    pub row: u32,
+    pub full_name: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub father_name: Option<String>,
+    pub gender: Option<String>,
+    pub class_name: Option<String>,
+    pub parent_phone: Option<String>,
+    pub photo_path: Option<String>,
+    /// Picture anchored on this row in the sheet drawing.
+    pub embedded_image: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Column {
+    FullName,
+    FirstName,
+    LastName,
+    FatherName,
+    Gender,
+    ClassName,
+    ParentPhone,
+    PhotoPath,
+}
+
+/// Header spellings accepted per column: the Hikvision "Person Information Template" headers,
+/// the registrator's own Excel template (uz) and plain English.
+fn header_column(header: &str) -> Option<Column> {
+    let normalized = header.trim().trim_start_matches('*').trim().to_lowercase();
+    let column = match normalized.as_str() {
+        "person name" | "full name" | "name" | "fio" | "f.i.o" | "f.i.sh" => Column::FullName,
+        "first name" | "ism" => Column::FirstName,
+        "last name" | "familiya" => Column::LastName,
+        "father name" | "otasining ismi" => Column::FatherName,
+        "gender" | "jinsi" => Column::Gender,
+        "organization" | "class" | "sinf" => Column::ClassName,
+        "contact" | "parent phone" | "phone" | "telefon" => Column::ParentPhone,
+        "photo" | "photo path" | "image" | "rasm" => Column::PhotoPath,
+        _ => return None,
+    };
+    Some(column)
+}
+
+fn detect_header(cells: &BTreeMap<u32, String>) -> Option<HashMap<Column, u32>> {
+    let mut columns = HashMap::new();
+    for (index, value) in cells {
+        if let Some(column) = header_column(value) {
+            columns.entry(column).or_insert(*index);
+        }
+    }
+    let has_name = columns.contains_key(&Column::FullName)
+        || columns.contains_key(&Column::FirstName)
+        || columns.contains_key(&Column::LastName);
+    (has_name && columns.len() >= 2).then_some(columns)
+}
+
+fn cell_value(value: &str) -> Option<String> {
+    // Excel convention for keeping leading zeros: '012345
+    let trimmed = value.trim().trim_start_matches('\'').trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// Turn the rows of one sheet into student rows, starting after the detected header row.
+fn rows_from_table(
+    sheet: &str,
+    table: &BTreeMap<u32, BTreeMap<u32, String>>,
+    images: &HashMap<u32, Vec<u8>>,
+) -> Vec<StudentFileRow> {
+    let Some((header_row, columns)) = table
+        .iter()
+        .find_map(|(row, cells)| detect_header(cells).map(|columns| (*row, columns)))
+    else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+    for (row, cells) in table.range(header_row + 1..) {
+        let get = |column: Column| {
+            columns
+                .get(&column)
+                .and_then(|index| cells.get(index))
+                .and_then(|value| cell_value(value))
+        };
+        let student = StudentFileRow {
+            sheet: sheet.to_string(),
+            row: *row,
+            full_name: get(Column::FullName),
+            first_name: get(Column::FirstName),
+            last_name: get(Column::LastName),
+            father_name: get(Column::FatherName),
+            gender: get(Column::Gender),
+            class_name: get(Column::ClassName),
+            parent_phone: get(Column::ParentPhone),
+            photo_path: get(Column::PhotoPath),
+            embedded_image: images.get(row).cloned(),
+        };
+        let is_empty = student.full_name.is_none()
+            && student.first_name.is_none()
+            && student.last_name.is_none()
+            && student.gender.is_none()
+            && student.photo_path.is_none()
+            && student.embedded_image.is_none();
+        if !is_empty {
+            rows.push(student);
+        }
+    }
+    rows
+}
+
+/// Read student rows from an `.xlsx` or `.csv` file.
+pub fn read_student_file(path: &Path) -> Result<Vec<StudentFileRow>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Faylni o'qib bo'lmadi: {}", e))?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match extension.as_str() {
+        "xlsx" => read_xlsx(&bytes),
+        "csv" => read_csv(&bytes),
+        other => Err(format!("Unsupported file type: .{} (xlsx or csv expected)", other)),
+    }
+}
+
+pub fn read_csv(bytes: &[u8]) -> Result<Vec<StudentFileRow>, String> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_start_matches('\u{feff}');
+    let first_line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes());
+    let mut table: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("CSV parse error: {}", e))?;
+        let line = record.position().map(|p| p.line() as u32).unwrap_or(0);
+        let cells = record
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index as u32, value.to_string()))
+            .collect();
+        table.insert(line, cells);
+    }
+    Ok(rows_from_table("csv", &table, &HashMap::new()))
+}
+
+pub fn read_xlsx(bytes: &[u8]) -> Result<Vec<StudentFileRow>, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid xlsx file: {}", e))?;
+
+    let shared_strings = match read_zip_entry(&mut archive, "xl/sharedStrings.xml") {
+        Some(xml) => parse_shared_strings(&xml)?,
+        None => Vec::new(),
+    };
+    let workbook = read_zip_entry(&mut archive, "xl/workbook.xml")
+        .ok_or_else(|| "Invalid xlsx file: xl/workbook.xml missing".to_string())?;
+    let workbook_rels = read_relationships(&mut archive, "xl/workbook.xml")?;
+
+    let mut rows = Vec::new();
+    for (name, rel_id) in parse_workbook_sheets(&workbook)? {
+        let Some((target, _)) = workbook_rels.get(&rel_id) else {
+            continue;
+        };
+        let sheet_path = resolve_part_path("xl/workbook.xml", target);
+        let Some(sheet_xml) = read_zip_entry(&mut archive, &sheet_path) else {
+            continue;
+        };
+        let table = parse_sheet_cells(&sheet_xml, &shared_strings)?;
+        let images = read_sheet_images(&mut archive, &sheet_path)?;
+        rows.extend(rows_from_table(&name, &table, &images));
+    }
+    Ok(rows)
+}
+
+type XlsxArchive<'a> = zip::ZipArchive<Cursor<&'a [u8]>>;
+
+fn read_zip_entry_bytes(archive: &mut XlsxArchive<'_>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn read_zip_entry(archive: &mut XlsxArchive<'_>, name: &str) -> Option<String> {
+    read_zip_entry_bytes(archive, name).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Resolve a relationship target against the part that owns the relationship.
+fn resolve_part_path(owner: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = owner.split('/').collect();
+    parts.pop();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            other => parts.push(other),
+        }
+    }
+    parts.join("/")
+}
+
+fn rels_path(owner: &str) -> String {
+    match owner.rsplit_once('/') {
+        Some((dir, file)) => format!("{}/_rels/{}.rels", dir, file),
+        None => format!("_rels/{}.rels", owner),
+    }
+}
+
+fn attr(e: &BytesStart<'_>, local_name: &[u8]) -> Option<String> {
+    e.attributes().flatten().find_map(|a| {
+        (a.key.local_name().as_ref() == local_name)
+            .then(|| a.unescape_value().ok().map(|v| v.into_owned()))
+            .flatten()
+    })
+}
+
+fn xml_error(e: quick_xml::Error) -> String {
+    format!("Invalid xlsx XML: {}", e)
+}
+
+/// Relationship id -> (target, type) for one part.
+fn read_relationships(
+    archive: &mut XlsxArchive<'_>,
+    owner: &str,
+) -> Result<HashMap<String, (String, String)>, String> {
+    let Some(xml) = read_zip_entry(archive, &rels_path(owner)) else {
+        return Ok(HashMap::new());
+    };
+    let mut reader = Reader::from_str(&xml);
+    let mut rels = HashMap::new();
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attr(&e, b"Id"), attr(&e, b"Target")) {
+                    rels.insert(id, (target, attr(&e, b"Type").unwrap_or_default()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(rels)
+}
+
+/// Visible sheets as (name, relationship id), in workbook order.
+fn parse_workbook_sheets(xml: &str) -> Result<Vec<(String, String)>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut sheets = Vec::new();
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheet" => {
+                let hidden = attr(&e, b"state").is_some_and(|state| state != "visible");
+                if let (false, Some(name), Some(id)) = (hidden, attr(&e, b"name"), attr(&e, b"id")) {
+                    sheets.push((name, id));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sheets)
+}
+
+fn parse_shared_strings(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let mut current: Option<String> = None;
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"si" => current = Some(String::new()),
+                b"t" => in_text = true,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Text(t) if in_text => {
+                if let Some(current) = current.as_mut() {
+                    current.push_str(&t.unescape().map_err(xml_error)?);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"si" => strings.push(current.take().unwrap_or_default()),
+                b"t" => in_text = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+/// Column index (0-based) from a cell reference like `K9`.
+fn column_index(reference: &str) -> Option<u32> {
+    let letters: String = reference.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let mut index = 0u32;
+    for c in letters.to_ascii_uppercase().chars() {
+        index = index * 26 + (c as u32 - 'A' as u32 + 1);
+    }
+    Some(index - 1)
+}
+
+fn row_index(reference: &str) -> Option<u32> {
+    reference
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+        .parse()
+        .ok()
+}
+
+/// Cell text keyed by 1-based row and 0-based column.
+fn parse_sheet_cells(
+    xml: &str,
+    shared_strings: &[String],
+) -> Result<BTreeMap<u32, BTreeMap<u32, String>>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut table: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
+    let mut current_row = 0u32;
+    let mut next_column = 0u32;
+    let mut cell: Option<(u32, Option<String>, String)> = None;
+    let mut in_value = false;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"row" => {
+                    current_row = attr(&e, b"r")
+                        .and_then(|r| r.parse().ok())
+                        .unwrap_or(current_row + 1);
+                    next_column = 0;
+                }
+                b"c" => {
+                    let column = attr(&e, b"r")
+                        .and_then(|r| {
+                            if let Some(row) = row_index(&r) {
+                                current_row = row;
+                            }
+                            column_index(&r)
+                        })
+                        .unwrap_or(next_column);
+                    next_column = column + 1;
+                    cell = Some((column, attr(&e, b"t"), String::new()));
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"c" => {
+                next_column = attr(&e, b"r").and_then(|r| column_index(&r)).unwrap_or(next_column) + 1;
+            }
+            Event::Text(t) if in_value => {
+                if let Some((_, _, text)) = cell.as_mut() {
+                    text.push_str(&t.unescape().map_err(xml_error)?);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    if let Some((column, kind, raw)) = cell.take() {
+                        let value = match kind.as_deref() {
+                            Some("s") => raw
+                                .trim()
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|i| shared_strings.get(i).cloned())
+                                .unwrap_or_default(),
+                            _ => raw,
+                        };
+                        if !value.trim().is_empty() {
+                            table.entry(current_row).or_default().insert(column, value);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(table)
+}
+
+/// Pictures placed on a sheet, keyed by the 1-based row of their top-left anchor.
+fn read_sheet_images(
+    archive: &mut XlsxArchive<'_>,
+    sheet_path: &str,
+) -> Result<HashMap<u32, Vec<u8>>, String> {
+    let mut images = HashMap::new();
+    let sheet_rels = read_relationships(archive, sheet_path)?;
+    for (target, rel_type) in sheet_rels.values() {
+        if !rel_type.ends_with("/drawing") {
+            continue;
+        }
+        let drawing_path = resolve_part_path(sheet_path, target);
+        let Some(drawing_xml) = read_zip_entry(archive, &drawing_path) else {
+            continue;
+        };
+        let drawing_rels = read_relationships(archive, &drawing_path)?;
+        for (row, embed_id) in parse_drawing_anchors(&drawing_xml)? {
+            let Some((media_target, _)) = drawing_rels.get(&embed_id) else {
+                continue;
+            };
+            let media_path = resolve_part_path(&drawing_path, media_target);
+            if let Some(bytes) = read_zip_entry_bytes(archive, &media_path) {
+                images.entry(row + 1).or_insert(bytes);
+            }
+        }
+    }
+    Ok(images)
+}
+
+/// (0-based anchor row, blip relationship id) for each picture in a drawing part.
+fn parse_drawing_anchors(xml: &str) -> Result<Vec<(u32, String)>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut anchors = Vec::new();
+    let mut in_from = false;
+    let mut in_row = false;
+    let mut row: Option<u32> = None;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"twoCellAnchor" | b"oneCellAnchor" | b"absoluteAnchor" => row = None,
+                b"from" => in_from = true,
+                b"row" if in_from => in_row = true,
+                b"blip" => {
+                    if let (Some(row), Some(id)) = (row, attr(&e, b"embed")) {
+                        anchors.push((row, id));
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"blip" => {
+                if let (Some(row), Some(id)) = (row, attr(&e, b"embed")) {
+                    anchors.push((row, id));
+                }
+            }
+            Event::Text(t) if in_row => {
+                row = t.unescape().map_err(xml_error)?.trim().parse().ok();
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"from" => in_from = false,
+                b"row" => in_row = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(anchors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn build_xlsx(parts: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut cursor);
+            for (name, content) in parts {
+                writer
+                    .start_file(*name, zip::write::FileOptions::default())
+                    .unwrap();
+                writer.write_all(content).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn reads_person_information_template_rows_and_embedded_images() {
+        let workbook = br#"<workbook xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="5-A" sheetId="1" r:id="rId1"/><sheet name="_validation" sheetId="2" state="veryHidden" r:id="rId2"/></sheets></workbook>"#;
+        let workbook_rels = br#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#;
+        let shared = br#"<sst><si><t xml:space="preserve">	Rule:</t></si><si><t>*Person ID</t></si><si><t>*Organization</t></si><si><t>*Person Name</t></si><si><t>*Gender</t></si><si><t>Contact</t></si><si><r><t>Aliyev </t></r><r><t>Vali</t></r></si></sst>"#;
+        let sheet = br#"<worksheet><sheetData>
+            <row r="1"><c r="A1" t="s"><v>0</v></c></row>
+            <row r="9"><c r="A9" t="s"><v>1</v></c><c r="B9" t="s"><v>2</v></c><c r="C9" t="s"><v>3</v></c><c r="D9" t="s"><v>4</v></c><c r="E9" t="s"><v>5</v></c></row>
+            <row r="10"><c r="A10"><v>1</v></c><c r="C10" t="s"><v>6</v></c><c r="D10"><v>1</v></c><c r="E10" t="inlineStr"><is><t>'998901234567</t></is></c></row>
+            <row r="11"></row>
+            <row r="12"><c r="C12" t="inlineStr"><is><t>Karimova Nodira</t></is></c><c r="D12"><v>2</v></c><c r="B12" t="inlineStr"><is><t>5-B</t></is></c></row>
+        </sheetData></worksheet>"#;
+        let sheet_rels = br#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/drawing" Target="../drawings/drawing1.xml"/></Relationships>"#;
+        let drawing = br#"<xdr:wsDr xmlns:xdr="x" xmlns:a="a" xmlns:r="r"><xdr:twoCellAnchor><xdr:from><xdr:col>5</xdr:col><xdr:row>9</xdr:row></xdr:from><xdr:pic><xdr:blipFill><a:blip r:embed="rIdImg"/></xdr:blipFill></xdr:pic></xdr:twoCellAnchor></xdr:wsDr>"#;
+        let drawing_rels = br#"<Relationships><Relationship Id="rIdImg" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/image1.jpg"/></Relationships>"#;
+        let bytes = build_xlsx(&[
+            ("xl/workbook.xml", workbook),
+            ("xl/_rels/workbook.xml.rels", workbook_rels),
+            ("xl/sharedStrings.xml", shared),
+            ("xl/worksheets/sheet1.xml", sheet),
+            ("xl/worksheets/_rels/sheet1.xml.rels", sheet_rels),
+            ("xl/drawings/drawing1.xml", drawing),
+            ("xl/drawings/_rels/drawing1.xml.rels", drawing_rels),
+            ("xl/media/image1.jpg", &[0xFF, 0xD8, 0xFF, 0xE0]),
+        ]);
+
+        let rows = read_xlsx(&bytes).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].sheet, "5-A");
+        assert_eq!(rows[0].row, 10);
+        assert_eq!(rows[0].full_name.as_deref(), Some("Aliyev Vali"));
+        assert_eq!(rows[0].gender.as_deref(), Some("1"));
+        assert_eq!(rows[0].parent_phone.as_deref(), Some("998901234567"));
+        assert_eq!(rows[0].embedded_image.as_deref(), Some(&[0xFF, 0xD8, 0xFF, 0xE0][..]));
+        assert_eq!(rows[1].row, 12);
+        assert_eq!(rows[1].class_name.as_deref(), Some("5-B"));
+        assert!(rows[1].embedded_image.is_none());
+    }
+
+    #[test]
+    fn reads_semicolon_csv_with_uzbek_headers() {
+        let csv = "\u{feff}Familiya;Ism;Jinsi;Sinf;Rasm\nAliyev;Vali;Erkak;5-A;photos/vali.jpg\n;;;;\nKarimova;Nodira;Ayol;5-B;\n";
+        let rows = read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].row, 2);
+        assert_eq!(rows[0].last_name.as_deref(), Some("Aliyev"));
+        assert_eq!(rows[0].photo_path.as_deref(), Some("photos/vali.jpg"));
+        assert_eq!(rows[1].row, 4);
+        assert_eq!(rows[1].gender.as_deref(), Some("Ayol"));
+    }
+
+    #[test]
+    fn resolves_relationship_targets() {
+        assert_eq!(
+            resolve_part_path("xl/worksheets/sheet1.xml", "../drawings/drawing1.xml"),
+            "xl/drawings/drawing1.xml"
+        );
+        assert_eq!(resolve_part_path("xl/workbook.xml", "/xl/worksheets/sheet2.xml"), "xl/worksheets/sheet2.xml");
+        assert_eq!(rels_path("xl/workbook.xml"), "xl/_rels/workbook.xml.rels");
+    }
+}
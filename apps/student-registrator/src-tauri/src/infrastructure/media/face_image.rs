@@ -0,0 +1,489 @@
+// Face photo normalization (decode, orient, crop, re-encode for the device face library)
+//
+// Devices only take small baseline JPEGs. Operators upload whatever the phone or scanner
+// produced, so every photo is decoded (JPEG / PNG / WebP), rotated by its EXIF orientation,
+// cropped around the face at the face-library aspect ratio and re-encoded until it fits.
+
+use std::fmt;
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, RgbImage};
+
+/// Face library pictures are portrait 3:4 (width:height).
+pub const FACE_ASPECT_WIDTH: u32 = 3;
+pub const FACE_ASPECT_HEIGHT: u32 = 4;
+/// Output size; smaller crops are kept as they are (never upscaled).
+pub const FACE_TARGET_WIDTH: u32 = 480;
+pub const FACE_TARGET_HEIGHT: u32 = 640;
+/// Refuse to decode anything bigger than this (a 48 MP phone photo is ~15 MB).
+pub const MAX_SOURCE_IMAGE_BYTES: usize = 25 * 1024 * 1024;
+
+const JPEG_QUALITY_STEPS: [u8; 8] = [90, 85, 80, 75, 70, 60, 50, 40];
+/// When even the lowest quality does not fit, shrink by this factor and try again.
+const DOWNSCALE_STEP: f32 = 0.8;
+const MIN_OUTPUT_WIDTH: u32 = 120;
+/// Skin detection runs on a copy scaled to this width.
+const ANALYSIS_WIDTH: u32 = 160;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceImageError {
+    /// Not a JPEG, PNG or WebP (e.g. HEIC straight from an iPhone).
+    Unsupported(String),
+    Decode(String),
+    TooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for FaceImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(format) => write!(f, "unsupported image format: {} (use JPG, PNG or WebP)", format),
+            Self::Decode(detail) => write!(f, "image could not be decoded: {}", detail),
+            Self::TooLarge { size, max } => write!(f, "image is too large: {} bytes (max {})", size, max),
+        }
+    }
+}
+
+/// JPEG ready for `FaceDataRecord` upload.
+#[derive(Debug, Clone)]
+pub struct NormalizedFaceImage {
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// JPEG quality used; `None` when the source already met the requirements.
+    pub quality: Option<u8>,
+}
+
+/// Region of skin-coloured pixels, in source image coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkinRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Skin pixels in the region, counted on the analysis copy.
+    pub pixels: u32,
+}
+
+fn detect_format(bytes: &[u8]) -> Result<ImageFormat, FaceImageError> {
+    match image::guess_format(bytes) {
+        Ok(format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) => Ok(format),
+        Ok(format) => Err(FaceImageError::Unsupported(format!("{:?}", format))),
+        Err(_) if bytes.len() > 12 && &bytes[4..8] == b"ftyp" => Err(FaceImageError::Unsupported(
+            String::from_utf8_lossy(&bytes[8..12]).trim().to_uppercase(),
+        )),
+        Err(_) => Err(FaceImageError::Unsupported("unknown".to_string())),
+    }
+}
+
+fn open_decoder(bytes: &[u8], format: ImageFormat) -> Result<impl ImageDecoder + '_, FaceImageError> {
+    let mut reader = ImageReader::new(Cursor::new(bytes));
+    reader.set_format(format);
+    reader
+        .into_decoder()
+        .map_err(|err| FaceImageError::Decode(err.to_string()))
+}
+
+fn is_face_aspect(width: u32, height: u32) -> bool {
+    // Allow one pixel of rounding in either direction.
+    (width as i64 * FACE_ASPECT_HEIGHT as i64 - height as i64 * FACE_ASPECT_WIDTH as i64).abs()
+        <= FACE_ASPECT_HEIGHT.max(FACE_ASPECT_WIDTH) as i64
+}
+
+/// Whether the JPEG's frame is anything but baseline/extended sequential (SOF0/SOF1), e.g.
+/// progressive (SOF2). Many terminals reject those even when size and aspect are fine.
+fn is_non_baseline_jpeg(bytes: &[u8]) -> bool {
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return false;
+        }
+        let marker = bytes[pos + 1];
+        match marker {
+            // Fill byte before a marker.
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Standalone markers without a length.
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            0xC0 | 0xC1 => return false,
+            // SOF2/3, SOF5-7, SOF9-11, SOF13-15 (0xC4 DHT, 0xC8 JPG and 0xCC DAC are not frames).
+            0xC2..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => return true,
+            0xD9 | 0xDA => return false,
+            _ => {}
+        }
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        pos += 2 + length;
+    }
+    false
+}
+
+/// Return the photo as an uploadable JPEG, re-encoding only when it is not one already.
+///
+/// A baseline JPEG that fits `max_bytes`, needs no rotation, is already 3:4 and no bigger than
+/// the target size passes through untouched, so running this twice does not crop the face twice.
+pub fn prepare_face_image(bytes: &[u8], max_bytes: usize) -> Result<NormalizedFaceImage, FaceImageError> {
+    let format = detect_format(bytes)?;
+    if format == ImageFormat::Jpeg && bytes.len() <= max_bytes && !is_non_baseline_jpeg(bytes) {
+        let mut decoder = open_decoder(bytes, format)?;
+        let (width, height) = decoder.dimensions();
+        let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+        if orientation == Orientation::NoTransforms
+            && is_face_aspect(width, height)
+            && width <= FACE_TARGET_WIDTH
+            && height <= FACE_TARGET_HEIGHT
+        {
+            return Ok(NormalizedFaceImage {
+                bytes: bytes.to_vec(),
+                width,
+                height,
+                quality: None,
+            });
+        }
+    }
+    normalize_face_image(bytes, max_bytes)
+}
+
+/// Decode, orient, crop to the face and encode as baseline JPEG no larger than `max_bytes`.
+pub fn normalize_face_image(bytes: &[u8], max_bytes: usize) -> Result<NormalizedFaceImage, FaceImageError> {
+    if bytes.len() > MAX_SOURCE_IMAGE_BYTES {
+        return Err(FaceImageError::TooLarge {
+            size: bytes.len(),
+            max: MAX_SOURCE_IMAGE_BYTES,
+        });
+    }
+    let format = detect_format(bytes)?;
+    let mut decoder = open_decoder(bytes, format)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image =
+        DynamicImage::from_decoder(decoder).map_err(|err| FaceImageError::Decode(err.to_string()))?;
+    image.apply_orientation(orientation);
+
+    let rgb = flatten_to_rgb(image);
+    let (x, y, width, height) = face_crop(&rgb);
+    let cropped = image::imageops::crop_imm(&rgb, x, y, width, height).to_image();
+    encode_within_limit(cropped, max_bytes)
+}
+
+/// Drop alpha by compositing over white (transparent PNG backgrounds turn black otherwise).
+fn flatten_to_rgb(image: DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.into_rgb8();
+    }
+    let rgba = image.into_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn is_skin(r: u8, g: u8, b: u8) -> bool {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let cb = 128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b;
+    let cr = 128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+    y > 40.0 && (77.0..=127.0).contains(&cb) && (133.0..=173.0).contains(&cr)
+}
+
+/// Connected skin-coloured regions, largest first. A rough stand-in for face detection that
+/// works on the well-lit, frontal portraits used for registration.
+pub fn skin_regions(image: &RgbImage) -> Vec<SkinRegion> {
+    let (src_w, src_h) = image.dimensions();
+    if src_w == 0 || src_h == 0 {
+        return Vec::new();
+    }
+    let scale = (ANALYSIS_WIDTH as f32 / src_w as f32).min(1.0);
+    let w = ((src_w as f32 * scale).round() as u32).max(1);
+    let h = ((src_h as f32 * scale).round() as u32).max(1);
+    let small = if scale < 1.0 {
+        image::imageops::resize(image, w, h, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+
+    let mask: Vec<bool> = small.pixels().map(|p| is_skin(p[0], p[1], p[2])).collect();
+    let mut seen = vec![false; mask.len()];
+    let mut regions = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        stack.push(start);
+        let (mut min_x, mut min_y, mut max_x, mut max_y, mut count) = (w, h, 0u32, 0u32, 0u32);
+        while let Some(index) = stack.pop() {
+            let (x, y) = ((index as u32) % w, (index as u32) / w);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+            count += 1;
+            let neighbours = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < w).then(|| index + 1),
+                (y > 0).then(|| index - w as usize),
+                (y + 1 < h).then(|| index + w as usize),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if mask[next] && !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        let to_src = |v: u32| ((v as f32 / scale).round() as u32).min(src_w.max(src_h));
+        let x = to_src(min_x).min(src_w - 1);
+        let y = to_src(min_y).min(src_h - 1);
+        regions.push(SkinRegion {
+            x,
+            y,
+            width: (to_src(max_x + 1).min(src_w) - x).max(1),
+            height: (to_src(max_y + 1).min(src_h) - y).max(1),
+            pixels: count,
+        });
+    }
+    regions.sort_by_key(|region| std::cmp::Reverse(region.pixels));
+    regions
+}
+
+/// Skin regions that look like a face: big enough, roughly upright oval, not the whole frame.
+/// Largest first.
+pub fn face_like_regions(image: &RgbImage) -> Vec<SkinRegion> {
+    let (width, height) = image.dimensions();
+    let min_side = (width.min(height) / 10).max(8);
+    skin_regions(image)
+        .into_iter()
+        .filter(|region| {
+            let ratio = region.height as f32 / region.width as f32;
+            region.width >= min_side
+                && region.height >= min_side
+                && (0.8..=2.6).contains(&ratio)
+                && (region.width as u64 * region.height as u64) < (width as u64 * height as u64) * 9 / 10
+        })
+        .collect()
+}
+
+pub fn find_face_region(image: &RgbImage) -> Option<SkinRegion> {
+    face_like_regions(image).into_iter().next()
+}
+
+/// Crop rectangle `(x, y, width, height)` at the face aspect ratio.
+///
+/// Centred on the detected face with room for hair and chin; without a face it falls back to the
+/// largest 3:4 rectangle, horizontally centred and biased towards the top of the photo.
+pub fn face_crop(image: &RgbImage) -> (u32, u32, u32, u32) {
+    let (img_w, img_h) = image.dimensions();
+    let fit = |w: f32, h: f32| -> (f32, f32) {
+        let shrink = (img_w as f32 / w).min(img_h as f32 / h).min(1.0);
+        (w * shrink, h * shrink)
+    };
+
+    let (crop_w, crop_h, center_x, center_y) = match find_face_region(image) {
+        Some(face) => {
+            // Skin boxes usually cover forehead to chin (sometimes neck); add margin for hair.
+            let height = (face.height as f32 * 1.8).max(face.width as f32 * 1.6 * FACE_ASPECT_HEIGHT as f32
+                / FACE_ASPECT_WIDTH as f32);
+            let width = height * FACE_ASPECT_WIDTH as f32 / FACE_ASPECT_HEIGHT as f32;
+            let (width, height) = fit(width, height);
+            let center_x = face.x as f32 + face.width as f32 / 2.0;
+            let center_y = face.y as f32 + face.height as f32 * 0.45;
+            (width, height, center_x, center_y)
+        }
+        None => {
+            let (width, height) = fit(
+                img_w as f32,
+                img_w as f32 * FACE_ASPECT_HEIGHT as f32 / FACE_ASPECT_WIDTH as f32,
+            );
+            let slack = img_h as f32 - height;
+            (width, height, img_w as f32 / 2.0, height / 2.0 + slack / 4.0)
+        }
+    };
+
+    let width = (crop_w.round() as u32).clamp(1, img_w);
+    let height = (crop_h.round() as u32).clamp(1, img_h);
+    let x = (center_x - width as f32 / 2.0).round().clamp(0.0, (img_w - width) as f32) as u32;
+    let y = (center_y - height as f32 / 2.0).round().clamp(0.0, (img_h - height) as f32) as u32;
+    (x, y, width, height)
+}
+
+fn encode_jpeg(image: &RgbImage, quality: u8) -> Result<Vec<u8>, FaceImageError> {
+    let mut bytes = Vec::new();
+    // The encoder only writes baseline (non-progressive) JPEG, which is what devices accept.
+    JpegEncoder::new_with_quality(&mut bytes, quality)
+        .encode_image(image)
+        .map_err(|err| FaceImageError::Decode(err.to_string()))?;
+    Ok(bytes)
+}
+
+/// Resize to the target size, then step quality (and, as a last resort, size) down until it fits.
+fn encode_within_limit(image: RgbImage, max_bytes: usize) -> Result<NormalizedFaceImage, FaceImageError> {
+    let scale = (FACE_TARGET_WIDTH as f32 / image.width() as f32)
+        .min(FACE_TARGET_HEIGHT as f32 / image.height() as f32)
+        .min(1.0);
+    let mut width = ((image.width() as f32 * scale).round() as u32).max(1);
+    let mut smallest = usize::MAX;
+    loop {
+        let height = ((width as f32 * image.height() as f32 / image.width() as f32).round() as u32).max(1);
+        let resized = if width == image.width() {
+            image.clone()
+        } else {
+            image::imageops::resize(&image, width, height, FilterType::Lanczos3)
+        };
+        for quality in JPEG_QUALITY_STEPS {
+            let bytes = encode_jpeg(&resized, quality)?;
+            if bytes.len() <= max_bytes {
+                return Ok(NormalizedFaceImage {
+                    bytes,
+                    width,
+                    height,
+                    quality: Some(quality),
+                });
+            }
+            smallest = smallest.min(bytes.len());
+        }
+        let next = (width as f32 * DOWNSCALE_STEP) as u32;
+        if next < MIN_OUTPUT_WIDTH {
+            return Err(FaceImageError::TooLarge {
+                size: smallest,
+                max: max_bytes,
+            });
+        }
+        width = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbaImage};
+
+    fn noise_image(width: u32, height: u32) -> RgbImage {
+        let mut seed = 0x2545_f491_u32;
+        RgbImage::from_fn(width, height, |_, _| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let [r, g, b, _] = seed.to_le_bytes();
+            Rgb([r, g, b])
+        })
+    }
+
+    fn jpeg_bytes(image: &RgbImage) -> Vec<u8> {
+        encode_jpeg(image, 90).unwrap()
+    }
+
+    /// Insert an EXIF APP1 segment carrying only the orientation tag right after SOI.
+    fn with_exif_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut tiff = b"MM\x00\x2a\x00\x00\x00\x08\x00\x01\x01\x12\x00\x03\x00\x00\x00\x01".to_vec();
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        segment.extend_from_slice(b"Exif\x00\x00");
+        segment.extend_from_slice(&tiff);
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&segment);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn large_photo_is_resized_and_fits_limit() {
+        let source = jpeg_bytes(&noise_image(1800, 2400));
+        assert!(source.len() > 200 * 1024);
+
+        let face = normalize_face_image(&source, 200 * 1024).unwrap();
+
+        assert!(face.bytes.len() <= 200 * 1024);
+        assert!(face.bytes.starts_with(&[0xFF, 0xD8, 0xFF]));
+        assert!(face.width <= FACE_TARGET_WIDTH && face.height <= FACE_TARGET_HEIGHT);
+        assert!(is_face_aspect(face.width, face.height));
+        assert!(face.quality.is_some());
+    }
+
+    #[test]
+    fn transparent_png_becomes_jpeg_on_white() {
+        let png = DynamicImage::ImageRgba8(RgbaImage::from_pixel(300, 400, image::Rgba([0, 0, 0, 0])));
+        let mut bytes = Vec::new();
+        png.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png).unwrap();
+
+        let face = prepare_face_image(&bytes, 200 * 1024).unwrap();
+        let decoded = image::load_from_memory(&face.bytes).unwrap().into_rgb8();
+
+        assert_eq!((face.width, face.height), (300, 400));
+        assert!(decoded.get_pixel(150, 200).0.iter().all(|c| *c > 240));
+    }
+
+    #[test]
+    fn exif_orientation_is_applied_before_cropping() {
+        // Stored landscape, EXIF says "rotate 90° clockwise" -> portrait 3:4, no crop needed.
+        let source = with_exif_orientation(&jpeg_bytes(&RgbImage::from_pixel(400, 300, Rgb([90, 90, 200]))), 6);
+
+        let face = prepare_face_image(&source, 200 * 1024).unwrap();
+
+        assert_eq!((face.width, face.height), (300, 400));
+    }
+
+    #[test]
+    fn normalized_jpeg_passes_through_unchanged() {
+        let first = normalize_face_image(&jpeg_bytes(&noise_image(800, 600)), 200 * 1024).unwrap();
+        let second = prepare_face_image(&first.bytes, 200 * 1024).unwrap();
+
+        assert_eq!(second.bytes, first.bytes);
+        assert_eq!(second.quality, None);
+    }
+
+    #[test]
+    fn progressive_jpeg_is_not_passed_through() {
+        let baseline = normalize_face_image(&jpeg_bytes(&noise_image(300, 400)), 200 * 1024).unwrap().bytes;
+        assert!(!is_non_baseline_jpeg(&baseline));
+
+        // Same file with its SOF0 frame header relabelled as SOF2 (progressive).
+        let sof = baseline.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+        let mut progressive = baseline.clone();
+        progressive[sof + 1] = 0xC2;
+        assert!(is_non_baseline_jpeg(&progressive));
+        let prepared = prepare_face_image(&progressive, 200 * 1024);
+        assert!(!matches!(prepared, Ok(ref image) if image.quality.is_none()));
+    }
+
+    #[test]
+    fn crop_follows_the_face() {
+        // Skin-toned oval on the right side of a blue landscape photo.
+        let image = RgbImage::from_fn(1200, 800, |x, y| {
+            let (dx, dy) = ((x as f32 - 900.0) / 120.0, (y as f32 - 350.0) / 160.0);
+            if dx * dx + dy * dy <= 1.0 {
+                Rgb([224, 172, 140])
+            } else {
+                Rgb([40, 70, 160])
+            }
+        });
+
+        let face = find_face_region(&image).unwrap();
+        let (x, y, width, height) = face_crop(&image);
+
+        assert!(face.x > 700 && face.x < 820);
+        assert!(is_face_aspect(width, height));
+        assert!(x <= 780 && x + width >= 1020);
+        assert!(y <= 190 && y + height >= 510);
+    }
+
+    #[test]
+    fn heic_is_rejected_with_its_brand() {
+        let mut heic = vec![0, 0, 0, 24];
+        heic.extend_from_slice(b"ftypheic\x00\x00\x00\x00mif1heic");
+
+        assert_eq!(
+            prepare_face_image(&heic, 200 * 1024).unwrap_err(),
+            FaceImageError::Unsupported("HEIC".to_string())
+        );
+    }
+}
@@ -0,0 +1,274 @@
+// Offline face photo quality check
+//
+// Runs on the normalized JPEG before any device is touched, so a bad photo is rejected up front
+// instead of failing `FaceDataRecord` halfway through a registration (and forcing a rollback).
+// Thresholds are deliberately lenient: they catch photos the devices refuse or cannot match.
+// Face counting is a skin-tone heuristic and only produces warnings.
+
+use image::{GrayImage, RgbImage};
+
+use super::face_image::{face_like_regions, FaceImageError};
+use crate::shared::error::{current_locale, Locale};
+use crate::types::{FaceQualityIssue, FaceQualityIssueCode, FaceQualityReport, FaceRegion};
+
+pub const MIN_FACE_WIDTH: u32 = 180;
+pub const MIN_FACE_HEIGHT: u32 = 240;
+/// Variance of the Laplacian below this is treated as out of focus / motion blur.
+pub const MIN_SHARPNESS: f64 = 40.0;
+pub const MIN_BRIGHTNESS: f64 = 50.0;
+pub const MAX_BRIGHTNESS: f64 = 215.0;
+pub const MIN_CONTRAST: f64 = 20.0;
+/// A second face-like region counts as another face when it is at least this share of the first.
+const SECOND_FACE_RATIO: f64 = 0.35;
+
+/// Decode `bytes` (normally the output of `prepare_face_image`) and assess it.
+pub fn assess_face_image(bytes: &[u8]) -> Result<FaceQualityReport, FaceImageError> {
+    let image = image::load_from_memory(bytes).map_err(|err| FaceImageError::Decode(err.to_string()))?;
+    Ok(assess_face_quality(&image.into_rgb8(), current_locale()))
+}
+
+pub fn assess_face_quality(image: &RgbImage, locale: Locale) -> FaceQualityReport {
+    let (width, height) = image.dimensions();
+    let gray = image::imageops::grayscale(image);
+    let sharpness = laplacian_variance(&gray);
+    let (_, contrast) = luma_stats(&gray, 0, 0, width, height);
+
+    let faces = face_like_regions(image);
+    let face = faces.first().copied();
+    let face_count = match face {
+        Some(first) => faces
+            .iter()
+            .filter(|region| region.pixels as f64 >= first.pixels as f64 * SECOND_FACE_RATIO)
+            .count(),
+        None => 0,
+    };
+    let brightness = match face {
+        Some(face) => luma_stats(&gray, face.x, face.y, face.width, face.height).0,
+        None => luma_stats(&gray, 0, 0, width, height).0,
+    };
+
+    let finding = |code: FaceQualityIssueCode, value: f64, threshold: f64| FaceQualityIssue {
+        code,
+        message: issue_message(code, locale).to_string(),
+        value,
+        threshold,
+    };
+    let mut issues = Vec::new();
+    let mut issue = |code, value, threshold| issues.push(finding(code, value, threshold));
+    if width < MIN_FACE_WIDTH || height < MIN_FACE_HEIGHT {
+        issue(
+            FaceQualityIssueCode::LowResolution,
+            width.min(height) as f64,
+            MIN_FACE_WIDTH.min(MIN_FACE_HEIGHT) as f64,
+        );
+    }
+    if sharpness < MIN_SHARPNESS {
+        issue(FaceQualityIssueCode::Blurry, sharpness, MIN_SHARPNESS);
+    }
+    if brightness < MIN_BRIGHTNESS {
+        issue(FaceQualityIssueCode::TooDark, brightness, MIN_BRIGHTNESS);
+    } else if brightness > MAX_BRIGHTNESS {
+        issue(FaceQualityIssueCode::TooBright, brightness, MAX_BRIGHTNESS);
+    }
+    if contrast < MIN_CONTRAST {
+        issue(FaceQualityIssueCode::LowContrast, contrast, MIN_CONTRAST);
+    }
+    let warnings = match face_count {
+        0 => vec![finding(FaceQualityIssueCode::NoFace, 0.0, 1.0)],
+        1 => Vec::new(),
+        count => vec![finding(FaceQualityIssueCode::MultipleFaces, count as f64, 1.0)],
+    };
+
+    FaceQualityReport {
+        ok: issues.is_empty(),
+        width,
+        height,
+        sharpness,
+        brightness,
+        contrast,
+        face_count,
+        face: face.map(|face| FaceRegion {
+            x: face.x,
+            y: face.y,
+            width: face.width,
+            height: face.height,
+        }),
+        issues,
+        warnings,
+    }
+}
+
+/// Variance of the 4-neighbour Laplacian over the interior pixels.
+fn laplacian_variance(gray: &GrayImage) -> f64 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    let at = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let (mut sum, mut sum_sq, mut count) = (0.0, 0.0, 0.0);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let value = at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            sum += value;
+            sum_sq += value * value;
+            count += 1.0;
+        }
+    }
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+/// Mean and standard deviation of luma inside a rectangle.
+fn luma_stats(gray: &GrayImage, x: u32, y: u32, width: u32, height: u32) -> (f64, f64) {
+    let (mut sum, mut sum_sq, mut count) = (0.0, 0.0, 0.0);
+    for py in y..(y + height).min(gray.height()) {
+        for px in x..(x + width).min(gray.width()) {
+            let value = gray.get_pixel(px, py)[0] as f64;
+            sum += value;
+            sum_sq += value * value;
+            count += 1.0;
+        }
+    }
+    if count == 0.0 {
+        return (0.0, 0.0);
+    }
+    let mean = sum / count;
+    (mean, (sum_sq / count - mean * mean).max(0.0).sqrt())
+}
+
+/// Operator hints as (uz, ru, en).
+fn issue_message(code: FaceQualityIssueCode, locale: Locale) -> &'static str {
+    let (uz, ru, en) = match code {
+        FaceQualityIssueCode::LowResolution => (
+            "Rasm o'lchami juda kichik, yaqinroqdan qayta suratga oling",
+            "Слишком маленькое фото, сфотографируйте ближе",
+            "Photo resolution is too low, take it closer",
+        ),
+        FaceQualityIssueCode::Blurry => (
+            "Rasm xira, kamerani qimirlatmasdan qayta suratga oling",
+            "Фото размыто, переснимите без движения камеры",
+            "Photo is blurred, retake it holding the camera still",
+        ),
+        FaceQualityIssueCode::TooDark => (
+            "Yuz juda qorong'i, yorug'roq joyda suratga oling",
+            "Лицо слишком тёмное, снимите при лучшем освещении",
+            "Face is too dark, retake in better light",
+        ),
+        FaceQualityIssueCode::TooBright => (
+            "Yuz juda yorug' (oqarib ketgan), chaqnoqsiz suratga oling",
+            "Лицо пересвечено, снимите без вспышки",
+            "Face is overexposed, retake without flash",
+        ),
+        FaceQualityIssueCode::LowContrast => (
+            "Rasm kontrasti past, fon va yorug'likni o'zgartiring",
+            "Низкий контраст, смените фон или освещение",
+            "Contrast is too low, change the background or lighting",
+        ),
+        FaceQualityIssueCode::NoFace => (
+            "Rasmda yuz topilmadi",
+            "Лицо на фото не найдено",
+            "No face found in the photo",
+        ),
+        FaceQualityIssueCode::MultipleFaces => (
+            "Rasmda bir nechta yuz bor, faqat o'quvchi tushsin",
+            "На фото несколько лиц, оставьте только ученика",
+            "More than one face in the photo, only the student should be visible",
+        ),
+    };
+    match locale {
+        Locale::Uz => uz,
+        Locale::Ru => ru,
+        Locale::En => en,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const SKIN: [u8; 3] = [224, 172, 140];
+
+    /// Portrait on a textured background with a skin-toned oval per `(cx, cy)` centre.
+    fn portrait(width: u32, height: u32, faces: &[(f32, f32)], background: u8) -> RgbImage {
+        let radius_x = width as f32 * 0.22;
+        let radius_y = height as f32 * 0.25;
+        RgbImage::from_fn(width, height, |x, y| {
+            let inside = faces.iter().any(|(cx, cy)| {
+                let (dx, dy) = ((x as f32 - cx) / radius_x, (y as f32 - cy) / radius_y);
+                dx * dx + dy * dy <= 1.0
+            });
+            if inside {
+                Rgb(SKIN)
+            } else if (x / 4 + y / 4) % 2 == 0 {
+                Rgb([background / 3, background / 2, background])
+            } else {
+                Rgb([10, 20, 40])
+            }
+        })
+    }
+
+    fn codes(report: &FaceQualityReport) -> Vec<FaceQualityIssueCode> {
+        report.issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn clear_single_face_passes() {
+        let report = assess_face_quality(&portrait(480, 640, &[(240.0, 280.0)], 200), Locale::En);
+
+        assert!(report.ok, "{:?}", report.issues);
+        assert_eq!(report.face_count, 1);
+        assert!(report.face.is_some());
+    }
+
+    #[test]
+    fn flat_dark_photo_reports_every_problem() {
+        let report = assess_face_quality(&RgbImage::from_pixel(120, 160, Rgb([20, 20, 20])), Locale::Uz);
+
+        assert!(!report.ok);
+        assert_eq!(
+            codes(&report),
+            [
+                FaceQualityIssueCode::LowResolution,
+                FaceQualityIssueCode::Blurry,
+                FaceQualityIssueCode::TooDark,
+                FaceQualityIssueCode::LowContrast,
+            ]
+        );
+        assert_eq!(report.warnings[0].code, FaceQualityIssueCode::NoFace);
+        assert_eq!(report.warnings[0].message, "Rasmda yuz topilmadi");
+    }
+
+    #[test]
+    fn two_faces_are_a_warning() {
+        let image = portrait(640, 480, &[(160.0, 240.0), (480.0, 240.0)], 200);
+
+        let report = assess_face_quality(&image, Locale::En);
+
+        assert_eq!(report.face_count, 2);
+        assert!(report.ok, "face count alone must not block: {:?}", report.issues);
+        assert_eq!(report.warnings[0].code, FaceQualityIssueCode::MultipleFaces);
+    }
+
+    #[test]
+    fn grayscale_photo_without_skin_tones_is_not_rejected() {
+        let color = portrait(480, 640, &[(240.0, 280.0)], 200);
+        let gray = image::DynamicImage::ImageLuma8(image::imageops::grayscale(&color)).into_rgb8();
+
+        let report = assess_face_quality(&gray, Locale::En);
+
+        assert!(report.ok, "{:?}", report.issues);
+        assert_eq!(report.warnings[0].code, FaceQualityIssueCode::NoFace);
+    }
+
+    #[test]
+    fn blurred_photo_is_flagged() {
+        let sharp = portrait(480, 640, &[(240.0, 280.0)], 200);
+        let blurred = image::imageops::blur(&sharp, 6.0);
+
+        let report = assess_face_quality(&blurred, Locale::En);
+
+        assert!(codes(&report).contains(&FaceQualityIssueCode::Blurry));
+        assert!(report.sharpness < assess_face_quality(&sharp, Locale::En).sharpness);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod face_image;
+pub mod face_quality;
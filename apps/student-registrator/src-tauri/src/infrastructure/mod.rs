@@ -1,2 +1,6 @@
 pub mod backend;
+pub mod discovery;
+pub mod import;
+pub mod media;
+pub mod relay;
 pub mod storage;
@@ -0,0 +1,3 @@
+mod http;
+pub mod webhook_capture;
+pub mod webhook_relay;
@@ -0,0 +1,113 @@
+// Webhook capture
+//
+// Short-lived listener for `test_device_webhook`: records every request whatever its path and
+// answers `200`, so the device counts its test post as delivered.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Request, Response, StatusCode};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+
+use super::http::{json_response, read_body, request_content_type, request_path, serve_http};
+use super::webhook_relay::MAX_RELAY_BODY_BYTES;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRequest {
+    pub method: String,
+    pub path: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+    pub received_at: Instant,
+}
+
+pub struct WebhookCapture {
+    local_addr: SocketAddr,
+    requests: mpsc::UnboundedReceiver<CapturedRequest>,
+    shutdown: watch::Sender<bool>,
+}
+
+async fn capture_request(
+    requests: mpsc::UnboundedSender<CapturedRequest>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let received_at = Instant::now();
+    let method = req.method().to_string();
+    let path = request_path(&req);
+    let content_type = request_content_type(&req);
+    let body = match read_body(req.into_body(), MAX_RELAY_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(status) => return Ok(json_response(status, serde_json::json!({ "error": status.to_string() }))),
+    };
+    let _ = requests.send(CapturedRequest {
+        method,
+        path,
+        content_type,
+        body,
+        received_at,
+    });
+    Ok(json_response(StatusCode::OK, serde_json::json!({ "ok": true })))
+}
+
+impl WebhookCapture {
+    pub async fn start(listen: SocketAddr) -> Result<Self, String> {
+        let listener = TcpListener::bind(listen)
+            .await
+            .map_err(|e| format!("cannot listen on {}: {}", listen, e))?;
+        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let (sender, requests) = mpsc::unbounded_channel();
+        let shutdown = watch::channel(false).0;
+        tokio::spawn(serve_http(listener, shutdown.subscribe(), move |req| {
+            capture_request(sender.clone(), req)
+        }));
+        Ok(Self {
+            local_addr,
+            requests,
+            shutdown,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Next request received, waiting at most `timeout`.
+    pub async fn next_request(&mut self, timeout: Duration) -> Option<CapturedRequest> {
+        tokio::time::timeout(timeout, self.requests.recv()).await.ok().flatten()
+    }
+}
+
+impl Drop for WebhookCapture {
+    fn drop(&mut self) {
+        self.shutdown.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn capture_records_the_request_and_answers_ok() {
+        let mut capture = WebhookCapture::start(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        assert_eq!(capture.next_request(Duration::from_millis(20)).await, None);
+
+        let res = reqwest::Client::new()
+            .post(format!("http://{}/webhook/s1/in?secret=abc", capture.local_addr()))
+            .header("Content-Type", "application/xml")
+            .body("<EventNotificationAlert/>")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+
+        let captured = capture.next_request(Duration::from_secs(2)).await.unwrap();
+        assert_eq!(
+            (captured.method.as_str(), captured.path.as_str(), captured.content_type.as_deref()),
+            ("POST", "/webhook/s1/in?secret=abc", Some("application/xml"))
+        );
+        assert_eq!(captured.body, b"<EventNotificationAlert/>");
+    }
+}
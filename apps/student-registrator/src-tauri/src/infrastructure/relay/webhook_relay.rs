@@ -0,0 +1,444 @@
+// Webhook relay (store-and-forward for schools the VPS cannot reach)
+//
+// Devices post their webhooks to a listener in this process instead of the VPS. Only POSTs to a
+// registered route (the exact `/webhook/<schoolId>/<in|out>?secret=..` path a device was synced
+// to) are accepted; each is written to `relay_queue` before the device gets its answer, and the
+// queue is capped so an outage cannot fill the disk. A forwarder then sends the queue to the
+// backend in arrival order with the same path and query, body and content type. While the
+// backend is unreachable, times out or answers 5xx, forwarding backs off exponentially; a
+// permanent 4xx moves the webhook to `FAILED` so it does not block the rest.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::{Body, Method, Request, Response, StatusCode};
+use tokio::net::TcpListener;
+use tokio::sync::{watch, Notify};
+
+use super::http::{json_response, read_body, request_content_type, request_path, serve_http};
+
+use crate::domain::entities::{is_permanent_rejection, relay_retry_delay_ms, RelayStatus};
+use crate::infrastructure::storage::relay_queue::{
+    enqueue_relay_webhook, next_relay_webhook, record_relay_failure, relay_queue_counts, remove_relay_webhook,
+    RelayQueueLimits, RelayedWebhook,
+};
+use crate::infrastructure::storage::sqlite_store::SqliteStore;
+
+/// Device webhooks carry one capture; anything bigger is not a device event.
+pub const MAX_RELAY_BODY_BYTES: usize = 5 * 1024 * 1024;
+pub const RELAY_PATH_PREFIX: &str = "/webhook/";
+
+#[derive(Debug, Clone)]
+pub struct RelayOptions {
+    pub listen: SocketAddr,
+    /// Backend base URL; the device's request path is appended to it.
+    pub backend_url: String,
+    /// Path and query of every synced device webhook; other paths are refused.
+    pub routes: HashSet<String>,
+    pub queue_limits: RelayQueueLimits,
+    pub retry_base: Duration,
+    pub retry_max: Duration,
+    pub forward_timeout: Duration,
+}
+
+impl RelayOptions {
+    pub fn new(listen: SocketAddr, backend_url: &str) -> Self {
+        Self {
+            listen,
+            backend_url: backend_url.trim().trim_end_matches('/').to_string(),
+            routes: HashSet::new(),
+            queue_limits: RelayQueueLimits {
+                max_rows: 20_000,
+                max_bytes: 1024 * 1024 * 1024,
+            },
+            retry_base: Duration::from_secs(2),
+            retry_max: Duration::from_secs(600),
+            forward_timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RelayProgress {
+    delivered: u64,
+    last_error: Option<String>,
+    retry_in_ms: Option<u64>,
+}
+
+struct RelayShared {
+    store: &'static SqliteStore,
+    options: RelayOptions,
+    routes: Mutex<HashSet<String>>,
+    /// Woken for every stored webhook so an idle forwarder starts at once.
+    queued: Notify,
+    progress: Mutex<RelayProgress>,
+}
+
+impl RelayShared {
+    fn new(store: &'static SqliteStore, options: RelayOptions) -> Self {
+        Self {
+            store,
+            routes: Mutex::new(options.routes.clone()),
+            options,
+            queued: Notify::new(),
+            progress: Mutex::new(RelayProgress::default()),
+        }
+    }
+
+    fn progress(&self, update: impl FnOnce(&mut RelayProgress)) {
+        if let Ok(mut progress) = self.progress.lock() {
+            update(&mut progress);
+        }
+    }
+
+    fn is_route(&self, path: &str) -> bool {
+        self.routes.lock().map(|routes| routes.contains(path)).unwrap_or(false)
+    }
+}
+
+/// A running relay: listener plus forwarder. Both stop on `stop` or when this is dropped; the
+/// queue stays in the database for the next start.
+pub struct WebhookRelay {
+    local_addr: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    shared: Arc<RelayShared>,
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl WebhookRelay {
+    pub async fn start(store: &'static SqliteStore, options: RelayOptions) -> Result<Self, String> {
+        let listener = TcpListener::bind(options.listen)
+            .await
+            .map_err(|e| format!("cannot listen on {}: {}", options.listen, e))?;
+        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let client = reqwest::Client::builder()
+            .timeout(options.forward_timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let shutdown = watch::channel(false).0;
+        let shared = Arc::new(RelayShared::new(store, options));
+        let receiver = shared.clone();
+        let listener_task = tokio::spawn(serve_http(listener, shutdown.subscribe(), move |req| {
+            receive_webhook(receiver.clone(), req)
+        }));
+        let forwarder_task = tokio::spawn(forward_webhooks(client, shared.clone(), shutdown.subscribe()));
+        Ok(Self {
+            local_addr,
+            shutdown,
+            shared,
+            tasks: vec![listener_task, forwarder_task],
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept webhooks posted to `route` (path and query) from now on.
+    pub fn allow_route(&self, route: &str) {
+        if let Ok(mut routes) = self.shared.routes.lock() {
+            routes.insert(route.to_string());
+        }
+    }
+
+    /// Forward again without waiting for the next webhook, e.g. after failed ones were requeued.
+    pub fn wake(&self) {
+        self.shared.queued.notify_one();
+    }
+
+    pub fn stop(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Stop and wait until the listener has released its port and the forwarder has let go of
+    /// the queue, so a new relay can take over both.
+    pub async fn shutdown(mut self) {
+        self.stop();
+        for task in self.tasks.drain(..) {
+            let _ = task.await;
+        }
+    }
+
+    pub fn status(&self) -> RelayStatus {
+        let (pending, failed) = relay_queue_counts(self.shared.store).unwrap_or_default();
+        let progress = self.shared.progress.lock();
+        let progress = progress.as_deref().ok();
+        RelayStatus {
+            running: true,
+            listen_port: Some(self.local_addr.port()),
+            backend_url: Some(self.shared.options.backend_url.clone()),
+            pending,
+            failed,
+            delivered: progress.map(|p| p.delivered).unwrap_or(0),
+            last_error: progress.and_then(|p| p.last_error.clone()),
+            retry_in_ms: progress.and_then(|p| p.retry_in_ms),
+        }
+    }
+}
+
+impl Drop for WebhookRelay {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response<Body> {
+    json_response(status, serde_json::json!({ "error": error }))
+}
+
+async fn receive_webhook(shared: Arc<RelayShared>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST || !req.uri().path().starts_with(RELAY_PATH_PREFIX) {
+        return Ok(error_response(StatusCode::NOT_FOUND, "Not found"));
+    }
+    let path = request_path(&req);
+    if !shared.is_route(&path) {
+        // Not a synced device (or a stale secret); the path itself is not logged, it holds one.
+        eprintln!("relay: webhook for an unknown route refused");
+        return Ok(error_response(StatusCode::FORBIDDEN, "Unknown webhook route"));
+    }
+    let content_type = request_content_type(&req);
+    let body = match read_body(req.into_body(), MAX_RELAY_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(status) => return Ok(error_response(status, &status.to_string())),
+    };
+    match enqueue_relay_webhook(shared.store, &path, content_type.as_deref(), &body, shared.options.queue_limits) {
+        Ok(Some(_)) => {
+            shared.queued.notify_one();
+            Ok(json_response(StatusCode::OK, serde_json::json!({ "ok": true, "queued": true })))
+        }
+        Ok(None) => {
+            shared.progress(|p| p.last_error = Some("relay queue is full".to_string()));
+            Ok(error_response(StatusCode::SERVICE_UNAVAILABLE, "queue full"))
+        }
+        Err(err) => {
+            eprintln!("relay: webhook not stored: {}", err);
+            Ok(error_response(StatusCode::SERVICE_UNAVAILABLE, "queue unavailable"))
+        }
+    }
+}
+
+enum ForwardError {
+    /// Backend refused the webhook for good.
+    Rejected(String),
+    /// Backend unreachable or temporarily failing; worth retrying.
+    Unavailable(String),
+}
+
+async fn forward_webhook(client: &reqwest::Client, backend_url: &str, webhook: &RelayedWebhook) -> Result<(), ForwardError> {
+    let mut req = client
+        .post(format!("{}{}", backend_url, webhook.path))
+        .body(webhook.body.clone());
+    if let Some(content_type) = webhook.content_type.as_deref() {
+        req = req.header(reqwest::header::CONTENT_TYPE, content_type);
+    }
+    let res = req.send().await.map_err(|e| ForwardError::Unavailable(e.to_string()))?;
+    let status = res.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = res.text().await.unwrap_or_default();
+    let message = format!("HTTP {}: {}", status.as_u16(), body.trim().chars().take(300).collect::<String>());
+    if is_permanent_rejection(status.as_u16()) {
+        Err(ForwardError::Rejected(message))
+    } else {
+        Err(ForwardError::Unavailable(message))
+    }
+}
+
+/// Forward the oldest pending webhook and record the outcome; `None` when the queue is empty.
+/// Only the HTTP call awaits, so dropping this mid-way leaves the webhook queued.
+async fn forward_next(client: &reqwest::Client, shared: &RelayShared) -> Option<Result<(), ForwardError>> {
+    let outcome = match next_relay_webhook(shared.store) {
+        Ok(None) => return None,
+        Ok(Some(webhook)) => {
+            let outcome = forward_webhook(client, &shared.options.backend_url, &webhook).await;
+            let recorded = match &outcome {
+                Ok(()) => remove_relay_webhook(shared.store, webhook.id),
+                Err(ForwardError::Rejected(reason)) => record_relay_failure(shared.store, webhook.id, reason, true),
+                Err(ForwardError::Unavailable(reason)) => record_relay_failure(shared.store, webhook.id, reason, false),
+            };
+            // A webhook whose outcome cannot be written would be sent again forever.
+            recorded.map_err(ForwardError::Unavailable).and(outcome)
+        }
+        Err(err) => Err(ForwardError::Unavailable(err)),
+    };
+    match &outcome {
+        Ok(()) => shared.progress(|p| {
+            p.delivered += 1;
+            p.last_error = None;
+        }),
+        Err(ForwardError::Rejected(reason)) => {
+            eprintln!("relay: webhook refused by backend: {}", reason);
+            shared.progress(|p| p.last_error = Some(reason.clone()));
+        }
+        Err(ForwardError::Unavailable(reason)) => shared.progress(|p| p.last_error = Some(reason.clone())),
+    }
+    Some(outcome)
+}
+
+async fn forward_webhooks(client: reqwest::Client, shared: Arc<RelayShared>, mut shutdown: watch::Receiver<bool>) {
+    let mut failures: u32 = 0;
+    loop {
+        if *shutdown.borrow() {
+            return;
+        }
+        let outcome = tokio::select! {
+            _ = shutdown.wait_for(|stop| *stop) => return,
+            outcome = forward_next(&client, &shared) => outcome,
+        };
+        match outcome {
+            None => {
+                tokio::select! {
+                    _ = shutdown.wait_for(|stop| *stop) => return,
+                    _ = shared.queued.notified() => {}
+                }
+            }
+            Some(Ok(())) | Some(Err(ForwardError::Rejected(_))) => failures = 0,
+            Some(Err(ForwardError::Unavailable(_))) => {
+                failures += 1;
+                let delay = relay_retry_delay_ms(
+                    failures,
+                    shared.options.retry_base.as_millis() as u64,
+                    shared.options.retry_max.as_millis() as u64,
+                );
+                shared.progress(|p| p.retry_in_ms = Some(delay));
+                tokio::select! {
+                    _ = shutdown.wait_for(|stop| *stop) => return,
+                    _ = tokio::time::sleep(Duration::from_millis(delay)) => {}
+                }
+                shared.progress(|p| p.retry_in_ms = None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::server::conn::Http;
+    use hyper::service::service_fn;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// What the mock backend accepted: path, content type, body.
+    type Accepted = Arc<Mutex<Vec<(String, String, Vec<u8>)>>>;
+
+    /// Stand-in for the VPS: refuses `/webhook/bad/..` with 403, answers the first `outages`
+    /// other requests with 503 and accepts the rest.
+    async fn spawn_mock_backend(outages: usize) -> (String, Accepted) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let accepted: Accepted = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::new(AtomicUsize::new(0));
+        let log = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (log, seen) = (log.clone(), seen.clone());
+                let service = service_fn(move |req: Request<Body>| {
+                    let (log, seen) = (log.clone(), seen.clone());
+                    async move {
+                        let path = req.uri().path_and_query().unwrap().to_string();
+                        let content_type = req
+                            .headers()
+                            .get(hyper::header::CONTENT_TYPE)
+                            .map(|v| v.to_str().unwrap().to_string())
+                            .unwrap_or_default();
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap().to_vec();
+                        let status = if path.starts_with("/webhook/bad/") {
+                            StatusCode::FORBIDDEN
+                        } else if seen.fetch_add(1, Ordering::SeqCst) < outages {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        } else {
+                            log.lock().unwrap().push((path, content_type, body));
+                            StatusCode::OK
+                        };
+                        Ok::<_, Infallible>(json_response(status, serde_json::json!({ "ok": status == StatusCode::OK })))
+                    }
+                });
+                tokio::spawn(Http::new().serve_connection(stream, service));
+            }
+        });
+        (url, accepted)
+    }
+
+    async fn post(shared: &Arc<RelayShared>, path: &str, body: &'static str) -> StatusCode {
+        let req = Request::post(path)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        receive_webhook(shared.clone(), req).await.unwrap().status()
+    }
+
+    fn step_name(outcome: Option<Result<(), ForwardError>>) -> &'static str {
+        match outcome {
+            None => "empty",
+            Some(Ok(())) => "delivered",
+            Some(Err(ForwardError::Rejected(_))) => "rejected",
+            Some(Err(ForwardError::Unavailable(_))) => "unavailable",
+        }
+    }
+
+    #[tokio::test]
+    async fn webhooks_are_stored_then_forwarded_in_order_through_outages() {
+        let (backend_url, accepted) = spawn_mock_backend(2).await;
+        let store: &'static SqliteStore = Box::leak(Box::new(SqliteStore::open_in_memory().unwrap()));
+        let mut options = RelayOptions::new(SocketAddr::from(([127, 0, 0, 1], 0)), &backend_url);
+        options.routes = ["/webhook/s1/in?secret=abc", "/webhook/bad/in", "/webhook/s1/out"]
+            .map(str::to_string)
+            .into();
+        options.queue_limits.max_rows = 3;
+        let shared = Arc::new(RelayShared::new(store, options));
+
+        assert_eq!(post(&shared, "/webhook/s1/in?secret=abc", r#"{"serialNo":1}"#).await, StatusCode::OK);
+        assert_eq!(post(&shared, "/webhook/bad/in", r#"{"serialNo":2}"#).await, StatusCode::OK);
+        assert_eq!(post(&shared, "/webhook/s1/in?secret=wrong", r#"{"serialNo":0}"#).await, StatusCode::FORBIDDEN);
+        assert_eq!(post(&shared, "/webhook/s1/out", r#"{"serialNo":3}"#).await, StatusCode::OK);
+        assert_eq!(post(&shared, "/webhook/s1/out", r#"{"serialNo":4}"#).await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(relay_queue_counts(store).unwrap(), (3, 0));
+
+        let client = reqwest::Client::new();
+        let mut steps = Vec::new();
+        loop {
+            let step = step_name(forward_next(&client, &shared).await);
+            steps.push(step);
+            if step == "empty" {
+                break;
+            }
+        }
+        assert_eq!(steps, ["unavailable", "unavailable", "delivered", "rejected", "delivered", "empty"]);
+
+        let accepted = accepted.lock().unwrap().clone();
+        let paths: Vec<&str> = accepted.iter().map(|(path, _, _)| path.as_str()).collect();
+        assert_eq!(paths, ["/webhook/s1/in?secret=abc", "/webhook/s1/out"]);
+        assert_eq!(accepted[0].1, "application/json");
+        assert_eq!(accepted[0].2, br#"{"serialNo":1}"#);
+
+        assert_eq!(relay_queue_counts(store).unwrap(), (0, 1));
+        let progress = shared.progress.lock().unwrap();
+        assert_eq!(progress.delivered, 2);
+        assert!(progress.last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn listener_refuses_other_requests_and_shutdown_stops_both_tasks() {
+        let store: &'static SqliteStore = Box::leak(Box::new(SqliteStore::open_in_memory().unwrap()));
+        let options = RelayOptions::new(SocketAddr::from(([127, 0, 0, 1], 0)), "http://127.0.0.1:9");
+        let relay = WebhookRelay::start(store, options).await.unwrap();
+        let relay_url = format!("http://{}", relay.local_addr());
+
+        let device = reqwest::Client::new();
+        let not_webhook = device.get(format!("{}/webhook/s1/in", relay_url)).send().await.unwrap();
+        assert_eq!(not_webhook.status(), 404);
+        let unknown = device.post(format!("{}/webhook/s1/in", relay_url)).send().await.unwrap();
+        assert_eq!(unknown.status(), 403);
+        relay.allow_route("/webhook/s1/in");
+        let known = device.post(format!("{}/webhook/s1/in", relay_url)).send().await.unwrap();
+        assert_eq!(known.status(), 200);
+
+        let listen = relay.local_addr();
+        tokio::time::timeout(Duration::from_secs(5), relay.shutdown()).await.unwrap();
+        TcpListener::bind(listen).await.unwrap();
+    }
+}
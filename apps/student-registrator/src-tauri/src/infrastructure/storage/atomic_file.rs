@@ -0,0 +1,27 @@
+// Crash-safe file writes for the JSON files kept next to the database
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Temp file + fsync + rename, so a crash leaves either the old or the new file.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let tmp = temp_path(path);
+    let mut file = File::create(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(content).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    drop(file);
+    fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        // Persist the rename itself.
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+// Encrypted device bundle (`*.srdevices`)
+//
+// Moves device profiles, passwords included, between registrator installations. The file is
+// JSON: a plain header (format, KDF parameters, nonce) and the AES-256-GCM sealed payload. The
+// key comes from the operator's passphrase via PBKDF2-HMAC-SHA256; the header is bound to the
+// ciphertext as associated data. The payload has its own version (`BundlePayload`), apart
+// from the legacy `devices.json` schema, so the export format does not depend on the importer
+// of old device files.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::domain::entities::DeviceConfig;
+
+const BUNDLE_FORMAT: &str = "student-registrator/devices";
+const BUNDLE_VERSION: u32 = 1;
+/// Version of the sealed `BundlePayload`; bump it when `DeviceConfig` changes incompatibly.
+const PAYLOAD_VERSION: u32 = 1;
+const KDF_NAME: &str = "PBKDF2-HMAC-SHA256";
+const KDF_ITERATIONS: u32 = 600_000;
+/// Bundles asking for fewer are refused as tampered; more would let a file stall the app.
+const KDF_ITERATIONS_RANGE: std::ops::RangeInclusive<u32> = 100_000..=10_000_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BundleKdf {
+    name: String,
+    iterations: u32,
+    salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BundleHeader {
+    format: String,
+    version: u32,
+    #[serde(rename = "exportedAt")]
+    exported_at: String,
+    kdf: BundleKdf,
+    nonce: String,
+}
+
+/// What the ciphertext holds. Fields added to `DeviceConfig` later must have serde defaults so
+/// older payloads keep parsing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BundlePayload {
+    #[serde(rename = "payloadVersion")]
+    payload_version: u32,
+    devices: Vec<DeviceConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeviceBundle {
+    #[serde(flatten)]
+    header: BundleHeader,
+    ciphertext: String,
+}
+
+fn pbkdf2_sha256(passphrase: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase, salt, iterations, &mut key);
+    key
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+fn associated_data(header: &BundleHeader) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(header).map_err(|e| e.to_string())
+}
+
+fn seal_with_iterations(devices: &[DeviceConfig], passphrase: &str, iterations: u32) -> Result<String, String> {
+    check_passphrase(passphrase)?;
+    let payload = BundlePayload {
+        payload_version: PAYLOAD_VERSION,
+        devices: devices
+            .iter()
+            .cloned()
+            .map(|mut device| {
+                device.password_ref = None;
+                device.pending_password_ref = None;
+                device
+            })
+            .collect(),
+    };
+    let plaintext = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let header = BundleHeader {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        kdf: BundleKdf {
+            name: KDF_NAME.to_string(),
+            iterations,
+            salt: STANDARD.encode(salt),
+        },
+        nonce: STANDARD.encode(nonce),
+    };
+
+    let key = pbkdf2_sha256(passphrase.as_bytes(), &salt, iterations);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let aad = associated_data(&header)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &aad })
+        .map_err(|e| e.to_string())?;
+
+    serde_json::to_string_pretty(&DeviceBundle {
+        header,
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Encrypt `devices` (with their resolved passwords) into bundle file content.
+pub fn seal_device_bundle(devices: &[DeviceConfig], passphrase: &str) -> Result<String, String> {
+    seal_with_iterations(devices, passphrase, KDF_ITERATIONS)
+}
+
+/// Decrypt bundle file content. A wrong passphrase and a modified file look the same.
+pub fn open_device_bundle(content: &str, passphrase: &str) -> Result<Vec<DeviceConfig>, String> {
+    let bundle: DeviceBundle =
+        serde_json::from_str(content).map_err(|e| format!("not a device bundle: {}", e))?;
+    let header = &bundle.header;
+    if header.format != BUNDLE_FORMAT {
+        return Err("not a device bundle".to_string());
+    }
+    if header.version > BUNDLE_VERSION {
+        return Err(format!(
+            "bundle version {} is newer than this app supports ({}); update the app",
+            header.version, BUNDLE_VERSION
+        ));
+    }
+    if header.kdf.name != KDF_NAME || !KDF_ITERATIONS_RANGE.contains(&header.kdf.iterations) {
+        return Err("unsupported key derivation parameters".to_string());
+    }
+    let salt = STANDARD.decode(&header.kdf.salt).map_err(|e| e.to_string())?;
+    let nonce = STANDARD.decode(&header.nonce).map_err(|e| e.to_string())?;
+    if nonce.len() != NONCE_LEN {
+        return Err("bundle nonce is malformed".to_string());
+    }
+    let ciphertext = STANDARD.decode(&bundle.ciphertext).map_err(|e| e.to_string())?;
+
+    let key = pbkdf2_sha256(passphrase.as_bytes(), &salt, header.kdf.iterations);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let aad = associated_data(header)?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|_| "wrong passphrase or damaged bundle".to_string())?;
+
+    let payload: BundlePayload =
+        serde_json::from_slice(&plaintext).map_err(|e| format!("bundle payload is unreadable: {}", e))?;
+    if payload.payload_version > PAYLOAD_VERSION {
+        return Err(format!(
+            "bundle payload version {} is newer than this app supports ({}); update the app",
+            payload.payload_version, PAYLOAD_VERSION
+        ));
+    }
+    Ok(payload.devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::device::test_device;
+    use crate::domain::entities::DeviceDirection;
+
+    // Low count so the debug-build test stays fast; still inside the accepted range.
+    const TEST_ITERATIONS: u32 = 100_000;
+
+    fn device() -> DeviceConfig {
+        DeviceConfig {
+            backend_id: Some("b1".to_string()),
+            host: "10.0.0.5".to_string(),
+            password: "Sup3r-secret".to_string(),
+            password_ref: Some("file:d1".to_string()),
+            credentials_lifetime_days: Some(90),
+            scheme: Some("http".to_string()),
+            door_count: 2,
+            labels: vec!["gate".to_string()],
+            direction: DeviceDirection::In,
+            ..test_device("d1")
+        }
+    }
+
+    #[test]
+    fn pbkdf2_matches_rfc7914_vector() {
+        let key = pbkdf2_sha256(b"passwd", b"salt", 1);
+        assert_eq!(
+            key[..16],
+            [0x55, 0xac, 0x04, 0x6e, 0x56, 0xe3, 0x08, 0x9f, 0xec, 0x16, 0x91, 0xc2, 0x25, 0x44, 0xb6, 0x05]
+        );
+        let key = pbkdf2_sha256(b"Password", b"NaCl", 80_000);
+        assert_eq!(
+            key,
+            [
+                0x4d, 0xdc, 0xd8, 0xf6, 0x0b, 0x98, 0xbe, 0x21, 0x83, 0x0c, 0xee, 0x5e, 0xf2, 0x27, 0x01, 0xf9,
+                0x64, 0x1a, 0x44, 0x18, 0xd0, 0x4c, 0x04, 0x14, 0xae, 0xff, 0x08, 0x87, 0x6b, 0x34, 0xab, 0x56,
+            ]
+        );
+    }
+
+    #[test]
+    fn bundle_round_trips_and_rejects_wrong_passphrase_or_tampering() {
+        let content = seal_with_iterations(&[device()], "correct horse", TEST_ITERATIONS).unwrap();
+        assert!(!content.contains("Sup3r-secret"));
+
+        let devices = open_device_bundle(&content, "correct horse").unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].password, "Sup3r-secret");
+        assert_eq!(devices[0].password_ref, None);
+        assert_eq!(devices[0].direction, DeviceDirection::In);
+
+        assert!(open_device_bundle(&content, "wrong horse").is_err());
+        let tampered = content.replace("\"version\": 1", "\"version\": 0");
+        assert!(open_device_bundle(&tampered, "correct horse").is_err());
+        assert!(seal_device_bundle(&[device()], "short").is_err());
+    }
+
+    #[test]
+    fn payload_is_versioned_on_its_own() {
+        let payload = BundlePayload {
+            payload_version: PAYLOAD_VERSION,
+            devices: vec![device()],
+        };
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["payloadVersion"], PAYLOAD_VERSION);
+        assert!(value.get("schemaVersion").is_none());
+    }
+}
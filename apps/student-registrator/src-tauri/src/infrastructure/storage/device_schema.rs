@@ -0,0 +1,151 @@
+// devices.json schema and migrations
+//
+// The file is an envelope `{ schemaVersion, devices, settings }`. Older files are upgraded one
+// version at a time on read (v0 -> v1 -> v2 ...). Devices now live in SQLite (`sqlite_store`);
+// this is only used to import the last `devices.json` an older build left behind.
+//
+// History:
+// - v0: bare `[DeviceConfig]` array
+// - v1: envelope with `settings`
+// - v2: `scheme` always set; `doorCount`, `labels`, `direction`, `schoolId` added
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::domain::entities::DeviceConfig;
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Step `i` upgrades a document from version `i` to `i + 1`.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; CURRENT_SCHEMA_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceStoreFile {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    pub devices: Vec<DeviceConfig>,
+    /// App-wide settings kept next to the devices.
+    pub settings: Map<String, Value>,
+}
+
+fn schema_version(document: &Value) -> Result<u32, String> {
+    match document {
+        Value::Array(_) => Ok(0),
+        Value::Object(map) => map
+            .get("schemaVersion")
+            .and_then(Value::as_u64)
+            .map(|version| version as u32)
+            .ok_or_else(|| "schemaVersion is missing".to_string()),
+        _ => Err("expected a device list or a versioned document".to_string()),
+    }
+}
+
+/// Version of a document written by a newer build. Such a file is read-only for this build:
+/// it is neither migrated down nor replaced by an older backup.
+pub fn newer_schema_version(content: &str) -> Option<u32> {
+    let document: Value = serde_json::from_str(content).ok()?;
+    schema_version(&document).ok().filter(|version| *version > CURRENT_SCHEMA_VERSION)
+}
+
+/// Parse any known version and upgrade it to the current one.
+///
+/// Returns the version the file was written with alongside the upgraded document.
+pub fn parse_device_store(content: &str) -> Result<(u32, DeviceStoreFile), String> {
+    let mut document: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let from_version = schema_version(&document)?;
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "schemaVersion {} is newer than this app supports ({})",
+            from_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    for migrate in MIGRATIONS.iter().skip(from_version as usize) {
+        document = migrate(document)?;
+    }
+    let store = serde_json::from_value(document).map_err(|e| e.to_string())?;
+    Ok((from_version, store))
+}
+
+fn device_objects(document: &mut Value) -> impl Iterator<Item = &mut Map<String, Value>> {
+    document
+        .get_mut("devices")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+fn migrate_v0_to_v1(document: Value) -> Result<Value, String> {
+    Ok(json!({
+        "schemaVersion": 1,
+        "devices": document,
+        "settings": {},
+    }))
+}
+
+fn migrate_v1_to_v2(mut document: Value) -> Result<Value, String> {
+    for device in device_objects(&mut document) {
+        if !matches!(device.get("scheme"), Some(Value::String(_))) {
+            device.insert("scheme".to_string(), json!("http"));
+        }
+        device.entry("doorCount").or_insert(json!(1));
+        device.entry("labels").or_insert(json!([]));
+        device.entry("direction").or_insert(json!("BOTH"));
+        device.entry("schoolId").or_insert(Value::Null);
+    }
+    document["schemaVersion"] = json!(2);
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::DeviceDirection;
+
+    #[test]
+    fn bare_array_is_upgraded_through_every_step() {
+        let legacy = r#"[{"id": "d1", "host": "10.0.0.5", "port": 80, "username": "admin", "password": "x"}]"#;
+
+        let (from_version, store) = parse_device_store(legacy).unwrap();
+
+        assert_eq!(from_version, 0);
+        assert_eq!(store.schema_version, CURRENT_SCHEMA_VERSION);
+        let device = &store.devices[0];
+        assert_eq!(device.scheme.as_deref(), Some("http"));
+        assert_eq!(device.door_count, 1);
+        assert!(device.labels.is_empty());
+        assert_eq!(device.direction, DeviceDirection::Both);
+        assert!(store.settings.is_empty());
+    }
+
+    #[test]
+    fn current_document_is_read_as_is() {
+        let current = r#"{"schemaVersion": 2, "settings": {"theme": "dark"}, "devices": [
+            {"id": "d1", "host": "h", "port": 443, "username": "u", "scheme": "https",
+             "doorCount": 2, "labels": ["gate"], "direction": "IN", "schoolId": "s1"}]}"#;
+
+        let (from_version, store) = parse_device_store(current).unwrap();
+
+        assert_eq!(from_version, 2);
+        assert_eq!(store.devices[0].door_count, 2);
+        assert_eq!(store.devices[0].direction, DeviceDirection::In);
+        assert_eq!(store.settings["theme"], "dark");
+        assert_eq!(store.devices[0].labels, ["gate"]);
+    }
+
+    #[test]
+    fn newer_document_is_refused_even_when_it_would_parse() {
+        let newer = r#"{"schemaVersion": 3, "settings": {}, "devices": [], "groups": []}"#;
+
+        assert!(parse_device_store(newer).is_err());
+        assert_eq!(newer_schema_version(newer), Some(3));
+        assert_eq!(newer_schema_version(r#"{"schemaVersion": 2}"#), None);
+    }
+
+    #[test]
+    fn unversioned_objects_are_rejected() {
+        assert!(parse_device_store(r#"{"devices": []}"#).is_err());
+        assert!(parse_device_store("42").is_err());
+    }
+}
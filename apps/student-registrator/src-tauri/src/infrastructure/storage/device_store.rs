@@ -1,95 +1,191 @@
-// Device storage (local JSON file)
+// Legacy device storage (`devices.json`), imported once into the SQLite store
+//
+// Older builds kept devices in `devices.json` (layout and migrations in `device_schema`) with
+// rotating `devices.json.bak.1..N` copies. On first start with the database the file is read,
+// deduped and written into the `devices` / `settings` tables in one transaction, then renamed to
+// `devices.json.imported` so it is never picked up again. An unreadable file falls back to the
+// newest backup that parses, instead of silently starting over with no devices; with no readable
+// backup either, nothing is imported and the file stays for the next start. A file written by a
+// newer build is not imported at all (and not replaced by an older backup).
 
-use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::domain::entities::DeviceConfig;
+use chrono::Utc;
 
-fn get_storage_path() -> PathBuf {
-    let data_dir = dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."));
-    let app_dir = data_dir.join("student-registrator");
-    fs::create_dir_all(&app_dir).ok();
-    app_dir.join("devices.json")
-}
+use super::device_schema::{newer_schema_version, parse_device_store, DeviceStoreFile};
+use super::secret_store::delete_device_password;
+use super::sqlite_devices::{prepare_devices, write_devices};
+use super::sqlite_store::{get_meta, set_meta, set_setting, SqliteStore};
 
-pub fn load_devices() -> Vec<DeviceConfig> {
-    let path = get_storage_path();
-    if !path.exists() {
-        return Vec::new();
-    }
-    
-    let content = match fs::read_to_string(&path) {
-        Ok(c) => c,
-        Err(_) => return Vec::new(),
-    };
-    
-    let devices: Vec<DeviceConfig> = serde_json::from_str(&content).unwrap_or_default();
-    let original_len = devices.len();
-    let deduped = dedupe_devices(devices);
-    let mut needs_canonical_save = deduped.len() != original_len;
-    if !needs_canonical_save {
-        if let Ok(canonical) = serde_json::to_string_pretty(&deduped) {
-            needs_canonical_save = canonical.trim() != content.trim();
-        }
-    }
-    if needs_canonical_save {
-        let _ = save_devices(&deduped);
-    }
-    deduped
+const MAX_BACKUPS: usize = 5;
+const IMPORTED_META_KEY: &str = "legacy_devices_json_imported_at";
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
 }
 
-pub fn save_devices(devices: &[DeviceConfig]) -> Result<(), String> {
-    let path = get_storage_path();
-    let content = serde_json::to_string_pretty(devices)
-        .map_err(|e| e.to_string())?;
-    fs::write(&path, content)
-        .map_err(|e| e.to_string())?;
-    Ok(())
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    sibling_path(path, &format!(".bak.{}", index))
 }
 
-pub fn get_device_by_id(device_id: &str) -> Option<DeviceConfig> {
-    let devices = load_devices();
-    devices.into_iter().find(|d| d.id == device_id)
+/// Read and parse the file. `Ok(None)` when it does not exist.
+fn parse_devices_file(path: &Path) -> Result<Option<DeviceStoreFile>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+    parse_device_store(&content).map(|(_, store)| Some(store))
 }
 
-fn normalize(value: &str) -> String {
-    value.trim().to_lowercase()
+/// The file, or the newest readable backup when the file itself does not parse. Fails when the
+/// file was written by a newer build, or when neither it nor any backup can be read.
+fn read_legacy_devices(path: &Path) -> Result<Option<DeviceStoreFile>, String> {
+    let reason = match parse_devices_file(path) {
+        Ok(stored) => return Ok(stored),
+        Err(reason) => reason,
+    };
+    if let Some(version) = fs::read_to_string(path).ok().as_deref().and_then(newer_schema_version) {
+        return Err(format!(
+            "devices.json was written by a newer app version (schemaVersion {}); update the app",
+            version
+        ));
+    }
+    eprintln!("devices.json is unreadable ({})", reason);
+    let restored = (1..=MAX_BACKUPS).find_map(|index| {
+        let backup = backup_path(path, index);
+        let stored = parse_devices_file(&backup).ok().flatten()?;
+        eprintln!("devices.json restored from {}", backup.display());
+        Some(stored)
+    });
+    restored
+        .map(Some)
+        .ok_or_else(|| format!("devices.json and its backups are unreadable ({})", reason))
 }
 
-fn dedupe_key(device: &DeviceConfig) -> String {
-    if let Some(backend_id) = device.backend_id.as_ref() {
-        if !backend_id.trim().is_empty() {
-            return format!("backend:{}", normalize(backend_id));
-        }
+/// Import `devices.json` into `store` unless that already happened. Returns how many devices
+/// were imported.
+pub fn import_legacy_devices_file(store: &SqliteStore, path: &Path) -> Result<usize, String> {
+    if get_meta(&store.connection(), IMPORTED_META_KEY)?.is_some() {
+        return Ok(0);
     }
-    if let Some(device_id) = device.device_id.as_ref() {
-        if !device_id.trim().is_empty() {
-            return format!("device:{}", normalize(device_id));
+    let legacy = read_legacy_devices(path)?;
+    // Passwords go to the secret store before the transaction starts.
+    let legacy = legacy
+        .map(|legacy| prepare_devices(legacy.devices).map(|devices| (devices, legacy.settings)))
+        .transpose()?;
+
+    let (imported, orphaned) = store.write(|tx| {
+        let Some((devices, settings)) = legacy.as_ref() else {
+            set_meta(tx, IMPORTED_META_KEY, &Utc::now().to_rfc3339())?;
+            return Ok((0, Vec::new()));
+        };
+        let count = devices.len();
+        let orphaned = write_devices(tx, devices)?;
+        for (key, value) in settings {
+            set_setting(tx, key, value)?;
+        }
+        set_meta(tx, IMPORTED_META_KEY, &Utc::now().to_rfc3339())?;
+        Ok((count, orphaned))
+    })?;
+    orphaned.iter().for_each(|reference| delete_device_password(reference));
+
+    if path.exists() {
+        let archived = sibling_path(path, ".imported");
+        if let Err(err) = fs::rename(path, &archived) {
+            eprintln!("devices.json imported but not renamed: {}", err);
         }
     }
-    format!(
-        "endpoint:{}:{}:{}",
-        normalize(&device.host),
-        device.port,
-        normalize(&device.username),
-    )
+    Ok(imported)
 }
 
-fn dedupe_devices(devices: Vec<DeviceConfig>) -> Vec<DeviceConfig> {
-    let mut deduped: Vec<DeviceConfig> = Vec::new();
-    let mut index_by_key: HashMap<String, usize> = HashMap::new();
-
-    for device in devices {
-        let key = dedupe_key(&device);
-        if let Some(index) = index_by_key.get(&key).copied() {
-            deduped[index] = device;
-        } else {
-            index_by_key.insert(key, deduped.len());
-            deduped.push(device);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::repositories::DeviceRepository;
+
+    fn temp_store() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sr-devices-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("devices.json")
+    }
+
+    #[test]
+    fn legacy_file_is_imported_once_with_duplicates_merged() {
+        let path = temp_store();
+        fs::write(
+            &path,
+            r#"{"schemaVersion": 1, "settings": {"locale": "uz"}, "devices": [
+                {"id": "d1", "host": "10.0.0.1", "port": 80, "username": "admin"},
+                {"id": "d2", "host": "10.0.0.2", "port": 80, "username": "admin"},
+                {"id": "d3", "host": " 10.0.0.1", "port": 80, "username": "ADMIN"}]}"#,
+        )
+        .unwrap();
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        assert_eq!(import_legacy_devices_file(&store, &path).unwrap(), 2);
+
+        let devices = store.list_devices().unwrap();
+        assert_eq!(devices.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["d3", "d2"]);
+        assert_eq!(devices[1].door_count, 1);
+        let locale: String = store
+            .connection()
+            .query_row("SELECT value FROM settings WHERE key = 'locale'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(locale, "\"uz\"");
+        assert!(!path.exists());
+        assert!(sibling_path(&path, ".imported").exists());
+
+        fs::write(&path, r#"[{"id": "d9", "host": "h", "port": 80, "username": "u"}]"#).unwrap();
+        assert_eq!(import_legacy_devices_file(&store, &path).unwrap(), 0);
+        assert_eq!(store.list_devices().unwrap().len(), 2);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn corrupt_file_falls_back_to_newest_readable_backup() {
+        let path = temp_store();
+        fs::write(backup_path(&path, 1), "not json").unwrap();
+        fs::write(backup_path(&path, 2), r#"[{"id": "d1", "host": "10.0.0.1", "port": 80, "username": "admin"}]"#)
+            .unwrap();
+        fs::write(&path, "[{\"id\": \"d1\", \"host\": ").unwrap();
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        assert_eq!(import_legacy_devices_file(&store, &path).unwrap(), 1);
+        assert_eq!(store.list_devices().unwrap()[0].id, "d1");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
     }
 
-    deduped
+    #[test]
+    fn unreadable_file_without_backups_is_not_marked_imported() {
+        let path = temp_store();
+        fs::write(backup_path(&path, 1), "not json").unwrap();
+        fs::write(&path, "[{\"id\": \"d1\", \"host\": ").unwrap();
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        assert!(import_legacy_devices_file(&store, &path).is_err());
+        assert!(get_meta(&store.connection(), IMPORTED_META_KEY).unwrap().is_none());
+        assert!(path.exists());
+        assert!(!sibling_path(&path, ".imported").exists());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn file_from_a_newer_build_is_left_alone() {
+        let path = temp_store();
+        fs::write(backup_path(&path, 1), r#"[{"id": "old", "host": "10.0.0.1", "port": 80, "username": "admin"}]"#)
+            .unwrap();
+        let newer = r#"{"schemaVersion": 9, "settings": {}, "devices": [{"id": "d1"}]}"#;
+        fs::write(&path, newer).unwrap();
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        assert!(import_legacy_devices_file(&store, &path).unwrap_err().contains("newer"));
+        assert!(store.list_devices().unwrap().is_empty());
+        assert!(get_meta(&store.connection(), IMPORTED_META_KEY).unwrap().is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
 }
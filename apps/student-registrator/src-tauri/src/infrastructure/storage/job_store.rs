@@ -0,0 +1,267 @@
+// Clone job storage (`clone_jobs` table of the SQLite store)
+//
+// One row per job holding its JSON. Backend tokens of `BACKEND_TO_DEVICE` jobs are never
+// written there: `create_job` moves the token to the secret store under `clone-job:<jobId>`
+// once and the job keeps only `backendTokenRef`, so progress saves are plain row updates.
+// Builds before the database kept jobs in `clone_jobs.json`; that file is imported once and
+// renamed to `clone_jobs.json.imported`.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+use crate::domain::entities::{CloneJob, CloneJobSpec, CloneJobStatus};
+
+use super::secret_store::{delete_device_password, load_device_password, store_device_password};
+use super::sqlite_store::{app_store, get_meta, set_meta, SqliteStore};
+
+/// Finished jobs kept for the history list; running/paused jobs are never pruned.
+const MAX_FINISHED_JOBS: usize = 50;
+const IMPORTED_META_KEY: &str = "legacy_clone_jobs_json_imported_at";
+
+/// Jobs in the file; a missing file is an empty list, an unreadable one an error so that the
+/// import does not mark it done and the file stays for the next start.
+fn read_jobs_file(path: &Path) -> Result<Vec<CloneJob>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("{} is unreadable: {}", path.display(), e))
+}
+
+fn job_secret_id(job_id: &str) -> String {
+    format!("clone-job:{}", job_id)
+}
+
+/// Put a plaintext backend token into the secret store and keep only the reference.
+fn seal_backend_token(job: &mut CloneJob) -> Result<(), String> {
+    let CloneJobSpec::BackendToDevice {
+        backend_token: Some(token),
+        backend_token_ref,
+        ..
+    } = &mut job.spec
+    else {
+        return Ok(());
+    };
+    let reference = store_device_password(&job_secret_id(&job.id), backend_token_ref.as_deref(), token)?;
+    *backend_token_ref = Some(reference);
+    Ok(())
+}
+
+/// Drop the token of a job that can no longer run.
+fn release_backend_token(job: &mut CloneJob) {
+    if let CloneJobSpec::BackendToDevice {
+        backend_token,
+        backend_token_ref,
+        ..
+    } = &mut job.spec
+    {
+        *backend_token = None;
+        if let Some(reference) = backend_token_ref.take() {
+            delete_device_password(&reference);
+        }
+    }
+}
+
+fn status_name(status: CloneJobStatus) -> String {
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn upsert_job(conn: &Connection, job: &CloneJob) -> Result<(), String> {
+    let data = serde_json::to_string(job).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO clone_jobs (id, status, updated_at, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+            status = excluded.status, updated_at = excluded.updated_at, data = excluded.data",
+        params![job.id, status_name(job.status), job.updated_at, data],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+fn read_jobs(conn: &Connection) -> Result<Vec<CloneJob>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, data FROM clone_jobs ORDER BY seq")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut jobs = Vec::new();
+    for row in rows {
+        let (id, data) = row.map_err(|e| e.to_string())?;
+        jobs.push(serde_json::from_str(&data).map_err(|e| format!("clone job {}: {}", id, e))?);
+    }
+    Ok(jobs)
+}
+
+/// Delete the oldest finished jobs over the cap and return them.
+fn prune_finished_jobs(conn: &Connection) -> Result<Vec<CloneJob>, String> {
+    let finished: Vec<CloneJob> = read_jobs(conn)?
+        .into_iter()
+        .filter(|job| job.status.is_finished())
+        .collect();
+    let excess = finished.len().saturating_sub(MAX_FINISHED_JOBS);
+    let pruned: Vec<CloneJob> = finished.into_iter().take(excess).collect();
+    for job in &pruned {
+        conn.execute("DELETE FROM clone_jobs WHERE id = ?1", [&job.id])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(pruned)
+}
+
+/// Store a new job; its backend token goes to the secret store here, once.
+pub fn create_job(job: &mut CloneJob) -> Result<(), String> {
+    seal_backend_token(job)?;
+    app_store()?.write(|tx| upsert_job(tx, job))
+}
+
+/// Write the job's current state. Completed and cancelled jobs lose their backend token.
+pub fn save_job(job: &CloneJob) -> Result<(), String> {
+    write_job(app_store()?, job)
+}
+
+fn write_job(store: &SqliteStore, job: &CloneJob) -> Result<(), String> {
+    let mut stored = job.clone();
+    if matches!(stored.status, CloneJobStatus::Completed | CloneJobStatus::Cancelled) {
+        release_backend_token(&mut stored);
+    }
+    let pruned = store.write(|tx| {
+        upsert_job(tx, &stored)?;
+        if stored.status.is_finished() {
+            prune_finished_jobs(tx)
+        } else {
+            Ok(Vec::new())
+        }
+    })?;
+    for mut job in pruned {
+        release_backend_token(&mut job);
+    }
+    Ok(())
+}
+
+pub fn load_jobs() -> Result<Vec<CloneJob>, String> {
+    read_jobs(&app_store()?.connection())
+}
+
+pub fn get_job(job_id: &str) -> Result<Option<CloneJob>, String> {
+    Ok(load_jobs()?.into_iter().find(|job| job.id == job_id))
+}
+
+/// Backend token of a job: the in-memory one, or the stored one for a job read from disk.
+pub fn load_job_backend_token(job: &CloneJob) -> Result<Option<String>, String> {
+    match &job.spec {
+        CloneJobSpec::BackendToDevice {
+            backend_token: Some(token),
+            ..
+        } => Ok(Some(token.clone())),
+        CloneJobSpec::BackendToDevice {
+            backend_token_ref: Some(reference),
+            ..
+        } => load_device_password(reference).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Import `clone_jobs.json` into `store` unless that already happened. Tokens older files still
+/// carry are moved to the secret store on the way. Returns how many jobs were imported.
+pub fn import_legacy_jobs_file(store: &SqliteStore, path: &Path) -> Result<usize, String> {
+    if get_meta(&store.connection(), IMPORTED_META_KEY)?.is_some() {
+        return Ok(0);
+    }
+    let mut jobs = read_jobs_file(path)?;
+    for job in jobs.iter_mut() {
+        seal_backend_token(job)?;
+    }
+    store.write(|tx| {
+        for job in &jobs {
+            upsert_job(tx, job)?;
+        }
+        set_meta(tx, IMPORTED_META_KEY, &Utc::now().to_rfc3339())
+    })?;
+
+    if path.exists() {
+        let archived = path.with_file_name("clone_jobs.json.imported");
+        if let Err(err) = fs::rename(path, &archived) {
+            eprintln!("clone_jobs.json imported but not renamed: {}", err);
+        }
+    }
+    Ok(jobs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::job::test_clone_job;
+
+    fn temp_jobs_file() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("sr-jobs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("clone_jobs.json")
+    }
+
+    #[test]
+    fn legacy_jobs_file_is_imported_once_and_unreadable_files_are_kept() {
+        let path = temp_jobs_file();
+        let store = SqliteStore::open_in_memory().unwrap();
+        let content = serde_json::to_string(&[test_clone_job("j1", CloneJobStatus::Paused)]).unwrap();
+
+        // A crash mid-write used to leave a truncated file that read back as "no jobs".
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+        assert!(import_legacy_jobs_file(&store, &path).is_err());
+        assert!(get_meta(&store.connection(), IMPORTED_META_KEY).unwrap().is_none());
+        assert!(path.exists());
+
+        fs::write(&path, &content).unwrap();
+        assert_eq!(import_legacy_jobs_file(&store, &path).unwrap(), 1);
+        assert_eq!(read_jobs(&store.connection()).unwrap()[0].status, CloneJobStatus::Paused);
+        assert!(!path.exists());
+
+        fs::write(&path, &content).unwrap();
+        assert_eq!(import_legacy_jobs_file(&store, &path).unwrap(), 0);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn saves_update_in_place_and_only_finished_jobs_are_pruned() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        write_job(&store, &test_clone_job("running", CloneJobStatus::Running)).unwrap();
+        for index in 0..=MAX_FINISHED_JOBS {
+            write_job(&store, &test_clone_job(&format!("done{}", index), CloneJobStatus::Completed)).unwrap();
+        }
+        let mut running = test_clone_job("running", CloneJobStatus::Running);
+        running.processed = 7;
+        write_job(&store, &running).unwrap();
+
+        let jobs = read_jobs(&store.connection()).unwrap();
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS + 1);
+        assert_eq!((jobs[0].id.as_str(), jobs[0].processed), ("running", 7));
+        assert_eq!(jobs[1].id, "done1");
+    }
+
+    #[test]
+    fn backend_token_is_not_serialized() {
+        let mut job = test_clone_job("j1", CloneJobStatus::Running);
+        job.spec = CloneJobSpec::BackendToDevice {
+            backend_device_id: "d1".to_string(),
+            backend_url: "http://backend".to_string(),
+            backend_token: Some("secret-token".to_string()),
+            backend_token_ref: Some("file:clone-job:j1".to_string()),
+            school_id: "s1".to_string(),
+            limit: 10,
+        };
+        let json = serde_json::to_string(&job).unwrap();
+        assert!(!json.contains("secret-token"));
+        assert!(json.contains("\"backendTokenRef\":\"file:clone-job:j1\""));
+
+        // Older files still carry the token; it is read so it can be moved.
+        let legacy = json.replace("\"backendTokenRef\"", "\"backendToken\":\"old\",\"x\"");
+        let parsed: CloneJob = serde_json::from_str(&legacy).unwrap();
+        assert!(matches!(parsed.spec, CloneJobSpec::BackendToDevice { backend_token: Some(ref t), .. } if t == "old"));
+    }
+}
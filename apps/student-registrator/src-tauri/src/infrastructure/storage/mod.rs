@@ -1 +1,9 @@
+pub mod atomic_file;
+pub mod device_bundle;
+pub mod device_schema;
 pub mod device_store;
+pub mod job_store;
+pub mod relay_queue;
+pub mod secret_store;
+pub mod sqlite_devices;
+pub mod sqlite_store;
@@ -0,0 +1,170 @@
+// Relay queue on the SQLite store
+//
+// Webhooks received by the relay listener are written here before the device gets its answer,
+// so nothing is lost when the app or the PC stops before forwarding. Rows are forwarded oldest
+// first and deleted once the backend accepts them; rows the backend refused for good move to
+// `FAILED` and stay until they are retried. The queue is capped in rows and body bytes so a
+// backend that stays down for weeks cannot fill the disk.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::sqlite_store::SqliteStore;
+
+/// One webhook as the device posted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayedWebhook {
+    pub id: i64,
+    /// Path and query, e.g. `/webhook/<schoolId>/in?secret=..`.
+    pub path: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+    pub attempts: u32,
+}
+
+/// Upper bounds on what the queue keeps, `PENDING` and `FAILED` rows together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayQueueLimits {
+    pub max_rows: u64,
+    pub max_bytes: u64,
+}
+
+/// Store a webhook; `None` when it would take the queue over `limits`.
+pub fn enqueue_relay_webhook(
+    store: &SqliteStore,
+    path: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+    limits: RelayQueueLimits,
+) -> Result<Option<i64>, String> {
+    store.write(|tx| {
+        let (rows, bytes): (u64, u64) = tx
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(LENGTH(body)), 0) FROM relay_queue",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        if rows >= limits.max_rows || bytes.saturating_add(body.len() as u64) > limits.max_bytes {
+            return Ok(None);
+        }
+        tx.execute(
+            "INSERT INTO relay_queue (received_at, path, content_type, body) VALUES (?1, ?2, ?3, ?4)",
+            params![chrono::Utc::now().to_rfc3339(), path, content_type, body],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Some(tx.last_insert_rowid()))
+    })
+}
+
+/// Oldest webhook still waiting to be forwarded.
+pub fn next_relay_webhook(store: &SqliteStore) -> Result<Option<RelayedWebhook>, String> {
+    store
+        .connection()
+        .query_row(
+            "SELECT id, path, content_type, body, attempts FROM relay_queue
+             WHERE state = 'PENDING' ORDER BY id LIMIT 1",
+            [],
+            |row| {
+                Ok(RelayedWebhook {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    content_type: row.get(2)?,
+                    body: row.get(3)?,
+                    attempts: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+pub fn remove_relay_webhook(store: &SqliteStore, id: i64) -> Result<(), String> {
+    store.write(|tx| {
+        tx.execute("DELETE FROM relay_queue WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+/// Count a failed attempt; `give_up` moves the row out of the forwarding order.
+pub fn record_relay_failure(store: &SqliteStore, id: i64, error: &str, give_up: bool) -> Result<(), String> {
+    store.write(|tx| {
+        tx.execute(
+            "UPDATE relay_queue SET attempts = attempts + 1, last_error = ?2,
+             state = CASE WHEN ?3 THEN 'FAILED' ELSE state END WHERE id = ?1",
+            params![id, error, give_up],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+/// Put refused webhooks back in line (e.g. after the webhook secret was fixed).
+pub fn requeue_failed_relay_webhooks(store: &SqliteStore) -> Result<usize, String> {
+    store.write(|tx| {
+        tx.execute("UPDATE relay_queue SET state = 'PENDING' WHERE state = 'FAILED'", [])
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// `(pending, failed)` row counts.
+pub fn relay_queue_counts(store: &SqliteStore) -> Result<(u64, u64), String> {
+    store
+        .connection()
+        .query_row(
+            "SELECT COALESCE(SUM(state = 'PENDING'), 0), COALESCE(SUM(state = 'FAILED'), 0) FROM relay_queue",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_LIMIT: RelayQueueLimits = RelayQueueLimits {
+        max_rows: u64::MAX,
+        max_bytes: u64::MAX,
+    };
+
+    #[test]
+    fn webhooks_leave_the_queue_in_order_and_refused_ones_wait() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let first = enqueue_relay_webhook(&store, "/webhook/s1/in", Some("application/json"), b"{}", NO_LIMIT)
+            .unwrap()
+            .unwrap();
+        enqueue_relay_webhook(&store, "/webhook/s1/out", None, b"raw", NO_LIMIT).unwrap();
+
+        let head = next_relay_webhook(&store).unwrap().unwrap();
+        assert_eq!((head.id, head.path.as_str(), head.attempts), (first, "/webhook/s1/in", 0));
+        record_relay_failure(&store, first, "HTTP 503", false).unwrap();
+        assert_eq!(next_relay_webhook(&store).unwrap().unwrap().attempts, 1);
+
+        record_relay_failure(&store, first, "HTTP 403", true).unwrap();
+        let head = next_relay_webhook(&store).unwrap().unwrap();
+        assert_eq!((head.path.as_str(), head.body.as_slice()), ("/webhook/s1/out", &b"raw"[..]));
+        assert_eq!(relay_queue_counts(&store).unwrap(), (1, 1));
+
+        remove_relay_webhook(&store, head.id).unwrap();
+        assert_eq!(requeue_failed_relay_webhooks(&store).unwrap(), 1);
+        assert_eq!(next_relay_webhook(&store).unwrap().unwrap().id, first);
+        assert_eq!(relay_queue_counts(&store).unwrap(), (1, 0));
+    }
+
+    #[test]
+    fn a_full_queue_refuses_new_webhooks() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let by_rows = RelayQueueLimits { max_rows: 2, ..NO_LIMIT };
+        assert!(enqueue_relay_webhook(&store, "/webhook/s1/in", None, b"a", by_rows).unwrap().is_some());
+        let second = enqueue_relay_webhook(&store, "/webhook/s1/in", None, b"b", by_rows).unwrap().unwrap();
+        record_relay_failure(&store, second, "HTTP 403", true).unwrap();
+        assert_eq!(enqueue_relay_webhook(&store, "/webhook/s1/in", None, b"c", by_rows).unwrap(), None);
+
+        let by_bytes = RelayQueueLimits { max_bytes: 4, ..NO_LIMIT };
+        assert!(enqueue_relay_webhook(&store, "/webhook/s1/in", None, b"abc", by_bytes).unwrap().is_none());
+        remove_relay_webhook(&store, second).unwrap();
+        assert!(enqueue_relay_webhook(&store, "/webhook/s1/in", None, b"abc", by_bytes).unwrap().is_some());
+        assert_eq!(relay_queue_counts(&store).unwrap(), (2, 0));
+    }
+}
@@ -0,0 +1,276 @@
+// Device password storage (OS keyring, encrypted file fallback)
+//
+// `devices.json` only keeps a `passwordRef`; the password itself lives in the OS secret store
+// (Secret Service on Linux, Credential Manager on Windows, Keychain on macOS). Where no secret
+// store is reachable (headless Linux, locked session) passwords go to `device_secrets.json`,
+// encrypted with AES-256-GCM under a key derived from the machine id. Without a machine id the
+// file backend is refused rather than keyed on something guessable.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use super::atomic_file::write_atomic;
+
+const KEYRING_SERVICE: &str = "student-registrator";
+const KEYRING_PREFIX: &str = "keyring:";
+const FILE_PREFIX: &str = "file:";
+const KEY_CONTEXT: &str = "student-registrator/device-secrets/v1";
+const NONCE_LEN: usize = 12;
+
+/// Serializes read-modify-write cycles of the fallback secrets file.
+static SECRETS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Passwords already read or written in this process, by reference. `load_devices` runs on
+/// almost every command, so the secret store is only hit once per device and password change.
+fn secret_cache() -> &'static Mutex<HashMap<String, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_secret(reference: &str) -> Option<String> {
+    secret_cache().lock().ok()?.get(reference).cloned()
+}
+
+fn cache_secret(reference: &str, password: Option<&str>) {
+    if let Ok(mut cache) = secret_cache().lock() {
+        match password {
+            Some(password) => cache.insert(reference.to_string(), password.to_string()),
+            None => cache.remove(reference),
+        };
+    }
+}
+
+fn get_secrets_path() -> PathBuf {
+    let data_dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let app_dir = data_dir.join("student-registrator");
+    fs::create_dir_all(&app_dir).ok();
+    app_dir.join("device_secrets.json")
+}
+
+/// `DEVICE_SECRET_BACKEND=file` skips the OS keyring (e.g. kiosk PCs without a login keyring).
+fn keyring_enabled() -> bool {
+    let raw = std::env::var("DEVICE_SECRET_BACKEND")
+        .ok()
+        .or_else(|| std::env::var("VITE_DEVICE_SECRET_BACKEND").ok());
+    !matches!(raw.as_deref().map(str::trim), Some(value) if value.eq_ignore_ascii_case("file"))
+}
+
+fn keyring_entry(device_id: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("device:{}", device_id)).map_err(|e| e.to_string())
+}
+
+fn store_in_keyring(device_id: &str, password: &str) -> Result<String, String> {
+    if !keyring_enabled() {
+        return Err("disabled by DEVICE_SECRET_BACKEND".to_string());
+    }
+    keyring_entry(device_id)?
+        .set_password(password)
+        .map_err(|e| e.to_string())?;
+    Ok(format!("{}{}", KEYRING_PREFIX, device_id))
+}
+
+/// Store a device password and return the reference to keep in `devices.json`.
+///
+/// Skips the write when this process already stored or read the same password for the device.
+pub fn store_device_password(device_id: &str, current_ref: Option<&str>, password: &str) -> Result<String, String> {
+    let known_refs = [
+        format!("{}{}", KEYRING_PREFIX, device_id),
+        format!("{}{}", FILE_PREFIX, device_id),
+    ];
+    let unchanged = current_ref
+        .map(str::to_string)
+        .into_iter()
+        .chain(known_refs)
+        .find(|reference| cached_secret(reference).as_deref() == Some(password));
+    if let Some(reference) = unchanged {
+        return Ok(reference);
+    }
+
+    let reference = match store_in_keyring(device_id, password) {
+        Ok(reference) => reference,
+        Err(err) => {
+            if keyring_enabled() {
+                eprintln!("device {}: OS keyring unavailable, using encrypted file: {}", device_id, err);
+            }
+            let _guard = SECRETS_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            write_file_secret(&get_secrets_path(), &machine_key()?, device_id, Some(password))?;
+            format!("{}{}", FILE_PREFIX, device_id)
+        }
+    };
+
+    // Moving between backends leaves the old copy behind; drop it.
+    if let Some(previous) = current_ref.filter(|previous| *previous != reference) {
+        delete_device_password(previous);
+    }
+    cache_secret(&reference, Some(password));
+    Ok(reference)
+}
+
+pub fn load_device_password(reference: &str) -> Result<String, String> {
+    if let Some(password) = cached_secret(reference) {
+        return Ok(password);
+    }
+    let password = if let Some(device_id) = reference.strip_prefix(KEYRING_PREFIX) {
+        keyring_entry(device_id)?.get_password().map_err(|e| e.to_string())?
+    } else if let Some(device_id) = reference.strip_prefix(FILE_PREFIX) {
+        let _guard = SECRETS_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        read_file_secret(&get_secrets_path(), &machine_key()?, device_id)?
+    } else {
+        return Err(format!("unknown password reference: {}", reference));
+    };
+    cache_secret(reference, Some(&password));
+    Ok(password)
+}
+
+/// Best effort: a secret that cannot be removed is only an orphan, not a failure.
+pub fn delete_device_password(reference: &str) {
+    cache_secret(reference, None);
+    let result = if let Some(device_id) = reference.strip_prefix(KEYRING_PREFIX) {
+        keyring_entry(device_id).and_then(|entry| match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err.to_string()),
+        })
+    } else if let Some(device_id) = reference.strip_prefix(FILE_PREFIX) {
+        let _guard = SECRETS_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        machine_key().and_then(|key| write_file_secret(&get_secrets_path(), &key, device_id, None))
+    } else {
+        Ok(())
+    };
+    if let Err(err) = result {
+        eprintln!("password {} not removed: {}", reference, err);
+    }
+}
+
+/// AES-256 key bound to this machine: the file is useless when copied to another PC. Fails
+/// when the OS gives no machine id; a key from anything else (user name, paths) could be rebuilt
+/// by whoever holds the file.
+fn machine_key() -> Result<[u8; 32], String> {
+    let machine_id = machine_uid::get()
+        .ok()
+        .filter(|id| !id.trim().is_empty())
+        .ok_or_else(|| "no machine id for the encrypted password file; the OS keyring is required".to_string())?;
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT.as_bytes());
+    hasher.update(machine_id.trim().as_bytes());
+    Ok(hasher.finalize().into())
+}
+
+fn encrypt_secret(key: &[u8; 32], plaintext: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+fn decrypt_secret(key: &[u8; 32], sealed: &str) -> Result<String, String> {
+    let sealed = STANDARD.decode(sealed.trim()).map_err(|e| e.to_string())?;
+    if sealed.len() <= NONCE_LEN {
+        return Err("encrypted password is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "password cannot be decrypted on this machine".to_string())?;
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+/// A missing file is empty; an unreadable one is an error, so a write never starts over from an
+/// empty map and wipes the other devices' passwords.
+fn read_secrets_file(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("{} is unreadable: {}", path.display(), e))
+}
+
+fn read_file_secret(path: &Path, key: &[u8; 32], device_id: &str) -> Result<String, String> {
+    let secrets = read_secrets_file(path)?;
+    let sealed = secrets
+        .get(device_id)
+        .ok_or_else(|| format!("no stored password for device {}", device_id))?;
+    decrypt_secret(key, sealed)
+}
+
+/// Set (`Some`) or remove (`None`) one device's password in the secrets file.
+fn write_file_secret(path: &Path, key: &[u8; 32], device_id: &str, password: Option<&str>) -> Result<(), String> {
+    let mut secrets = read_secrets_file(path)?;
+    match password {
+        Some(password) => {
+            secrets.insert(device_id.to_string(), encrypt_secret(key, password)?);
+        }
+        None => {
+            if secrets.remove(device_id).is_none() {
+                return Ok(());
+            }
+        }
+    }
+    let content = serde_json::to_string_pretty(&secrets).map_err(|e| e.to_string())?;
+    write_atomic(path, content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_secrets_round_trip_and_stay_encrypted() {
+        let path = std::env::temp_dir().join(format!("sr-secrets-{}.json", uuid::Uuid::new_v4()));
+        let key = [7u8; 32];
+
+        write_file_secret(&path, &key, "d1", Some("Admin12345")).unwrap();
+        write_file_secret(&path, &key, "d2", Some("other")).unwrap();
+
+        assert_eq!(read_file_secret(&path, &key, "d1").unwrap(), "Admin12345");
+        assert!(!fs::read_to_string(&path).unwrap().contains("Admin12345"));
+        assert!(read_file_secret(&path, &[8u8; 32], "d1").is_err());
+
+        write_file_secret(&path, &key, "d1", None).unwrap();
+        assert!(read_file_secret(&path, &key, "d1").is_err());
+        assert_eq!(read_file_secret(&path, &key, "d2").unwrap(), "other");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn corrupt_secrets_file_is_not_overwritten() {
+        let path = std::env::temp_dir().join(format!("sr-secrets-{}.json", uuid::Uuid::new_v4()));
+        let key = [7u8; 32];
+        write_file_secret(&path, &key, "d1", Some("first")).unwrap();
+        write_file_secret(&path, &key, "d2", Some("second")).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let truncated = &content[..content.len() - 5];
+        fs::write(&path, truncated).unwrap();
+
+        assert!(write_file_secret(&path, &key, "d3", Some("third")).is_err());
+        assert!(read_file_secret(&path, &key, "d1").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), truncated);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn each_encryption_uses_a_fresh_nonce() {
+        let key = [1u8; 32];
+        let first = encrypt_secret(&key, "secret").unwrap();
+        let second = encrypt_secret(&key, "secret").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(decrypt_secret(&key, &second).unwrap(), "secret");
+    }
+}
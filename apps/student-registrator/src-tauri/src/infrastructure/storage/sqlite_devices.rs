@@ -0,0 +1,388 @@
+// Device repository on the SQLite store
+//
+// Rows keep `password_ref` (and `pending_password_ref` during a rotation) only; passwords go
+// through `secret_store` on the way in and out, exactly as `devices.json` did. `dedupe_key` is
+// a UNIQUE column, so two rows can never describe the same physical device. Secret store calls
+// (OS keyring round-trips) happen before or after the write transaction, never inside it.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::domain::entities::{DeviceConfig, DeviceDirection};
+use crate::domain::repositories::{dedupe_devices, dedupe_key, DeviceRepository};
+use super::secret_store::{delete_device_password, load_device_password, store_device_password};
+use super::sqlite_store::{app_store, SqliteStore};
+
+/// Serializes `update_devices` / `delete_device` in this process, so the read-update-write cycle
+/// does not hold the database lock while passwords are resolved and stored.
+static DEVICE_UPDATES: Mutex<()> = Mutex::new(());
+
+const DEVICE_COLUMNS: &str = "id, backend_id, host, port, username, password_ref, credentials_updated_at, \
+     credentials_expires_at, credentials_lifetime_days, device_id, scheme, tls_fingerprint, door_count, labels, direction, school_id, \
+     pending_password_ref, serial_number";
+
+fn direction_to_str(direction: DeviceDirection) -> &'static str {
+    match direction {
+        DeviceDirection::In => "IN",
+        DeviceDirection::Out => "OUT",
+        DeviceDirection::Both => "BOTH",
+    }
+}
+
+fn direction_from_str(value: &str) -> DeviceDirection {
+    match value {
+        "IN" => DeviceDirection::In,
+        "OUT" => DeviceDirection::Out,
+        _ => DeviceDirection::Both,
+    }
+}
+
+fn device_from_row(row: &Row) -> rusqlite::Result<DeviceConfig> {
+    let labels: String = row.get("labels")?;
+    let direction: String = row.get("direction")?;
+    Ok(DeviceConfig {
+        id: row.get("id")?,
+        backend_id: row.get("backend_id")?,
+        host: row.get("host")?,
+        port: row.get("port")?,
+        username: row.get("username")?,
+        password: String::new(),
+        password_ref: row.get("password_ref")?,
+        pending_password_ref: row.get("pending_password_ref")?,
+        credentials_updated_at: row.get("credentials_updated_at")?,
+        credentials_expires_at: row.get("credentials_expires_at")?,
+        credentials_lifetime_days: row.get("credentials_lifetime_days")?,
+        device_id: row.get("device_id")?,
+        serial_number: row.get("serial_number")?,
+        scheme: row.get("scheme")?,
+        tls_fingerprint: row.get("tls_fingerprint")?,
+        door_count: row.get("door_count")?,
+        labels: serde_json::from_str(&labels).unwrap_or_default(),
+        direction: direction_from_str(&direction),
+        school_id: row.get("school_id")?,
+    })
+}
+
+/// Rows as stored: `password` empty, `password_ref` set.
+fn read_stored_devices(conn: &Connection) -> Result<Vec<DeviceConfig>, String> {
+    let mut statement = conn
+        .prepare(&format!("SELECT {} FROM devices ORDER BY position", DEVICE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = statement
+        .query_map([], device_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn resolve_password(device: &mut DeviceConfig) {
+    if let Some(reference) = device.password_ref.as_deref() {
+        match load_device_password(reference) {
+            Ok(password) => device.password = password,
+            Err(err) => eprintln!("device {}: password not available: {}", device.id, err),
+        }
+    }
+}
+
+/// Move plain-text passwords into the secret store, leaving only the reference.
+fn store_passwords(devices: &mut [DeviceConfig]) -> Result<(), String> {
+    for device in devices.iter_mut() {
+        if device.password.is_empty() {
+            continue;
+        }
+        let reference = store_device_password(&device.id, device.password_ref.as_deref(), &device.password)?;
+        device.password_ref = Some(reference);
+        device.password.clear();
+    }
+    Ok(())
+}
+
+/// Replace the table contents with `devices` (already deduped, passwords already stored).
+fn replace_stored_devices(conn: &Connection, devices: &[DeviceConfig]) -> Result<(), String> {
+    conn.execute("DELETE FROM devices", []).map_err(|e| e.to_string())?;
+    let mut statement = conn
+        .prepare(&format!(
+            "INSERT INTO devices (position, dedupe_key, {}) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+            DEVICE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    for (position, device) in devices.iter().enumerate() {
+        let labels = serde_json::to_string(&device.labels).map_err(|e| e.to_string())?;
+        statement
+            .execute(params![
+                position as i64,
+                dedupe_key(device),
+                device.id,
+                device.backend_id,
+                device.host,
+                device.port,
+                device.username,
+                device.password_ref,
+                device.credentials_updated_at,
+                device.credentials_expires_at,
+                device.credentials_lifetime_days,
+                device.device_id,
+                device.scheme,
+                device.tls_fingerprint,
+                device.door_count,
+                labels,
+                direction_to_str(device.direction),
+                device.school_id,
+                device.pending_password_ref,
+                device.serial_number,
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Dedupe `devices` and move their passwords to the secret store; call before the write
+/// transaction.
+pub(super) fn prepare_devices(devices: Vec<DeviceConfig>) -> Result<Vec<DeviceConfig>, String> {
+    let mut stored = dedupe_devices(devices);
+    store_passwords(&mut stored)?;
+    Ok(stored)
+}
+
+fn password_refs(devices: &[DeviceConfig]) -> impl Iterator<Item = &String> {
+    devices
+        .iter()
+        .flat_map(|device| device.password_ref.iter().chain(&device.pending_password_ref))
+}
+
+/// Write devices from `prepare_devices` inside the caller's transaction. Returns the references
+/// no longer used by any device; delete them after commit.
+pub(super) fn write_devices(conn: &Connection, stored: &[DeviceConfig]) -> Result<Vec<String>, String> {
+    let previous_refs: Vec<String> = password_refs(&read_stored_devices(conn)?).cloned().collect();
+    replace_stored_devices(conn, stored)?;
+    let in_use = |reference: &str| {
+        stored.iter().any(|device| {
+            device.password_ref.as_deref() == Some(reference) || device.pending_password_ref.as_deref() == Some(reference)
+        })
+    };
+    Ok(previous_refs.into_iter().filter(|reference| !in_use(reference)).collect())
+}
+
+impl DeviceRepository for SqliteStore {
+    fn list_devices(&self) -> Result<Vec<DeviceConfig>, String> {
+        let mut devices = read_stored_devices(&self.connection())?;
+        devices.iter_mut().for_each(resolve_password);
+        Ok(devices)
+    }
+
+    fn get_device(&self, id: &str) -> Result<Option<DeviceConfig>, String> {
+        let device = self
+            .connection()
+            .query_row(&format!("SELECT {} FROM devices WHERE id = ?1", DEVICE_COLUMNS), [id], device_from_row)
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(mut device) = device else {
+            return Ok(None);
+        };
+        resolve_password(&mut device);
+        Ok(Some(device))
+    }
+
+    fn delete_device(&self, id: &str) -> Result<bool, String> {
+        let _updating = DEVICE_UPDATES.lock().unwrap_or_else(|e| e.into_inner());
+        let refs = self.write(|tx| {
+            let refs: Option<(Option<String>, Option<String>)> = tx
+                .query_row(
+                    "SELECT password_ref, pending_password_ref FROM devices WHERE id = ?1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            tx.execute("DELETE FROM devices WHERE id = ?1", [id])
+                .map_err(|e| e.to_string())?;
+            Ok(refs)
+        })?;
+        // `None`: no such device; the references are `None` when nothing was stored.
+        if let Some((password_ref, pending_password_ref)) = refs.as_ref() {
+            password_ref.iter().chain(pending_password_ref).for_each(|reference| delete_device_password(reference));
+        }
+        Ok(refs.is_some())
+    }
+
+    /// Passwords are resolved and stored outside the write transaction; the rows are checked to
+    /// be unchanged when it starts, so another app instance writing in between is an error
+    /// rather than a lost update.
+    fn update_devices(&self, update: &mut dyn FnMut(&mut Vec<DeviceConfig>)) -> Result<(), String> {
+        let _updating = DEVICE_UPDATES.lock().unwrap_or_else(|e| e.into_inner());
+        let snapshot = read_stored_devices(&self.connection())?;
+        let snapshot_value = serde_json::to_value(&snapshot).map_err(|e| e.to_string())?;
+        let mut devices = snapshot.clone();
+        devices.iter_mut().for_each(resolve_password);
+        let before = serde_json::to_value(&devices).map_err(|e| e.to_string())?;
+        update(&mut devices);
+        if serde_json::to_value(&devices).map_err(|e| e.to_string())? == before {
+            return Ok(());
+        }
+
+        let stored = prepare_devices(devices)?;
+        let written = self.write(|tx| {
+            let current = serde_json::to_value(read_stored_devices(tx)?).map_err(|e| e.to_string())?;
+            if current != snapshot_value {
+                return Err("devices were changed by another app instance; try again".to_string());
+            }
+            write_devices(tx, &stored)
+        });
+        match written {
+            Ok(orphaned) => orphaned.iter().for_each(|reference| delete_device_password(reference)),
+            Err(err) => {
+                // Secrets stored for this update only (e.g. a device that was being added).
+                let known: Vec<&String> = password_refs(&snapshot).collect();
+                password_refs(&stored)
+                    .filter(|reference| !known.contains(reference))
+                    .for_each(|reference| delete_device_password(reference));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keep `password` as the pending password of a device before a rotation sends it. Clearing
+/// `pending_password_ref` (or promoting the password) drops the secret again.
+pub fn save_pending_password(device_id: &str, password: &str) -> Result<(), String> {
+    let reference = store_device_password(&format!("{}:pending", device_id), None, password)?;
+    let saved = with_devices_mut(|devices| {
+        let stored = devices
+            .iter_mut()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| "device was removed".to_string())?;
+        stored.pending_password_ref = Some(reference.clone());
+        Ok(())
+    })
+    .and_then(|saved| saved);
+    if saved.is_err() {
+        delete_device_password(&reference);
+    }
+    saved
+}
+
+pub fn load_pending_password(device: &DeviceConfig) -> Result<Option<String>, String> {
+    device.pending_password_ref.as_deref().map(load_device_password).transpose()
+}
+
+/// All devices. A database error is returned rather than read as "no devices", so callers
+/// do not treat an unavailable store as an empty one.
+pub fn load_devices() -> Result<Vec<DeviceConfig>, String> {
+    app_store()?.list_devices()
+}
+
+pub fn get_device_by_id(device_id: &str) -> Option<DeviceConfig> {
+    match app_store().and_then(|store| store.get_device(device_id)) {
+        Ok(device) => device,
+        Err(err) => {
+            eprintln!("device {} not loaded: {}", device_id, err);
+            None
+        }
+    }
+}
+
+pub fn remove_device(device_id: &str) -> Result<bool, String> {
+    app_store()?.delete_device(device_id)
+}
+
+/// Run `update` on the current device list and save the result in one transaction if it
+/// changed. Use this instead of load + save so concurrent commands do not undo each other.
+/// Not re-entrant: never load or save devices from inside `update`.
+pub fn with_devices_mut<T>(update: impl FnOnce(&mut Vec<DeviceConfig>) -> T) -> Result<T, String> {
+    let mut update = Some(update);
+    let mut result = None;
+    app_store()?.update_devices(&mut |devices| {
+        if let Some(update) = update.take() {
+            result = Some(update(devices));
+        }
+    })?;
+    result.ok_or_else(|| "device update did not run".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::device::test_device;
+
+    fn device(id: &str, host: &str) -> DeviceConfig {
+        DeviceConfig {
+            host: host.to_string(),
+            password: String::new(),
+            scheme: Some("https".to_string()),
+            tls_fingerprint: Some("ab:cd".to_string()),
+            door_count: 2,
+            labels: vec!["gate".to_string()],
+            direction: DeviceDirection::In,
+            school_id: Some("school-1".to_string()),
+            ..test_device(id)
+        }
+    }
+
+    #[test]
+    fn devices_round_trip_in_order() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        store
+            .update_devices(&mut |devices| {
+                devices.push(device("d2", "10.0.0.2"));
+                devices.push(device("d1", "10.0.0.1"));
+            })
+            .unwrap();
+
+        let devices = store.list_devices().unwrap();
+
+        assert_eq!(devices.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["d2", "d1"]);
+        assert_eq!(
+            serde_json::to_value(&devices[1]).unwrap(),
+            serde_json::to_value(device("d1", "10.0.0.1")).unwrap()
+        );
+        assert_eq!(store.get_device("d1").unwrap().unwrap().host, "10.0.0.1");
+        assert!(store.get_device("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn same_dedupe_key_replaces_the_earlier_device() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut first = device("d1", "10.0.0.1");
+        first.device_id = Some("SERIAL1".to_string());
+        let mut second = device("d2", "10.0.0.9");
+        second.device_id = Some("serial1 ".to_string());
+        store
+            .update_devices(&mut |devices| {
+                devices.push(first.clone());
+                devices.push(device("d3", "10.0.0.3"));
+            })
+            .unwrap();
+
+        store.update_devices(&mut |devices| devices.push(second.clone())).unwrap();
+
+        let devices = store.list_devices().unwrap();
+        assert_eq!(devices.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["d2", "d3"]);
+        assert_eq!(devices[0].host, "10.0.0.9");
+    }
+
+    #[test]
+    fn cleared_pending_password_is_released() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut rotating = device("d1", "10.0.0.1");
+        rotating.pending_password_ref = Some("file:d1:pending".to_string());
+        store.write(|tx| write_devices(tx, &[rotating.clone()])).unwrap();
+        assert_eq!(store.get_device("d1").unwrap().unwrap().pending_password_ref, rotating.pending_password_ref);
+
+        rotating.pending_password_ref = None;
+        let orphaned = store.write(|tx| write_devices(tx, &[rotating])).unwrap();
+
+        assert_eq!(orphaned, ["file:d1:pending"]);
+    }
+
+    #[test]
+    fn delete_reports_whether_a_device_existed() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.update_devices(&mut |devices| devices.push(device("d1", "h"))).unwrap();
+
+        assert!(store.delete_device("d1").unwrap());
+        assert!(!store.delete_device("d1").unwrap());
+        assert!(store.list_devices().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+// Local SQLite database (`student-registrator.db`)
+//
+// One embedded database for everything the app keeps between runs. Tables are created by the
+// numbered migrations below (tracked in `PRAGMA user_version`); new tables for provisioning
+// history, offline event buffers, audit entries etc. are added as further steps, never by editing
+// an applied one. All access goes through one connection behind a mutex; writers take
+// `BEGIN IMMEDIATE` so a second app instance waits instead of failing half-way.
+//
+// The app database is copied (`VACUUM INTO`) to rotating `.bak.1..N` files before migrations
+// run and otherwise once a day. A file that fails `PRAGMA quick_check` is moved aside as
+// `.corrupt-<time>` and the newest backup that passes takes its place.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use chrono::Utc;
+
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::device_store::import_legacy_devices_file;
+use super::job_store::import_legacy_jobs_file;
+
+const DATABASE_FILE: &str = "student-registrator.db";
+const MAX_BACKUPS: usize = 5;
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Step `i` brings the database from `user_version = i` to `i + 1`.
+const MIGRATIONS: &[&str] = &[
+    // 1: devices, app settings, bookkeeping
+    "CREATE TABLE devices (
+        id TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        dedupe_key TEXT NOT NULL UNIQUE,
+        backend_id TEXT,
+        host TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        username TEXT NOT NULL,
+        password_ref TEXT,
+        credentials_updated_at TEXT,
+        credentials_expires_at TEXT,
+        device_id TEXT,
+        scheme TEXT,
+        tls_fingerprint TEXT,
+        door_count INTEGER NOT NULL DEFAULT 1,
+        labels TEXT NOT NULL DEFAULT '[]',
+        direction TEXT NOT NULL DEFAULT 'BOTH',
+        school_id TEXT
+    );
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    // 2: per-device credential lifetime
+    "ALTER TABLE devices ADD COLUMN credentials_lifetime_days INTEGER;",
+    // 3: store-and-forward queue of device webhooks (relay mode)
+    "CREATE TABLE relay_queue (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        received_at TEXT NOT NULL,
+        path TEXT NOT NULL,
+        content_type TEXT,
+        body BLOB NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        last_error TEXT,
+        state TEXT NOT NULL DEFAULT 'PENDING'
+    );
+    CREATE INDEX relay_queue_state ON relay_queue (state, id);",
+    // 4: password a rotation has not finished putting on the device
+    "ALTER TABLE devices ADD COLUMN pending_password_ref TEXT;",
+    // 5: serial number from deviceInfo, matched against LAN discovery
+    "ALTER TABLE devices ADD COLUMN serial_number TEXT;",
+    // 6: clone jobs (were `clone_jobs.json`), one JSON row per job
+    "CREATE TABLE clone_jobs (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        id TEXT NOT NULL UNIQUE,
+        status TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        data TEXT NOT NULL
+    );",
+];
+
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.busy_timeout(Duration::from_secs(5)).map_err(|e| e.to_string())?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+        Self::init(conn)
+    }
+
+    /// Fresh private database, for tests.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, String> {
+        Self::init(Connection::open_in_memory().map_err(|e| e.to_string())?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self, String> {
+        conn.pragma_update(None, "foreign_keys", true).map_err(|e| e.to_string())?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `f` in a write transaction; commits on `Ok`, rolls back on `Err`.
+    pub fn write<T>(&self, f: impl FnOnce(&Transaction) -> Result<T, String>) -> Result<T, String> {
+        let mut conn = self.connection();
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| e.to_string())?;
+        let value = f(&tx)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(value)
+    }
+}
+
+fn schema_version(conn: &Connection) -> Result<usize, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    sibling_path(path, &format!(".bak.{}", index))
+}
+
+/// Open `path` read-only and run `PRAGMA quick_check`.
+fn check_database(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result != "ok" {
+        return Err(result);
+    }
+    Ok(conn)
+}
+
+/// Move a corrupt database (and its WAL files) aside and put the newest healthy backup in its
+/// place. With no usable backup the app starts with an empty database; the bad file is kept.
+fn quarantine_database(path: &Path, reason: &str) -> Result<(), String> {
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    for suffix in ["", "-wal", "-shm"] {
+        let file = sibling_path(path, suffix);
+        if file.exists() {
+            std::fs::rename(&file, sibling_path(path, &format!(".corrupt-{}{}", stamp, suffix)))
+                .map_err(|e| format!("{} is corrupt ({}) and cannot be moved aside: {}", path.display(), reason, e))?;
+        }
+    }
+    eprintln!("{} is corrupt ({}), moved aside", path.display(), reason);
+    let restored = (1..=MAX_BACKUPS)
+        .map(|index| backup_path(path, index))
+        .find(|backup| backup.exists() && check_database(backup).is_ok());
+    if let Some(backup) = restored {
+        std::fs::copy(&backup, path).map_err(|e| format!("{} not restored: {}", backup.display(), e))?;
+        eprintln!("{} restored from {}", path.display(), backup.display());
+    }
+    Ok(())
+}
+
+/// Shift `.bak.1..N-1` up by one and write a fresh `.bak.1`.
+fn backup_database(conn: &Connection, path: &Path) -> Result<(), String> {
+    let _ = std::fs::remove_file(backup_path(path, MAX_BACKUPS));
+    for index in (1..MAX_BACKUPS).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, index + 1)).map_err(|e| e.to_string())?;
+        }
+    }
+    let target = backup_path(path, 1);
+    conn.execute("VACUUM INTO ?1", [target.to_string_lossy()])
+        .map(|_| ())
+        .map_err(|e| format!("backup {} failed: {}", target.display(), e))
+}
+
+fn backup_due(conn: &Connection, path: &Path) -> Result<bool, String> {
+    if schema_version(conn)? < MIGRATIONS.len() {
+        return Ok(true);
+    }
+    let age = std::fs::metadata(backup_path(path, 1))
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    Ok(!matches!(age, Some(age) if age < BACKUP_INTERVAL))
+}
+
+/// Open the app database at `path`: quarantine it when corrupt, back it up when due, then
+/// migrate. A failed backup is logged, not fatal.
+fn open_app_database(path: &Path) -> Result<SqliteStore, String> {
+    if path.exists() {
+        match check_database(path) {
+            Ok(conn) => {
+                let backed_up = backup_due(&conn, path)
+                    .and_then(|due| if due { backup_database(&conn, path) } else { Ok(()) });
+                if let Err(err) = backed_up {
+                    eprintln!("{}", err);
+                }
+            }
+            Err(reason) => quarantine_database(path, &reason)?,
+        }
+    }
+    SqliteStore::open(path)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version = schema_version(conn)?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "{} has schema version {}, this app supports up to {}; update the app",
+            DATABASE_FILE,
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| e.to_string())?;
+        tx.execute_batch(sql)
+            .map_err(|e| format!("migration {} failed: {}", index + 1, e))?;
+        tx.pragma_update(None, "user_version", index + 1).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+pub fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// A setting stored as JSON; `None` when unset.
+pub fn get_setting<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>, String> {
+    let raw: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    raw.map(|raw| serde_json::from_str(&raw).map_err(|e| format!("setting {}: {}", key, e)))
+        .transpose()
+}
+
+pub fn set_setting<T: Serialize + ?Sized>(conn: &Connection, key: &str, value: &T) -> Result<(), String> {
+    let raw = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, raw.as_str()],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+pub fn load_setting<T: DeserializeOwned>(key: &str) -> Result<Option<T>, String> {
+    get_setting(&app_store()?.connection(), key)
+}
+
+pub fn save_setting<T: Serialize + ?Sized>(key: &str, value: &T) -> Result<(), String> {
+    app_store()?.write(|tx| set_setting(tx, key, value))
+}
+
+fn get_app_dir() -> PathBuf {
+    let data_dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let app_dir = data_dir.join("student-registrator");
+    std::fs::create_dir_all(&app_dir).ok();
+    app_dir
+}
+
+/// The app database, opened (and the old `devices.json` / `clone_jobs.json` imported) on first use. A failed open
+/// is not cached: the next call tries again (e.g. once another instance released a lock).
+pub fn app_store() -> Result<&'static SqliteStore, String> {
+    static STORE: OnceLock<SqliteStore> = OnceLock::new();
+    static OPENING: Mutex<()> = Mutex::new(());
+    if let Some(store) = STORE.get() {
+        return Ok(store);
+    }
+    let _guard = OPENING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(store) = STORE.get() {
+        return Ok(store);
+    }
+    let app_dir = get_app_dir();
+    let store = open_app_database(&app_dir.join(DATABASE_FILE))
+        .map_err(|err| format!("{} unavailable: {}", DATABASE_FILE, err))?;
+    if let Err(err) = import_legacy_devices_file(&store, &app_dir.join("devices.json")) {
+        eprintln!("devices.json not imported: {}", err);
+    }
+    if let Err(err) = import_legacy_jobs_file(&store, &app_dir.join("clone_jobs.json")) {
+        eprintln!("clone_jobs.json not imported: {}", err);
+    }
+    Ok(STORE.get_or_init(|| store))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_run_once_and_newer_databases_are_refused() {
+        let path = std::env::temp_dir().join(format!("sr-store-{}.db", uuid::Uuid::new_v4()));
+        {
+            let store = SqliteStore::open(&path).unwrap();
+            set_meta(&store.connection(), "k", "v").unwrap();
+        }
+        let reopened = SqliteStore::open(&path).unwrap();
+        assert_eq!(get_meta(&reopened.connection(), "k").unwrap().as_deref(), Some("v"));
+        reopened
+            .connection()
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        drop(reopened);
+
+        assert!(SqliteStore::open(&path).err().unwrap().contains("update the app"));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn app_database_is_backed_up_and_restored_when_corrupt() {
+        let dir = std::env::temp_dir().join(format!("sr-store-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(DATABASE_FILE);
+        {
+            let store = open_app_database(&path).unwrap();
+            set_meta(&store.connection(), "k", "v").unwrap();
+        }
+        // First open had nothing to copy; the second backs up the database with `k`.
+        drop(open_app_database(&path).unwrap());
+        assert!(backup_path(&path, 1).exists());
+        assert!(!backup_path(&path, 2).exists());
+
+        std::fs::write(&path, vec![0x5a; 8192]).unwrap();
+        let store = open_app_database(&path).unwrap();
+        assert_eq!(get_meta(&store.connection(), "k").unwrap().as_deref(), Some("v"));
+        let quarantined = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().contains(".corrupt-"));
+        assert!(quarantined);
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn settings_round_trip_as_json() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        set_setting(&store.connection(), "limits", &serde_json::json!({"days": 30})).unwrap();
+        set_setting(&store.connection(), "limits", &serde_json::json!({"days": 45})).unwrap();
+
+        let value: Option<serde_json::Value> = get_setting(&store.connection(), "limits").unwrap();
+        assert_eq!(value.unwrap()["days"], 45);
+        assert!(get_setting::<u32>(&store.connection(), "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn failed_write_rolls_back() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let result: Result<(), String> = store.write(|tx| {
+            set_meta(tx, "k", "v")?;
+            Err("boom".to_string())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(get_meta(&store.connection(), "k").unwrap(), None);
+    }
+}
@@ -1,39 +1,49 @@
 #[tauri::command]
 pub async fn clone_device_to_device(
+    app: AppHandle,
     source_device_id: String,
     target_device_id: String,
     limit: Option<u32>,
-) -> Result<Value, String> {
-    let source = get_device_by_id(&source_device_id)
-        .ok_or("Manba qurilma topilmadi")?;
-    let target = get_device_by_id(&target_device_id)
-        .ok_or("Maqsad qurilma topilmadi")?;
-
-    if is_credentials_expired(&source) {
-        return Err("Manba qurilmaning ulanish sozlamalari muddati tugagan".to_string());
-    }
-    if is_credentials_expired(&target) {
-        return Err("Maqsad qurilmaning ulanish sozlamalari muddati tugagan".to_string());
-    }
+) -> Result<Value, CommandError> {
+    let spec = CloneJobSpec::DeviceToDevice {
+        source_device_id,
+        target_device_id,
+        limit: limit.unwrap_or(10000),
+    };
+    let job = create_clone_job(spec)?;
+    let control = claim_clone_job(&job.id).ok_or_else(|| job_state_conflict(&job))?;
+    let job = run_clone_job(job, control, CloneJobReporter::new(Some(app))).await?;
+    Ok(clone_job_summary(&job))
+}
 
-    let src_client = HikvisionClient::new(source.clone());
-    let tgt_client = HikvisionClient::new(target.clone());
+/// Devices of a device-to-device job, checked the same way when a job starts and resumes.
+fn load_clone_devices(
+    source_device_id: &str,
+    target_device_id: &str,
+) -> Result<(DeviceConfig, DeviceConfig), CommandError> {
+    let source = load_active_device(source_device_id).map_err(|e| e.with_detail("role", "source"))?;
+    let target = load_active_device(target_device_id).map_err(|e| e.with_detail("role", "target"))?;
+    Ok((source, target))
+}
 
-    let mut offset = 0i32;
+async fn run_device_clone_job(
+    job: &mut CloneJob,
+    source_device_id: &str,
+    target_device_id: &str,
+    limit: u32,
+    control: &CloneJobControl,
+    reporter: &CloneJobReporter,
+) -> Result<(), CommandError> {
+    let (source, target) = load_clone_devices(source_device_id, target_device_id)?;
+    let src_client = HikvisionClient::new(source);
+    let tgt_client = HikvisionClient::new(target);
     let page_size = 30i32;
-    let max = limit.unwrap_or(10000) as i32;
-
-    let mut processed = 0u32;
-    let mut success = 0u32;
-    let mut failed = 0u32;
-    let mut skipped = 0u32;
-    let mut errors: Vec<Value> = Vec::new();
 
     loop {
-        if processed as i32 >= max {
+        if job.processed >= limit {
             break;
         }
-        let response = src_client.search_users(offset, page_size).await;
+        let response = src_client.search_users(job.cursor.offset as i32, page_size).await;
         let info = response.user_info_search;
         let users = info.and_then(|v| v.user_info).unwrap_or_default();
         if users.is_empty() {
@@ -41,105 +51,91 @@ pub async fn clone_device_to_device(
         }
 
         for user in users {
-            if processed as i32 >= max {
+            if job.processed >= limit {
                 break;
             }
-            processed += 1;
-
-            let employee_no = user.employee_no.clone();
-            let name = user.name.clone();
-            let gender_raw = user.gender.clone().unwrap_or_else(|| "male".to_string());
-            let gender = match gender_raw.trim().to_lowercase().as_str() {
-                "female" | "f" | "ayol" | "2" => "female",
-                "male" | "m" | "erkak" | "1" => "male",
-                "ma" | "male " => "male",
-                "fa" | "female " => "female",
-                other if other.contains("female") => "female",
-                _ => "male",
-            }.to_string();
-            let face_url = user.face_url.clone().unwrap_or_default();
-
-            if employee_no.trim().is_empty() || name.trim().is_empty() || face_url.trim().is_empty() {
-                skipped += 1;
-                errors.push(serde_json::json!({
-                    "employeeNo": employee_no,
-                    "name": name,
-                    "reason": "Ma'lumot yetarli emas (employeeNo/name/faceURL)"
-                }));
-                continue;
+            if !clone_job_checkpoint(job, control, reporter).await {
+                return Ok(());
             }
+            job.current = Some(user.name.clone());
+            let outcome = clone_device_user(&src_client, &tgt_client, &user).await;
+            record_clone_outcome(job, outcome);
+            job.cursor.offset += 1;
+            reporter.progress(job);
+        }
+    }
+    Ok(())
+}
 
-            let face_bytes = match src_client.fetch_face_image(&face_url).await {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    failed += 1;
-                    errors.push(serde_json::json!({
-                        "employeeNo": employee_no,
-                        "name": name,
-                        "reason": "Rasmni manba qurilmadan olishda xato"
-                    }));
-                    continue;
-                }
-            };
-            let face_base64 = STANDARD.encode(&face_bytes);
-
-            let existing = tgt_client.get_user_by_employee_no(&employee_no).await;
-            if existing.is_none() {
-                let now = Local::now();
-                let begin_time = to_device_time(now);
-                let end_time = to_device_time(now.with_year(now.year() + 10).unwrap_or(now));
-                let create = tgt_client
-                    .create_user(&employee_no, &name, &gender, &begin_time, &end_time)
-                    .await;
-                if !create.ok {
-                    let reason = create.error_msg.unwrap_or_else(|| "Create failed".to_string());
-                    let lower = reason.to_lowercase();
-                    if lower.contains("already exist")
-                        || lower.contains("duplicate")
-                        || lower.contains("exist")
-                        || lower.contains("already")
-                    {
-                        skipped += 1;
-                    } else {
-                        failed += 1;
-                        errors.push(serde_json::json!({
-                            "employeeNo": employee_no,
-                            "name": name,
-                            "reason": reason
-                        }));
-                    }
-                    continue;
-                }
-            }
+async fn clone_device_user(
+    src_client: &HikvisionClient,
+    tgt_client: &HikvisionClient,
+    user: &UserInfoEntry,
+) -> CloneItemOutcome {
+    let employee_no = user.employee_no.clone();
+    let name = user.name.clone();
+    let gender_raw = user.gender.clone().unwrap_or_else(|| "male".to_string());
+    let gender = match gender_raw.trim().to_lowercase().as_str() {
+        "female" | "f" | "ayol" | "2" => "female",
+        "male" | "m" | "erkak" | "1" => "male",
+        "ma" | "male " => "male",
+        "fa" | "female " => "female",
+        other if other.contains("female") => "female",
+        _ => "male",
+    }.to_string();
+    let face_url = user.face_url.clone().unwrap_or_default();
+    let error = |reason: String| {
+        serde_json::json!({
+            "employeeNo": employee_no,
+            "name": name,
+            "reason": reason
+        })
+    };
 
-            let upload = tgt_client
-                .upload_face(&employee_no, &name, &gender, &face_base64)
-                .await;
-            if !upload.ok {
-                failed += 1;
-                errors.push(serde_json::json!({
-                    "employeeNo": employee_no,
-                    "name": name,
-                    "reason": upload.error_msg.unwrap_or_else(|| "Upload failed".to_string())
-                }));
-                continue;
-            }
-            success += 1;
+    if employee_no.trim().is_empty() || name.trim().is_empty() || face_url.trim().is_empty() {
+        return CloneItemOutcome::Skipped(Some(error(
+            "Ma'lumot yetarli emas (employeeNo/name/faceURL)".to_string(),
+        )));
+    }
+
+    let face_bytes = match src_client.fetch_face_image(&face_url).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return CloneItemOutcome::Failed(error("Rasmni manba qurilmadan olishda xato".to_string()));
         }
+    };
 
-        offset += page_size;
+    let face_base64 = STANDARD.encode(&face_bytes);
+    let existing = tgt_client.get_user_by_employee_no(&employee_no).await;
+    if existing.is_none() {
+        let now = Local::now();
+        let begin_time = to_device_time(now);
+        let end_time = to_device_time(now.with_year(now.year() + 10).unwrap_or(now));
+        let create = tgt_client
+            .create_user(&employee_no, &name, &gender, &begin_time, &end_time)
+            .await;
+        if !create.ok {
+            if is_already_exists_status(create.sub_status_code.as_deref()) {
+                return CloneItemOutcome::Skipped(None);
+            }
+            return CloneItemOutcome::Failed(error(
+                create.error_msg.unwrap_or_else(|| "Create failed".to_string()),
+            ));
+        }
     }
 
-    Ok(serde_json::json!({
-        "ok": true,
-        "source": device_match_label(&source),
-        "target": device_match_label(&target),
-        "processed": processed,
-        "success": success,
-        "failed": failed,
-        "skipped": skipped,
-        "errors": errors
-    }))
+    let upload = tgt_client
+        .upload_face(&employee_no, &name, &gender, &face_base64)
+        .await;
+    if !upload.ok {
+        if is_already_exists_status(upload.sub_status_code.as_deref()) {
+            return CloneItemOutcome::Skipped(None);
+        }
+        return CloneItemOutcome::Failed(error(
+            upload.error_msg.unwrap_or_else(|| "Upload failed".to_string()),
+        ));
+    }
+    CloneItemOutcome::Success
 }
 
 #[cfg(test)]
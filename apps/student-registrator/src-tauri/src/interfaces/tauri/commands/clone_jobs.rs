@@ -0,0 +1,504 @@
+// ============ Clone Jobs ============
+//
+// Clone operations run as jobs: every student is pushed to the UI as a `clone-job-progress`
+// event, and state (counters + cursor) is written to the `clone_jobs` table every few students
+// and on every status change. Pause/cancel are honoured between students; jobs left RUNNING by
+// a crash or restart resume from their last saved cursor.
+
+const CLONE_JOB_EVENT: &str = "clone-job-progress";
+/// Progress is written every this many students or this often, whichever comes first.
+const CLONE_JOB_SAVE_EVERY: u32 = 25;
+const CLONE_JOB_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Per-job error list cap, so a job over a broken source does not bloat the jobs file.
+const MAX_CLONE_JOB_ERRORS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloneJobSignal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Control channel between the job commands and the task running the job.
+struct CloneJobControl {
+    signal: tokio::sync::watch::Sender<CloneJobSignal>,
+}
+
+impl CloneJobControl {
+    fn new() -> Self {
+        Self {
+            signal: tokio::sync::watch::channel(CloneJobSignal::Run).0,
+        }
+    }
+
+    fn get(&self) -> CloneJobSignal {
+        *self.signal.borrow()
+    }
+
+    fn set(&self, signal: CloneJobSignal) {
+        self.signal.send_replace(signal);
+    }
+
+    /// Wait until the job is no longer paused; returns the signal that ended the pause.
+    async fn wait_while_paused(&self) -> CloneJobSignal {
+        let mut rx = self.signal.subscribe();
+        loop {
+            let signal = *rx.borrow_and_update();
+            if signal != CloneJobSignal::Pause {
+                return signal;
+            }
+            if rx.changed().await.is_err() {
+                return CloneJobSignal::Cancel;
+            }
+        }
+    }
+}
+
+/// Controls of jobs that have a live task in this process.
+fn clone_job_controls() -> &'static Mutex<HashMap<String, Arc<CloneJobControl>>> {
+    static CONTROLS: OnceLock<Mutex<HashMap<String, Arc<CloneJobControl>>>> = OnceLock::new();
+    CONTROLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn live_clone_job_control(job_id: &str) -> Option<Arc<CloneJobControl>> {
+    clone_job_controls()
+        .lock()
+        .ok()
+        .and_then(|controls| controls.get(job_id).cloned())
+}
+
+/// Register the control of a new runner for `job_id`. Check and insert happen under one lock,
+/// so `None` (a runner is already live) is the only answer a second caller can get.
+fn claim_clone_job(job_id: &str) -> Option<Arc<CloneJobControl>> {
+    let mut controls = clone_job_controls().lock().unwrap_or_else(|e| e.into_inner());
+    if controls.contains_key(job_id) {
+        return None;
+    }
+    let control = Arc::new(CloneJobControl::new());
+    controls.insert(job_id.to_string(), control.clone());
+    Some(control)
+}
+
+enum CloneItemOutcome {
+    Success,
+    Skipped(Option<Value>),
+    Failed(Value),
+}
+
+fn record_clone_outcome(job: &mut CloneJob, outcome: CloneItemOutcome) {
+    job.processed += 1;
+    let error = match outcome {
+        CloneItemOutcome::Success => {
+            job.success += 1;
+            None
+        }
+        CloneItemOutcome::Skipped(error) => {
+            job.skipped += 1;
+            error
+        }
+        CloneItemOutcome::Failed(error) => {
+            job.failed += 1;
+            Some(error)
+        }
+    };
+    if let Some(error) = error {
+        if job.errors.len() < MAX_CLONE_JOB_ERRORS {
+            job.errors.push(error);
+        }
+    }
+}
+
+/// Persists job state and mirrors it to the UI.
+struct CloneJobReporter {
+    app: Option<AppHandle>,
+    /// When and at which `processed` count the job was last written.
+    last_saved: Mutex<(std::time::Instant, u32)>,
+}
+
+impl CloneJobReporter {
+    fn new(app: Option<AppHandle>) -> Self {
+        Self {
+            app,
+            last_saved: Mutex::new((std::time::Instant::now(), 0)),
+        }
+    }
+
+    fn emit(&self, job: &CloneJob) {
+        if let Some(app) = self.app.as_ref() {
+            let _ = app.emit_all(CLONE_JOB_EVENT, CloneJobProgress::from(job));
+        }
+    }
+
+    /// Write the job now; for status changes.
+    fn persist(&self, job: &mut CloneJob) {
+        job.updated_at = Utc::now().to_rfc3339();
+        if let Err(err) = save_job(job) {
+            eprintln!("clone job {}: state not saved: {}", job.id, err);
+        }
+        if let Ok(mut last_saved) = self.last_saved.lock() {
+            *last_saved = (std::time::Instant::now(), job.processed);
+        }
+        self.emit(job);
+    }
+
+    /// After each student: the UI always hears about it, the table only every
+    /// `CLONE_JOB_SAVE_EVERY` students or `CLONE_JOB_SAVE_INTERVAL`. A crash repeats at most
+    /// that many students.
+    fn progress(&self, job: &mut CloneJob) {
+        let due = self.last_saved.lock().map_or(true, |last_saved| {
+            job.processed.saturating_sub(last_saved.1) >= CLONE_JOB_SAVE_EVERY
+                || last_saved.0.elapsed() >= CLONE_JOB_SAVE_INTERVAL
+        });
+        if due {
+            self.persist(job);
+        } else {
+            self.emit(job);
+        }
+    }
+}
+
+/// Honour pause/cancel between students. Returns false when the job has to stop.
+async fn clone_job_checkpoint(
+    job: &mut CloneJob,
+    control: &CloneJobControl,
+    reporter: &CloneJobReporter,
+) -> bool {
+    let signal = match control.get() {
+        CloneJobSignal::Pause => {
+            job.status = CloneJobStatus::Paused;
+            job.current = None;
+            reporter.persist(job);
+            control.wait_while_paused().await
+        }
+        signal => signal,
+    };
+    if signal == CloneJobSignal::Cancel {
+        job.status = CloneJobStatus::Cancelled;
+        job.current = None;
+        reporter.persist(job);
+        return false;
+    }
+    if job.status != CloneJobStatus::Running {
+        job.status = CloneJobStatus::Running;
+        reporter.persist(job);
+    }
+    true
+}
+
+/// Validate a job's inputs and return the source/target labels shown in its summary.
+fn clone_job_labels(spec: &CloneJobSpec) -> Result<Value, CommandError> {
+    match spec {
+        CloneJobSpec::DeviceToDevice {
+            source_device_id,
+            target_device_id,
+            ..
+        } => {
+            let (source, target) = load_clone_devices(source_device_id, target_device_id)?;
+            Ok(serde_json::json!({
+                "source": device_match_label(&source),
+                "target": device_match_label(&target),
+            }))
+        }
+        CloneJobSpec::BackendToDevice { backend_device_id, .. } => {
+            let target = load_backend_clone_target(backend_device_id)?;
+            Ok(serde_json::json!({ "device": device_match_label(&target) }))
+        }
+    }
+}
+
+fn create_clone_job(spec: CloneJobSpec) -> Result<CloneJob, CommandError> {
+    let labels = clone_job_labels(&spec)?;
+    let now = Utc::now().to_rfc3339();
+    let mut job = CloneJob {
+        id: Uuid::new_v4().to_string(),
+        spec,
+        status: CloneJobStatus::Queued,
+        cursor: CloneJobCursor::default(),
+        processed: 0,
+        success: 0,
+        failed: 0,
+        skipped: 0,
+        errors: Vec::new(),
+        current: None,
+        labels,
+        error: None,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+    create_job(&mut job).map_err(CommandError::storage)?;
+    Ok(job)
+}
+
+/// Run a job from its cursor until it completes, fails or is cancelled. `control` comes from
+/// `claim_clone_job` and is released here.
+async fn run_clone_job(
+    mut job: CloneJob,
+    control: Arc<CloneJobControl>,
+    reporter: CloneJobReporter,
+) -> Result<CloneJob, CommandError> {
+    job.status = CloneJobStatus::Running;
+    job.error = None;
+    reporter.persist(&mut job);
+
+    let result = match job.spec.clone() {
+        CloneJobSpec::DeviceToDevice {
+            source_device_id,
+            target_device_id,
+            limit,
+        } => {
+            run_device_clone_job(&mut job, &source_device_id, &target_device_id, limit, &control, &reporter)
+                .await
+        }
+        CloneJobSpec::BackendToDevice {
+            backend_device_id,
+            backend_url,
+            school_id,
+            limit,
+            ..
+        } => match load_job_backend_token(&job) {
+            Ok(backend_token) => {
+                run_backend_clone_job(
+                    &mut job,
+                    &backend_device_id,
+                    &backend_url,
+                    backend_token.as_deref(),
+                    &school_id,
+                    limit,
+                    &control,
+                    &reporter,
+                )
+                .await
+            }
+            Err(reason) => Err(CommandError::storage(reason)),
+        },
+    };
+
+    if let Ok(mut controls) = clone_job_controls().lock() {
+        controls.remove(&job.id);
+    }
+    job.current = None;
+    match result {
+        Ok(()) => {
+            if job.status == CloneJobStatus::Running {
+                job.status = CloneJobStatus::Completed;
+            }
+            reporter.persist(&mut job);
+            Ok(job)
+        }
+        Err(err) => {
+            job.status = CloneJobStatus::Failed;
+            job.error = serde_json::to_value(&err).ok();
+            reporter.persist(&mut job);
+            Err(err)
+        }
+    }
+}
+
+/// Start a runner unless one is already live for the job; returns whether it started.
+fn spawn_clone_job(app: AppHandle, job: CloneJob) -> bool {
+    let Some(control) = claim_clone_job(&job.id) else {
+        return false;
+    };
+    tauri::async_runtime::spawn(async move {
+        let _ = run_clone_job(job, control, CloneJobReporter::new(Some(app))).await;
+    });
+    true
+}
+
+/// Result shape of the blocking clone commands.
+fn clone_job_summary(job: &CloneJob) -> Value {
+    let mut summary = match &job.labels {
+        Value::Object(map) => map.clone(),
+        _ => Map::new(),
+    };
+    summary.insert("ok".to_string(), Value::Bool(true));
+    summary.insert("jobId".to_string(), Value::String(job.id.clone()));
+    summary.insert("status".to_string(), serde_json::json!(job.status));
+    summary.insert("processed".to_string(), job.processed.into());
+    summary.insert("success".to_string(), job.success.into());
+    summary.insert("failed".to_string(), job.failed.into());
+    summary.insert("skipped".to_string(), job.skipped.into());
+    summary.insert("errors".to_string(), Value::Array(job.errors.clone()));
+    Value::Object(summary)
+}
+
+/// Jobs that were running (or queued) when the app stopped.
+fn interrupted_clone_jobs(jobs: Vec<CloneJob>) -> Vec<CloneJob> {
+    jobs.into_iter()
+        .filter(|job| matches!(job.status, CloneJobStatus::Running | CloneJobStatus::Queued))
+        .collect()
+}
+
+/// Called once on startup: continue interrupted jobs from their saved cursor.
+pub fn resume_interrupted_clone_jobs(app: AppHandle) {
+    let jobs = match load_jobs() {
+        Ok(jobs) => jobs,
+        Err(err) => {
+            eprintln!("clone jobs not resumed: {}", err);
+            return;
+        }
+    };
+    for job in interrupted_clone_jobs(jobs) {
+        spawn_clone_job(app.clone(), job);
+    }
+}
+
+fn find_clone_job(job_id: &str) -> Result<CloneJob, CommandError> {
+    get_job(job_id)
+        .map_err(CommandError::storage)?
+        .ok_or_else(|| CommandError::new(ErrorCode::JobNotFound).with_detail("jobId", job_id))
+}
+
+fn job_state_conflict(job: &CloneJob) -> CommandError {
+    CommandError::new(ErrorCode::JobStateConflict)
+        .with_detail("jobId", job.id.clone())
+        .with_detail("status", serde_json::json!(job.status))
+}
+
+#[tauri::command]
+pub async fn start_clone_device_job(
+    app: AppHandle,
+    source_device_id: String,
+    target_device_id: String,
+    limit: Option<u32>,
+) -> Result<CloneJob, CommandError> {
+    let job = create_clone_job(CloneJobSpec::DeviceToDevice {
+        source_device_id,
+        target_device_id,
+        limit: limit.unwrap_or(10000),
+    })?;
+    spawn_clone_job(app, job.clone());
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn start_clone_students_job(
+    app: AppHandle,
+    backend_device_id: String,
+    backend_url: Option<String>,
+    backend_token: Option<String>,
+    school_id: Option<String>,
+    max_students: Option<u32>,
+) -> Result<CloneJob, CommandError> {
+    let spec = backend_clone_spec(backend_device_id, backend_url, backend_token, school_id, max_students)?;
+    let job = create_clone_job(spec)?;
+    spawn_clone_job(app, job.clone());
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn list_clone_jobs() -> Result<Vec<CloneJob>, CommandError> {
+    let mut jobs = load_jobs().map_err(CommandError::storage)?;
+    jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(jobs)
+}
+
+#[tauri::command]
+pub async fn get_clone_job(job_id: String) -> Result<CloneJob, CommandError> {
+    find_clone_job(&job_id)
+}
+
+#[tauri::command]
+pub async fn pause_clone_job(job_id: String) -> Result<CloneJob, CommandError> {
+    let job = find_clone_job(&job_id)?;
+    let control = live_clone_job_control(&job_id).ok_or_else(|| job_state_conflict(&job))?;
+    control.set(CloneJobSignal::Pause);
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn resume_clone_job(app: AppHandle, job_id: String) -> Result<CloneJob, CommandError> {
+    let job = find_clone_job(&job_id)?;
+    if let Some(control) = live_clone_job_control(&job_id) {
+        control.set(CloneJobSignal::Run);
+        return Ok(job);
+    }
+    if matches!(job.status, CloneJobStatus::Cancelled | CloneJobStatus::Completed) {
+        return Err(job_state_conflict(&job));
+    }
+    // Paused before a restart, or failed (e.g. device offline): continue from the cursor.
+    clone_job_labels(&job.spec)?;
+    if !spawn_clone_job(app, job.clone()) {
+        // Another resume started a runner since the check above.
+        if let Some(control) = live_clone_job_control(&job_id) {
+            control.set(CloneJobSignal::Run);
+        }
+    }
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn cancel_clone_job(job_id: String) -> Result<CloneJob, CommandError> {
+    let mut job = find_clone_job(&job_id)?;
+    if let Some(control) = live_clone_job_control(&job_id) {
+        control.set(CloneJobSignal::Cancel);
+        return Ok(job);
+    }
+    if job.status.is_finished() {
+        return Err(job_state_conflict(&job));
+    }
+    job.status = CloneJobStatus::Cancelled;
+    CloneJobReporter::new(None).persist(&mut job);
+    Ok(job)
+}
+
+#[cfg(test)]
+mod clone_jobs_tests {
+    use super::{
+        claim_clone_job, clone_job_controls, interrupted_clone_jobs, record_clone_outcome, CloneItemOutcome,
+        CloneJobControl, CloneJobSignal, MAX_CLONE_JOB_ERRORS,
+    };
+    use crate::domain::entities::job::test_clone_job as test_job;
+    use crate::types::CloneJobStatus;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn clone_job_control_waits_while_paused() {
+        let control = Arc::new(CloneJobControl::new());
+        control.set(CloneJobSignal::Pause);
+        let waiter = {
+            let control = control.clone();
+            tokio::spawn(async move { control.wait_while_paused().await })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        control.set(CloneJobSignal::Cancel);
+        assert_eq!(waiter.await.unwrap(), CloneJobSignal::Cancel);
+    }
+
+    #[test]
+    fn clone_job_outcomes_update_counters_and_cap_errors() {
+        let mut job = test_job("j1", CloneJobStatus::Running);
+        record_clone_outcome(&mut job, CloneItemOutcome::Success);
+        record_clone_outcome(&mut job, CloneItemOutcome::Skipped(None));
+        for _ in 0..MAX_CLONE_JOB_ERRORS + 5 {
+            record_clone_outcome(&mut job, CloneItemOutcome::Failed(serde_json::json!({ "reason": "x" })));
+        }
+        assert_eq!(job.processed as usize, MAX_CLONE_JOB_ERRORS + 7);
+        assert_eq!((job.success, job.skipped), (1, 1));
+        assert_eq!(job.failed as usize, MAX_CLONE_JOB_ERRORS + 5);
+        assert_eq!(job.errors.len(), MAX_CLONE_JOB_ERRORS);
+    }
+
+    #[test]
+    fn only_running_and_queued_jobs_resume_after_restart() {
+        let jobs = vec![
+            test_job("running", CloneJobStatus::Running),
+            test_job("paused", CloneJobStatus::Paused),
+            test_job("queued", CloneJobStatus::Queued),
+            test_job("done", CloneJobStatus::Completed),
+        ];
+        let ids: Vec<String> = interrupted_clone_jobs(jobs).into_iter().map(|job| job.id).collect();
+        assert_eq!(ids, ["running", "queued"]);
+    }
+
+    #[test]
+    fn a_job_can_only_be_claimed_by_one_runner() {
+        let id = uuid::Uuid::new_v4().to_string();
+        assert!(claim_clone_job(&id).is_some());
+        assert!(claim_clone_job(&id).is_none());
+        clone_job_controls().lock().unwrap().remove(&id);
+        assert!(claim_clone_job(&id).is_some());
+        clone_job_controls().lock().unwrap().remove(&id);
+    }
+}
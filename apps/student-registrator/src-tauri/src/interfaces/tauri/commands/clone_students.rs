@@ -1,143 +1,171 @@
 #[tauri::command]
 pub async fn clone_students_to_device(
+    app: AppHandle,
     backend_device_id: String,
     backend_url: Option<String>,
     backend_token: Option<String>,
     school_id: Option<String>,
     page_size: Option<u32>,
     max_students: Option<u32>,
-) -> Result<Value, String> {
+) -> Result<Value, CommandError> {
+    let _per_page = page_size.unwrap_or(50).clamp(10, 200);
+    let spec = backend_clone_spec(backend_device_id, backend_url, backend_token, school_id, max_students)?;
+    let job = create_clone_job(spec)?;
+    let control = claim_clone_job(&job.id).ok_or_else(|| job_state_conflict(&job))?;
+    let job = run_clone_job(job, control, CloneJobReporter::new(Some(app))).await?;
+    Ok(clone_job_summary(&job))
+}
+
+fn backend_clone_spec(
+    backend_device_id: String,
+    backend_url: Option<String>,
+    backend_token: Option<String>,
+    school_id: Option<String>,
+    max_students: Option<u32>,
+) -> Result<CloneJobSpec, CommandError> {
     let backend_url = backend_url.filter(|v| !v.trim().is_empty())
-        .ok_or("backendUrl is required")?;
+        .ok_or_else(|| CommandError::invalid_input("backendUrl is required"))?;
     let school_id = school_id.filter(|v| !v.trim().is_empty())
-        .ok_or("schoolId is required")?;
-    let token = backend_token.filter(|v| !v.trim().is_empty());
-    let _per_page = page_size.unwrap_or(50).clamp(10, 200);
-    let limit = max_students.unwrap_or(10000);
+        .ok_or_else(|| CommandError::invalid_input("schoolId is required"))?;
+    Ok(CloneJobSpec::BackendToDevice {
+        backend_device_id,
+        backend_url,
+        backend_token: backend_token.filter(|v| !v.trim().is_empty()),
+        backend_token_ref: None,
+        school_id,
+        limit: max_students.unwrap_or(10000),
+    })
+}
 
-    let local_devices = load_devices();
-    let local_index = find_local_device_index(&local_devices, &backend_device_id, None)
-        .ok_or("Local ulanish sozlamasi topilmadi")?;
+fn load_backend_clone_target(backend_device_id: &str) -> Result<DeviceConfig, CommandError> {
+    let local_devices = load_devices().map_err(CommandError::storage)?;
+    let local_index = find_local_device_index(&local_devices, backend_device_id, None)
+        .ok_or_else(|| device_not_found(backend_device_id))?;
     let target_device = local_devices[local_index].clone();
-    if is_credentials_expired(&target_device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+    ensure_credentials_valid(&target_device)?;
+    Ok(target_device)
+}
 
+#[allow(clippy::too_many_arguments)]
+async fn run_backend_clone_job(
+    job: &mut CloneJob,
+    backend_device_id: &str,
+    backend_url: &str,
+    token: Option<&str>,
+    school_id: &str,
+    limit: u32,
+    control: &CloneJobControl,
+    reporter: &CloneJobReporter,
+) -> Result<(), CommandError> {
+    let target_device = load_backend_clone_target(backend_device_id)?;
+    let hik = HikvisionClient::new(target_device);
     let client = Client::new();
-    let mut page = 1u32;
-    let mut total_processed = 0u32;
-    let mut success = 0u32;
-    let mut failed = 0u32;
-    let mut skipped = 0u32;
-    let mut errors: Vec<Value> = Vec::new();
+    if job.cursor.page == 0 {
+        job.cursor.page = 1;
+    }
 
     loop {
-        if total_processed >= limit {
+        if job.processed >= limit {
             break;
         }
         let url = format!(
             "{}/schools/{}/students?page={}",
-            backend_url, school_id, page
+            backend_url, school_id, job.cursor.page
         );
         let mut req = client.get(&url);
-        if let Some(t) = token.as_ref() {
+        if let Some(t) = token {
             req = req.header("Authorization", format!("Bearer {}", t));
         }
-        let res = req.send().await.map_err(|e| e.to_string())?;
+        let res = req.send().await.map_err(|e| CommandError::backend(e.to_string()))?;
         if !res.status().is_success() {
+            let status = res.status().as_u16();
             let text = res.text().await.unwrap_or_default();
-            return Err(text);
+            return Err(CommandError::backend(text).with_detail("httpStatus", status));
         }
-        let payload: Value = res.json().await.map_err(|e| e.to_string())?;
+        let payload: Value = res.json().await.map_err(|e| CommandError::backend(e.to_string()))?;
         let data = payload.get("data").and_then(|v| v.as_array()).cloned().unwrap_or_default();
         if data.is_empty() {
             break;
         }
 
-        for item in data {
-            if total_processed >= limit {
+        for item in data.iter().skip(job.cursor.offset as usize) {
+            if job.processed >= limit {
                 break;
             }
-            total_processed += 1;
-            let student_id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
-            let device_student_id = item.get("deviceStudentId").and_then(|v| v.as_str()).unwrap_or("");
-            let full_name = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            let gender = item.get("gender").and_then(|v| v.as_str()).unwrap_or("MALE");
-            let photo_url = item.get("photoUrl").and_then(|v| v.as_str()).unwrap_or("");
-
-            if device_student_id.is_empty() || full_name.is_empty() || photo_url.is_empty() {
-                skipped += 1;
-                errors.push(serde_json::json!({
-                    "studentId": student_id,
-                    "name": full_name,
-                    "reason": "Ma'lumot yetarli emas (deviceStudentId/name/photoUrl)"
-                }));
-                continue;
+            if !clone_job_checkpoint(job, control, reporter).await {
+                return Ok(());
             }
+            job.current = item.get("name").and_then(|v| v.as_str()).map(|s| s.to_string());
+            let outcome = clone_backend_student(&client, &hik, backend_url, item).await;
+            record_clone_outcome(job, outcome);
+            job.cursor.offset += 1;
+            reporter.progress(job);
+        }
+
+        job.cursor.page += 1;
+        job.cursor.offset = 0;
+    }
+    Ok(())
+}
 
-            let photo_full_url = if photo_url.starts_with("http://") || photo_url.starts_with("https://") {
-                photo_url.to_string()
-            } else {
-                format!("{}{}", backend_url, photo_url)
-            };
+async fn clone_backend_student(
+    client: &Client,
+    hik: &HikvisionClient,
+    backend_url: &str,
+    item: &Value,
+) -> CloneItemOutcome {
+    let student_id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
+    let device_student_id = item.get("deviceStudentId").and_then(|v| v.as_str()).unwrap_or("");
+    let full_name = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
+    let gender = item.get("gender").and_then(|v| v.as_str()).unwrap_or("MALE");
+    let photo_url = item.get("photoUrl").and_then(|v| v.as_str()).unwrap_or("");
+    let error = |reason: String| {
+        serde_json::json!({
+            "studentId": student_id,
+            "name": full_name,
+            "reason": reason
+        })
+    };
 
-            let img_res = client.get(&photo_full_url).send().await;
-            let bytes = match img_res {
-                Ok(resp) if resp.status().is_success() => resp.bytes().await.map_err(|e| e.to_string())?.to_vec(),
-                _ => {
-                    failed += 1;
-                    errors.push(serde_json::json!({
-                        "studentId": student_id,
-                        "name": full_name,
-                        "reason": "Rasm yuklab bo'lmadi"
-                    }));
-                    continue;
-                }
-            };
+    if device_student_id.is_empty() || full_name.is_empty() || photo_url.is_empty() {
+        return CloneItemOutcome::Skipped(Some(error(
+            "Ma'lumot yetarli emas (deviceStudentId/name/photoUrl)".to_string(),
+        )));
+    }
 
-            let face_base64 = STANDARD.encode(&bytes);
-            let hik = HikvisionClient::new(target_device.clone());
+    let photo_full_url = if photo_url.starts_with("http://") || photo_url.starts_with("https://") {
+        photo_url.to_string()
+    } else {
+        format!("{}{}", backend_url, photo_url)
+    };
 
-            let now = Local::now();
-            let begin_time = to_device_time(now);
-            let end_time = to_device_time(now.with_year(now.year() + 10).unwrap_or(now));
+    let bytes = match client.get(&photo_full_url).send().await {
+        Ok(resp) if resp.status().is_success() => match resp.bytes().await {
+            Ok(bytes) => bytes.to_vec(),
+            Err(e) => return CloneItemOutcome::Failed(error(e.to_string())),
+        },
+        _ => return CloneItemOutcome::Failed(error("Rasm yuklab bo'lmadi".to_string())),
+    };
 
-            let create = hik.create_user(device_student_id, full_name, gender, &begin_time, &end_time).await;
-            if !create.ok {
-                failed += 1;
-                errors.push(serde_json::json!({
-                    "studentId": student_id,
-                    "name": full_name,
-                    "reason": create.error_msg.unwrap_or_else(|| "Create failed".to_string())
-                }));
-                continue;
-            }
+    let face_base64 = STANDARD.encode(&bytes);
 
-            let upload = hik.upload_face(device_student_id, full_name, gender, &face_base64).await;
-            if !upload.ok {
-                failed += 1;
-                errors.push(serde_json::json!({
-                    "studentId": student_id,
-                    "name": full_name,
-                    "reason": upload.error_msg.unwrap_or_else(|| "Upload failed".to_string())
-                }));
-                continue;
-            }
+    let now = Local::now();
+    let begin_time = to_device_time(now);
+    let end_time = to_device_time(now.with_year(now.year() + 10).unwrap_or(now));
 
-            success += 1;
-        }
+    let create = hik.create_user(device_student_id, full_name, gender, &begin_time, &end_time).await;
+    if !create.ok {
+        return CloneItemOutcome::Failed(error(
+            create.error_msg.unwrap_or_else(|| "Create failed".to_string()),
+        ));
+    }
 
-        page += 1;
+    let upload = hik.upload_face(device_student_id, full_name, gender, &face_base64).await;
+    if !upload.ok {
+        return CloneItemOutcome::Failed(error(
+            upload.error_msg.unwrap_or_else(|| "Upload failed".to_string()),
+        ));
     }
 
-    Ok(serde_json::json!({
-        "ok": true,
-        "device": device_match_label(&target_device),
-        "processed": total_processed,
-        "success": success,
-        "failed": failed,
-        "skipped": skipped,
-        "errors": errors
-    }))
+    CloneItemOutcome::Success
 }
-
@@ -0,0 +1,76 @@
+const ACTIVATION_USERNAME: &str = "admin";
+
+/// Activate a factory-new device (found by `discover_devices` with `activated = false`) with
+/// `password` for `admin`, then add it like `create_device`. Nothing is saved when activation
+/// fails; an already active device is refused (`details.stage = "status"`).
+#[tauri::command]
+pub async fn activate_device(
+    host: String,
+    port: u16,
+    password: String,
+    scheme: Option<String>,
+    backend_id: Option<String>,
+    school_id: Option<String>,
+) -> Result<DeviceConfig, CommandError> {
+    let host = host.trim().to_string();
+    let scheme = normalize_device_scheme(scheme.as_deref()).map_err(CommandError::invalid_input)?;
+    validate_device_password(ACTIVATION_USERNAME, &password).map_err(CommandError::invalid_input)?;
+    // Check before the device is touched: an activated device we cannot save is hard to find again.
+    let max_local_devices = get_max_local_devices();
+    if load_devices().map_err(CommandError::storage)?.len() >= max_local_devices {
+        return Err(CommandError::new(ErrorCode::DeviceLimitReached)
+            .with_detail("max", max_local_devices)
+            .with_reason(format!("Maximum {} devices allowed", max_local_devices)));
+    }
+    let activation_error = |stage: &str| {
+        CommandError::new(ErrorCode::ActivationFailed)
+            .with_detail("host", host.clone())
+            .with_detail("port", port)
+            .with_detail("stage", stage)
+    };
+
+    let mut device = DeviceConfig::endpoint(&host, port, ACTIVATION_USERNAME, &password, &scheme);
+    let client = HikvisionClient::new(device.clone());
+    if client.activation_status().await.map_err(CommandError::from)? == Some(true) {
+        return Err(activation_error("status")
+            .with_detail("activated", true)
+            .with_reason("device is already activated"));
+    }
+    client
+        .activate(&password)
+        .await
+        .map_err(|err| activation_error("activate").with_reason(err.to_string()))?;
+
+    // The device confirmed the new password; the login check only picks up its identity.
+    let verification = verify_device_login(&device).await;
+    if !verification.ok {
+        eprintln!("activated device {} not reachable yet: {:?}", host, verification.message);
+    }
+    apply_connection_identity(&mut device, &verification);
+    // `create_device` checks the limit again inside its transaction: another device may have
+    // been added while this one was being activated. The device keeps the new password either
+    // way, so the error says so.
+    let mut saved = create_device(
+        backend_id,
+        host,
+        port,
+        ACTIVATION_USERNAME.to_string(),
+        password,
+        device.device_id.clone(),
+        Some(scheme),
+        device.tls_fingerprint.clone(),
+        None,
+        None,
+        None,
+        school_id,
+        None,
+    )
+    .await
+    .map_err(|err| err.with_detail("stage", "save").with_detail("activated", true))?;
+    // `create_device` takes no serial; store the one the login check learned.
+    if device.serial_number.is_some() {
+        copy_connection_identity(&mut saved, &device);
+        save_device_identity(std::slice::from_ref(&saved));
+    }
+    Ok(saved)
+}
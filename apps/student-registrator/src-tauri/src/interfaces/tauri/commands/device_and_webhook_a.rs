@@ -1,14 +1,24 @@
 #[tauri::command]
 pub fn get_contract_version() -> String {
-    "sr-tauri-v1".to_string()
+    "sr-tauri-v2".to_string()
 }
 
+/// Select the language of `CommandError.message` (uz | ru | en).
 #[tauri::command]
-pub async fn get_devices() -> Result<Vec<DeviceConfig>, String> {
-    Ok(load_devices())
+pub fn set_error_locale(locale: String) -> Result<String, CommandError> {
+    let parsed = Locale::parse(&locale)
+        .ok_or_else(|| CommandError::invalid_input("locale must be uz|ru|en").with_detail("locale", locale))?;
+    set_locale(parsed);
+    Ok(parsed.as_str().to_string())
 }
 
 #[tauri::command]
+pub async fn get_devices() -> Result<Vec<DeviceConfig>, CommandError> {
+    load_devices().map_err(CommandError::storage)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_device(
     backend_id: Option<String>,
     host: String,
@@ -16,54 +26,85 @@ pub async fn create_device(
     username: String,
     password: String,
     device_id: Option<String>,
-) -> Result<DeviceConfig, String> {
-    let mut devices = load_devices();
-    
+    scheme: Option<String>,
+    tls_fingerprint: Option<String>,
+    door_count: Option<u32>,
+    labels: Option<Vec<String>>,
+    direction: Option<DeviceDirection>,
+    school_id: Option<String>,
+    credentials_lifetime_days: Option<u32>,
+) -> Result<DeviceConfig, CommandError> {
+    let credentials_lifetime_days = parse_device_lifetime(credentials_lifetime_days)?;
+    let policy = load_credential_policy();
+    let door_count = validate_door_count(door_count.unwrap_or(1))?;
+    let labels = normalize_device_labels(labels.unwrap_or_default());
+    let school_id = school_id.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let scheme = normalize_device_scheme(scheme.as_deref()).map_err(CommandError::invalid_input)?;
+    let tls_fingerprint = tls_fingerprint
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty() && scheme == "https");
     let backend_id = backend_id
         .map(|v| v.trim().to_string())
         .filter(|v| !v.is_empty());
 
-    if let Some(existing) = devices.iter_mut().find(|d| d.backend_id == backend_id && backend_id.is_some()) {
-        let now = Utc::now();
-        let expires = now + Duration::days(30);
-        existing.host = host.trim().to_string();
-        existing.port = port;
-        existing.username = username.trim().to_string();
-        existing.password = password;
-        existing.device_id = device_id;
-        existing.credentials_updated_at = Some(now.to_rfc3339());
-        existing.credentials_expires_at = Some(expires.to_rfc3339());
-        let saved = existing.clone();
-        save_devices(&devices)?;
-        return Ok(saved);
-    }
+    with_devices_mut(|devices| {
+        if let Some(existing) = devices.iter_mut().find(|d| d.backend_id == backend_id && backend_id.is_some()) {
+            existing.host = host.trim().to_string();
+            existing.port = port;
+            existing.username = username.trim().to_string();
+            existing.password = password;
+            existing.device_id = device_id;
+            existing.scheme = Some(scheme);
+            existing.tls_fingerprint = tls_fingerprint;
+            existing.door_count = door_count;
+            existing.labels = labels;
+            existing.direction = direction.unwrap_or(existing.direction);
+            existing.school_id = school_id.or_else(|| existing.school_id.clone());
+            if let Some(lifetime_days) = credentials_lifetime_days {
+                existing.credentials_lifetime_days = lifetime_days;
+            }
+            policy.renew(existing, Utc::now());
+            return Ok(existing.clone());
+        }
 
-    let max_local_devices = get_max_local_devices();
-    if devices.len() >= max_local_devices {
-        return Err(format!("Maximum {} devices allowed", max_local_devices));
-    }
+        let max_local_devices = get_max_local_devices();
+        if devices.len() >= max_local_devices {
+            return Err(CommandError::new(ErrorCode::DeviceLimitReached)
+                .with_detail("max", max_local_devices)
+                .with_reason(format!("Maximum {} devices allowed", max_local_devices)));
+        }
 
-    let now = Utc::now();
-    let expires = now + Duration::days(30);
-    let device = DeviceConfig {
-        id: Uuid::new_v4().to_string(),
-        backend_id,
-        host: host.trim().to_string(),
-        port,
-        username: username.trim().to_string(),
-        password,
-        credentials_updated_at: Some(now.to_rfc3339()),
-        credentials_expires_at: Some(expires.to_rfc3339()),
-        device_id,
-    };
+        let mut device = DeviceConfig {
+            id: Uuid::new_v4().to_string(),
+            backend_id,
+            host: host.trim().to_string(),
+            port,
+            username: username.trim().to_string(),
+            password,
+            password_ref: None,
+            pending_password_ref: None,
+            credentials_updated_at: None,
+            credentials_expires_at: None,
+            credentials_lifetime_days: credentials_lifetime_days.flatten(),
+            device_id,
+            serial_number: None,
+            scheme: Some(scheme),
+            tls_fingerprint,
+            door_count,
+            labels,
+            direction: direction.unwrap_or_default(),
+            school_id,
+        };
+        policy.renew(&mut device, Utc::now());
 
-    devices.push(device.clone());
-    save_devices(&devices)?;
-    
-    Ok(device)
+        devices.push(device.clone());
+        Ok(device)
+    })
+    .map_err(CommandError::storage)?
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_device(
     id: String,
     backend_id: Option<String>,
@@ -72,105 +113,171 @@ pub async fn update_device(
     username: String,
     password: String,
     device_id: Option<String>,
-) -> Result<DeviceConfig, String> {
-    let mut devices = load_devices();
-    
-    let index = devices.iter().position(|d| d.id == id)
-        .ok_or("Device not found")?;
-
-    let now = Utc::now();
-    let expires = now + Duration::days(30);
-    let device = DeviceConfig {
-        id,
-        backend_id: backend_id.or_else(|| devices[index].backend_id.clone()),
-        host: host.trim().to_string(),
-        port,
-        username: username.trim().to_string(),
-        password,
-        credentials_updated_at: Some(now.to_rfc3339()),
-        credentials_expires_at: Some(expires.to_rfc3339()),
-        device_id: device_id.or_else(|| devices[index].device_id.clone()),
-    };
-    devices[index] = device.clone();
-    save_devices(&devices)?;
-    
-    Ok(device)
+    scheme: Option<String>,
+    tls_fingerprint: Option<String>,
+    door_count: Option<u32>,
+    labels: Option<Vec<String>>,
+    direction: Option<DeviceDirection>,
+    school_id: Option<String>,
+    credentials_lifetime_days: Option<u32>,
+) -> Result<DeviceConfig, CommandError> {
+    let credentials_lifetime_days = parse_device_lifetime(credentials_lifetime_days)?;
+    let policy = load_credential_policy();
+    let door_count = door_count.map(validate_door_count).transpose()?;
+    with_devices_mut(|devices| {
+        let index = devices.iter().position(|d| d.id == id)
+            .ok_or_else(|| device_not_found(&id))?;
+
+        let scheme = normalize_device_scheme(scheme.as_deref().or(devices[index].scheme.as_deref()))
+            .map_err(CommandError::invalid_input)?;
+        // A new endpoint means a new certificate; only keep the old pin for the same endpoint.
+        let same_endpoint = devices[index].host == host.trim()
+            && devices[index].port == port
+            && devices[index].scheme.as_deref().unwrap_or("http") == scheme;
+        let tls_fingerprint = tls_fingerprint
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .or_else(|| same_endpoint.then(|| devices[index].tls_fingerprint.clone()).flatten())
+            .filter(|_| scheme == "https");
+
+        let mut device = DeviceConfig {
+            id: id.clone(),
+            backend_id: backend_id.or_else(|| devices[index].backend_id.clone()),
+            host: host.trim().to_string(),
+            port,
+            username: username.trim().to_string(),
+            password,
+            password_ref: devices[index].password_ref.clone(),
+            pending_password_ref: devices[index].pending_password_ref.clone(),
+            credentials_updated_at: None,
+            credentials_expires_at: None,
+            credentials_lifetime_days: credentials_lifetime_days
+                .unwrap_or(devices[index].credentials_lifetime_days),
+            device_id: device_id.or_else(|| devices[index].device_id.clone()),
+            serial_number: devices[index].serial_number.clone(),
+            scheme: Some(scheme),
+            tls_fingerprint,
+            // Omitted fields keep their stored values; an empty `schoolId` clears the binding.
+            door_count: door_count.unwrap_or(devices[index].door_count),
+            labels: labels.map(normalize_device_labels).unwrap_or_else(|| devices[index].labels.clone()),
+            direction: direction.unwrap_or(devices[index].direction),
+            school_id: match school_id {
+                Some(v) => Some(v.trim().to_string()).filter(|v| !v.is_empty()),
+                None => devices[index].school_id.clone(),
+            },
+        };
+        policy.renew(&mut device, Utc::now());
+        devices[index] = device.clone();
+        Ok(device)
+    })
+    .map_err(CommandError::storage)?
 }
 
 #[tauri::command]
-pub async fn delete_device(id: String) -> Result<bool, String> {
-    let mut devices = load_devices();
-    let original_len = devices.len();
-    devices.retain(|d| d.id != id);
-    
-    if devices.len() == original_len {
-        return Err("Device not found".to_string());
+pub async fn delete_device(id: String) -> Result<bool, CommandError> {
+    let removed = remove_device(&id).map_err(CommandError::storage)?;
+
+    if !removed {
+        return Err(device_not_found(&id));
     }
-    
-    save_devices(&devices)?;
     Ok(true)
 }
 
 #[tauri::command]
-pub async fn test_device_connection(device_id: String) -> Result<DeviceConnectionResult, String> {
-    let mut devices = load_devices();
-    let index = devices
-        .iter()
-        .position(|d| d.id == device_id)
-        .ok_or("Device not found")?;
+pub async fn test_device_connection(device_id: String) -> Result<DeviceConnectionResult, CommandError> {
+    let mut device = get_device_by_id(&device_id).ok_or_else(|| device_not_found(&device_id))?;
+    ensure_credentials_valid(&device)?;
+    let client = HikvisionClient::new(device.clone());
+    let result = client.test_connection().await;
 
-    let device = devices[index].clone();
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
+    if apply_connection_identity(&mut device, &result) {
+        save_device_identity(std::slice::from_ref(&device));
     }
-    let client = HikvisionClient::new(device);
-    let result = client.test_connection().await;
 
-    if result.ok {
-        if let Some(found_id) = result.device_id.clone() {
-            if devices[index].device_id.as_deref() != Some(found_id.as_str()) {
-                devices[index].device_id = Some(found_id);
-                let _ = save_devices(&devices);
+    Ok(result)
+}
+
+/// Persist what a connection taught us (serial number, pinned certificate) onto the stored
+/// devices, leaving every other field as other commands may have changed it meanwhile.
+fn save_device_identity(updated: &[DeviceConfig]) {
+    let result = with_devices_mut(|devices| {
+        for device in updated {
+            if let Some(stored) = devices.iter_mut().find(|d| d.id == device.id) {
+                copy_connection_identity(stored, device);
             }
         }
+    });
+    if let Err(err) = result {
+        eprintln!("device identity not saved: {}", err);
     }
+}
 
-    Ok(result)
+fn validate_door_count(door_count: u32) -> Result<u32, CommandError> {
+    if !(1..=MAX_DOOR_COUNT).contains(&door_count) {
+        return Err(CommandError::invalid_input(format!("doorCount must be 1..{}", MAX_DOOR_COUNT))
+            .with_detail("doorCount", door_count));
+    }
+    Ok(door_count)
 }
 
-fn normalize_direction(direction: &str) -> Result<&'static str, String> {
-    match direction.trim().to_lowercase().as_str() {
-        "in" => Ok("in"),
-        "out" => Ok("out"),
-        _ => Err("direction must be in|out".to_string()),
+/// Trimmed, non-empty, without case-insensitive duplicates, in the order given.
+fn normalize_device_labels(labels: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for label in labels {
+        let label = label.trim();
+        if !label.is_empty() && !normalized.iter().any(|v| v.eq_ignore_ascii_case(label)) {
+            normalized.push(label.to_string());
+        }
     }
+    normalized
 }
 
-fn replace_url_fields(value: &mut Value, new_url: &str) -> usize {
-    match value {
-        Value::Object(map) => replace_url_fields_in_object(map, new_url),
-        Value::Array(items) => items
-            .iter_mut()
-            .map(|item| replace_url_fields(item, new_url))
-            .sum(),
-        _ => 0,
+fn device_not_found(device_id: &str) -> CommandError {
+    CommandError::device_not_found().with_detail("deviceId", device_id)
+}
+
+fn ensure_credentials_valid(device: &DeviceConfig) -> Result<(), CommandError> {
+    if is_credentials_expired(device) {
+        return Err(CommandError::credentials_expired()
+            .with_detail("deviceId", device.id.clone())
+            .with_detail("expiresAt", device.credentials_expires_at.clone()));
     }
+    Ok(())
 }
 
-fn replace_url_fields_in_object(map: &mut Map<String, Value>, new_url: &str) -> usize {
-    let mut changed = 0usize;
-    for (k, v) in map {
-        if v.is_string() && k.to_ascii_lowercase().contains("url") {
-            *v = Value::String(new_url.to_string());
-            changed += 1;
-            continue;
-        }
-        changed += replace_url_fields(v, new_url);
+/// Error for a failed device write, keeping the ISAPI status fields as details.
+fn action_error(result: &DeviceActionResult) -> CommandError {
+    let code = match result.status_string.as_deref() {
+        Some("ImageTooLarge") => ErrorCode::FaceTooLarge,
+        Some("InvalidImage") => ErrorCode::InvalidImage,
+        _ => ErrorCode::DeviceRejected,
+    };
+    let mut err = CommandError::new(code)
+        .with_detail("statusCode", result.status_code)
+        .with_detail("statusString", result.status_string.clone())
+        .with_detail("subStatusCode", result.sub_status_code.clone());
+    if let Some(message) = &result.error_msg {
+        err = err.with_reason(message.clone());
     }
-    changed
+    err
+}
+
+/// Stored device by id, rejected if its credentials have expired.
+fn load_active_device(device_id: &str) -> Result<DeviceConfig, CommandError> {
+    let device = get_device_by_id(device_id).ok_or_else(|| device_not_found(device_id))?;
+    ensure_credentials_valid(&device)?;
+    Ok(device)
 }
 
-async fn read_device_webhook_config(client: &HikvisionClient) -> Result<(String, Value), String> {
+fn normalize_direction(direction: &str) -> Result<&'static str, CommandError> {
+    match direction.trim().to_lowercase().as_str() {
+        "in" => Ok("in"),
+        "out" => Ok("out"),
+        _ => Err(CommandError::invalid_input("direction must be in|out")),
+    }
+}
+
+async fn read_device_webhook_config(client: &HikvisionClient) -> Result<(String, Value), CommandError> {
     let mut errors = Vec::<String>::new();
     for path in WEBHOOK_CANDIDATE_PATHS {
         match client.get_isapi_json(path).await {
@@ -178,10 +285,7 @@ async fn read_device_webhook_config(client: &HikvisionClient) -> Result<(String,
             Err(err) => errors.push(format!("{} => {}", path, err)),
         }
     }
-    Err(format!(
-        "Webhook config o'qib bo'lmadi. Sinovlar: {}",
-        errors.join(" | ")
-    ))
+    Err(CommandError::new(ErrorCode::WebhookReadFailed).with_detail("attempts", errors))
 }
 
 fn decode_markup_entities(input: &str) -> String {
@@ -293,3 +397,55 @@ fn response_status_ok(value: &Value) -> bool {
     true
 }
 
+
+#[cfg(test)]
+mod device_and_webhook_a_tests {
+    use super::*;
+    use crate::domain::repositories::DeviceRepository;
+    use crate::infrastructure::storage::sqlite_store::SqliteStore;
+    use hyper::service::{make_service_fn, service_fn};
+
+    /// Device double answering every request with a `deviceInfo` document.
+    async fn spawn_device_info(serial: &'static str) -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, std::convert::Infallible>(service_fn(move |_req| async move {
+                let body = serde_json::json!({ "DeviceInfo": { "deviceID": "isapi-1", "serialNumber": serial } });
+                Ok::<_, std::convert::Infallible>(hyper::Response::new(hyper::Body::from(body.to_string())))
+            }))
+        });
+        let server = hyper::Server::from_tcp(listener).unwrap().serve(make_service);
+        tokio::spawn(server);
+        port
+    }
+
+    #[tokio::test]
+    async fn serial_learned_on_connect_is_stored_and_matches_discovery() {
+        let port = spawn_device_info("DS-K1T671-0001").await;
+        let store = SqliteStore::open_in_memory().unwrap();
+        let configured = DeviceConfig {
+            port,
+            ..crate::domain::entities::device::test_device("d1")
+        };
+        store.update_devices(&mut |devices| devices.push(configured.clone())).unwrap();
+
+        let mut device = store.get_device("d1").unwrap().unwrap();
+        let result = HikvisionClient::new(device.clone()).test_connection().await;
+        assert!(apply_connection_identity(&mut device, &result));
+        store
+            .update_devices(&mut |devices| copy_connection_identity(&mut devices[0], &device))
+            .unwrap();
+
+        let stored = store.list_devices().unwrap();
+        assert_eq!(stored[0].serial_number.as_deref(), Some("DS-K1T671-0001"));
+        let mut discovered = [DiscoveredDevice {
+            serial_number: Some("ds-k1t671-0001".to_string()),
+            ipv4_address: "10.0.0.50".to_string(),
+            http_port: Some(80),
+            ..Default::default()
+        }];
+        match_discovered_devices(&mut discovered, &stored);
+        assert_eq!(discovered[0].local_device_id.as_deref(), Some("d1"));
+    }
+}
@@ -0,0 +1,86 @@
+const CREDENTIAL_POLICY_SETTING: &str = "credentialPolicy";
+
+fn load_credential_policy() -> CredentialPolicy {
+    match load_setting::<CredentialPolicy>(CREDENTIAL_POLICY_SETTING) {
+        Ok(Some(policy)) => policy,
+        Ok(None) => default_credential_policy(),
+        Err(err) => {
+            eprintln!("credential policy not loaded, using defaults: {}", err);
+            default_credential_policy()
+        }
+    }
+}
+
+/// `credentialsLifetimeDays` from create/update: `0` drops the device override (back to the
+/// school / default lifetime), anything else must be a valid lifetime.
+fn parse_device_lifetime(days: Option<u32>) -> Result<Option<Option<u32>>, CommandError> {
+    match days {
+        None => Ok(None),
+        Some(0) => Ok(Some(None)),
+        Some(days) => validate_lifetime_days(days)
+            .map(|days| Some(Some(days)))
+            .map_err(CommandError::invalid_input),
+    }
+}
+
+#[tauri::command]
+pub async fn get_credential_policy() -> Result<CredentialPolicy, CommandError> {
+    Ok(load_credential_policy())
+}
+
+#[tauri::command]
+pub async fn set_credential_policy(policy: CredentialPolicy) -> Result<CredentialPolicy, CommandError> {
+    let mut policy = policy;
+    policy.school_lifetime_days = policy
+        .school_lifetime_days
+        .into_iter()
+        .map(|(school_id, days)| (school_id.trim().to_string(), days))
+        .filter(|(school_id, _)| !school_id.is_empty())
+        .collect();
+    policy.validate().map_err(CommandError::invalid_input)?;
+    save_setting(CREDENTIAL_POLICY_SETTING, &policy).map_err(CommandError::storage)?;
+    Ok(policy)
+}
+
+/// Re-check the saved credentials against the device and, only if they still work, start a
+/// new lifetime. Works on expired devices too; that is what it is for.
+#[tauri::command]
+pub async fn renew_device_credentials(
+    device_id: String,
+    lifetime_days: Option<u32>,
+) -> Result<DeviceConfig, CommandError> {
+    let lifetime_days = parse_device_lifetime(lifetime_days)?;
+    let mut device = get_device_by_id(&device_id).ok_or_else(|| device_not_found(&device_id))?;
+
+    let result = HikvisionClient::new(device.clone()).test_connection().await;
+    if !result.ok {
+        return Err(CommandError::new(ErrorCode::CredentialsRenewalFailed)
+            .with_detail("deviceId", device_id)
+            .with_reason(result.message.unwrap_or_else(|| "connection test failed".to_string())));
+    }
+    apply_connection_identity(&mut device, &result);
+
+    let policy = load_credential_policy();
+    with_devices_mut(|devices| {
+        let stored = devices
+            .iter_mut()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| device_not_found(&device_id))?;
+        copy_connection_identity(stored, &device);
+        if let Some(lifetime_days) = lifetime_days {
+            stored.credentials_lifetime_days = lifetime_days;
+        }
+        policy.renew(stored, Utc::now());
+        Ok(stored.clone())
+    })
+    .map_err(CommandError::storage)?
+}
+
+/// Devices whose credentials expire within `within_days` (default: the policy's `warnDays`),
+/// including already expired ones, soonest first.
+#[tauri::command]
+pub async fn list_expiring_devices(within_days: Option<u32>) -> Result<Vec<ExpiringDevice>, CommandError> {
+    let within_days = within_days.unwrap_or_else(|| load_credential_policy().warn_days);
+    let devices = load_devices().map_err(CommandError::storage)?;
+    Ok(expiring_devices(&devices, Utc::now(), within_days))
+}
@@ -0,0 +1,53 @@
+const DISCOVERY_TIMEOUT_MS: u64 = 3000;
+const MAX_DISCOVERY_TIMEOUT_MS: u64 = 15_000;
+
+/// Find Hikvision devices on the local network via SADP multicast. Devices that are already
+/// configured carry their local id in `localDeviceId`; the others can be passed to
+/// `create_device` as `host = ipv4Address`, `port = httpPort`.
+#[tauri::command]
+pub async fn discover_devices(timeout_ms: Option<u64>) -> Result<Vec<DiscoveredDevice>, CommandError> {
+    let timeout_ms = timeout_ms.unwrap_or(DISCOVERY_TIMEOUT_MS).clamp(500, MAX_DISCOVERY_TIMEOUT_MS);
+    let mut discovered = discover_sadp_devices(std::time::Duration::from_millis(timeout_ms))
+        .await
+        .map_err(|reason| CommandError::new(ErrorCode::DiscoveryFailed).with_reason(reason))?;
+    let devices = load_devices().map_err(CommandError::storage)?;
+    match_discovered_devices(&mut discovered, &devices);
+    Ok(discovered)
+}
+
+const SCAN_CONNECT_TIMEOUT_MS: u64 = 700;
+const SCAN_CONCURRENCY: u32 = 64;
+const MAX_SCAN_CONCURRENCY: u32 = 256;
+
+/// Fallback for networks without multicast: probe every address of `cidr` (default: the network
+/// of this PC's outgoing interface from its netmask, no wider than `/22`, `/24` when the netmask
+/// is unknown) on ports 80/8080/443/8000 and keep what fingerprints as Hikvision ISAPI.
+/// Returns the same list as `discover_devices`; a device found on 443 needs `scheme = https`.
+#[tauri::command]
+pub async fn scan_devices(
+    cidr: Option<String>,
+    timeout_ms: Option<u64>,
+    concurrency: Option<u32>,
+) -> Result<Vec<DiscoveredDevice>, CommandError> {
+    let cidr = match cidr.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+        Some(cidr) => cidr,
+        None => default_scan_cidr().map_err(|reason| CommandError::new(ErrorCode::DiscoveryFailed).with_reason(reason))?,
+    };
+    let hosts = parse_cidr(&cidr)
+        .and_then(|(ip, prefix)| cidr_hosts(ip, prefix))
+        .map_err(|reason| CommandError::invalid_input(reason).with_detail("cidr", cidr.clone()))?;
+    let timeout_ms = timeout_ms.unwrap_or(SCAN_CONNECT_TIMEOUT_MS).clamp(100, 5000);
+    let concurrency = concurrency.unwrap_or(SCAN_CONCURRENCY).clamp(1, MAX_SCAN_CONCURRENCY);
+
+    let mut discovered = scan_hosts(
+        hosts,
+        &SCAN_PORTS,
+        concurrency as usize,
+        std::time::Duration::from_millis(timeout_ms),
+    )
+    .await
+    .map_err(|reason| CommandError::new(ErrorCode::DiscoveryFailed).with_detail("cidr", cidr).with_reason(reason))?;
+    let devices = load_devices().map_err(CommandError::storage)?;
+    match_discovered_devices(&mut discovered, &devices);
+    Ok(discovered)
+}
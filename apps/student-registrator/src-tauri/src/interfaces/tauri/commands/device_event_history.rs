@@ -0,0 +1,224 @@
+// ============ Access Event History ============
+//
+// Devices keep their access log; events whose webhook was lost (school offline, VPS down) are
+// read back with the `AcsEvent` search and replayed to the backend webhook. A high-water mark
+// per device (`eventBackfillMarks` setting) makes a re-run send only what is new.
+
+/// Most firmware caps `maxResults` at 30.
+const ACS_EVENT_PAGE_SIZE: u32 = 30;
+const FETCH_ACCESS_EVENTS_LIMIT: u32 = 100;
+const MAX_FETCH_ACCESS_EVENTS: u32 = 1000;
+const MAX_BACKFILL_EVENTS: u32 = 5000;
+const BACKFILL_DEFAULT_DAYS: i64 = 7;
+const BACKFILL_POST_TIMEOUT_SECS: u64 = 15;
+const EVENT_BACKFILL_MARKS_SETTING: &str = "eventBackfillMarks";
+
+/// Pages of one search from `offset`, until `limit` events or the last page.
+async fn collect_access_events(
+    client: &HikvisionClient,
+    filter: &AccessEventFilter,
+    offset: u32,
+    limit: u32,
+) -> Result<AccessEventPage, HikvisionError> {
+    let search_id = Uuid::new_v4().to_string();
+    let mut collected = AccessEventPage {
+        next_position: Some(offset),
+        ..Default::default()
+    };
+    while let Some(position) = collected.next_position {
+        let remaining = limit.saturating_sub(collected.events.len() as u32);
+        if remaining == 0 {
+            break;
+        }
+        let page = client
+            .search_access_events(filter, &search_id, position, remaining.min(ACS_EVENT_PAGE_SIZE))
+            .await?;
+        collected.total_matches = page.total_matches;
+        collected.next_position = page.next_position;
+        collected.events.extend(page.events);
+    }
+    Ok(collected)
+}
+
+/// `[start, end]` in device time; `start` defaults to `default_start`, `end` to now.
+fn event_time_range(
+    start_time: Option<&str>,
+    end_time: Option<&str>,
+    default_start: chrono::DateTime<Local>,
+) -> Result<(String, String), CommandError> {
+    let parse = |value: Option<&str>, field: &str| {
+        value
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| parse_ui_time(v).map_err(|reason| CommandError::invalid_input(reason).with_detail("field", field)))
+            .transpose()
+    };
+    let start = parse(start_time, "startTime")?.unwrap_or(default_start);
+    let end = parse(end_time, "endTime")?.unwrap_or_else(Local::now);
+    if start > end {
+        return Err(CommandError::invalid_input("startTime is after endTime"));
+    }
+    Ok((to_device_event_time(start), to_device_event_time(end)))
+}
+
+fn load_backfill_marks() -> HashMap<String, EventBackfillMark> {
+    match load_setting::<HashMap<String, EventBackfillMark>>(EVENT_BACKFILL_MARKS_SETTING) {
+        Ok(marks) => marks.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("event backfill marks not loaded: {}", err);
+            HashMap::new()
+        }
+    }
+}
+
+fn save_backfill_mark(device_id: &str, mark: &EventBackfillMark) -> Result<(), String> {
+    let mut marks = load_backfill_marks();
+    marks.insert(device_id.to_string(), mark.clone());
+    save_setting(EVENT_BACKFILL_MARKS_SETTING, &marks)
+}
+
+/// Webhook body in the shape the device itself posts. `deviceID` / `dateTime` are repeated
+/// inside the event because the backend reads JSON bodies from `AccessControllerEvent` only.
+fn backfill_webhook_payload(event: &AccessEvent, backend_device_id: &str) -> Value {
+    serde_json::json!({
+        "deviceID": backend_device_id,
+        "dateTime": event.time,
+        "eventType": "AccessControllerEvent",
+        "eventState": "active",
+        "AccessControllerEvent": {
+            "deviceID": backend_device_id,
+            "dateTime": event.time,
+            "majorEventType": event.major,
+            "subEventType": event.minor,
+            "employeeNoString": event.employee_no,
+            "name": event.name,
+            "doorNo": event.door_no,
+            "currentVerifyMode": event.verify_mode,
+            "serialNo": event.serial_no,
+            "backfill": true,
+        }
+    })
+}
+
+async fn post_backfill_event(http: &Client, webhook_url: &str, payload: &Value) -> Result<(), String> {
+    let res = http.post(webhook_url).json(payload).send().await.map_err(|e| e.to_string())?;
+    let status = res.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = res.text().await.unwrap_or_default();
+    Err(format!("HTTP {}: {}", status.as_u16(), body.trim()))
+}
+
+/// Read the device access log (`ISAPI/AccessControl/AcsEvent`). `major` / `minor` 0 (default)
+/// match every type; 5 / 75 (`ACCESS_EVENT_MAJOR` / `FACE_AUTH_PASSED_MINOR`) are the events
+/// `backfill_events_to_backend` sends. `offset` is the `searchResultPosition` to start from.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_access_events(
+    device_id: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    major: Option<u32>,
+    minor: Option<u32>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<AccessEventPage, CommandError> {
+    let device = load_active_device(&device_id)?;
+    let (start_time, end_time) = event_time_range(
+        start_time.as_deref(),
+        end_time.as_deref(),
+        Local::now() - Duration::days(1),
+    )?;
+    let filter = AccessEventFilter {
+        start_time,
+        end_time,
+        major: major.unwrap_or(0),
+        minor: minor.unwrap_or(0),
+    };
+    let limit = limit.unwrap_or(FETCH_ACCESS_EVENTS_LIMIT).clamp(1, MAX_FETCH_ACCESS_EVENTS);
+    let client = HikvisionClient::new(device);
+    collect_access_events(&client, &filter, offset.unwrap_or(0), limit)
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id))
+}
+
+/// Send the device's successful face events that the backend may have missed to
+/// `webhook_url` (the school's in/out webhook, with its secret). Only events after the stored
+/// mark are sent, oldest first; the mark is saved after every accepted event, so a failed or
+/// interrupted run can simply be repeated. `reset` ignores the mark, e.g. after a device
+/// factory reset restarted its serial numbers; the backend drops events it already has.
+#[tauri::command]
+pub async fn backfill_events_to_backend(
+    device_id: String,
+    webhook_url: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    reset: Option<bool>,
+) -> Result<EventBackfillReport, CommandError> {
+    let device = load_active_device(&device_id)?;
+    let webhook_url = webhook_url.trim().to_string();
+    if !matches!(reqwest::Url::parse(&webhook_url).map(|url| url.scheme().to_string()).as_deref(), Ok("http" | "https")) {
+        return Err(CommandError::invalid_input("webhookUrl must be an http(s) URL"));
+    }
+    let client = HikvisionClient::new(device.clone());
+    let backend_device_id = match device.device_id.clone().filter(|id| !id.trim().is_empty()) {
+        Some(id) => id,
+        None => {
+            let connection = client.test_connection().await;
+            connection.device_id.filter(|_| connection.ok).ok_or_else(|| {
+                CommandError::new(ErrorCode::DeviceInvalidResponse)
+                    .with_detail("deviceId", device_id.clone())
+                    .with_reason(connection.message.unwrap_or_else(|| "deviceID is unknown".to_string()))
+            })?
+        }
+    };
+
+    let mut mark = if reset.unwrap_or(false) {
+        EventBackfillMark::default()
+    } else {
+        load_backfill_marks().remove(&device_id).unwrap_or_default()
+    };
+    let default_start = mark
+        .time
+        .as_deref()
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Local))
+        .unwrap_or_else(|| Local::now() - Duration::days(BACKFILL_DEFAULT_DAYS));
+    let (start_time, end_time) = event_time_range(start_time.as_deref(), end_time.as_deref(), default_start)?;
+    let filter = AccessEventFilter::face_auth_passed(start_time.clone(), end_time.clone());
+    let fetched = collect_access_events(&client, &filter, 0, MAX_BACKFILL_EVENTS)
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id.clone()))?
+        .events;
+
+    let mut report = EventBackfillReport {
+        device_id: device_id.clone(),
+        start_time,
+        end_time,
+        fetched: fetched.len(),
+        ..Default::default()
+    };
+    let pending = events_after_mark(fetched, &mark);
+    report.skipped = report.fetched - pending.len();
+
+    let http = Client::builder()
+        .timeout(std::time::Duration::from_secs(BACKFILL_POST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| CommandError::new(ErrorCode::BackendRejected).with_reason(e.to_string()))?;
+    for event in &pending {
+        if let Err(err) = post_backfill_event(&http, &webhook_url, &backfill_webhook_payload(event, &backend_device_id)).await {
+            report.error = Some(err);
+            break;
+        }
+        mark.advance(event);
+        report.sent += 1;
+        // Saved per event: a run cut short (app closed, crash) must not resend what was accepted.
+        if let Err(err) = save_backfill_mark(&device_id, &mark) {
+            report.error = Some(format!("mark not saved: {}", err));
+            break;
+        }
+    }
+    report.mark = mark;
+    Ok(report)
+}
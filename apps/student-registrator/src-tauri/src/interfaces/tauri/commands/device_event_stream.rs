@@ -0,0 +1,224 @@
+// ============ Device Event Stream ============
+//
+// `start_event_stream` keeps an `alertStream` subscription open to one device in a background
+// task. Every access event goes to the UI as `device-access-event`, connection changes as
+// `device-event-stream-status`. A dropped connection is reopened with exponential backoff until
+// `stop_event_stream`; rejected credentials stop the subscription instead, so the device does
+// not lock the account. The last events per device are kept in memory, without captures.
+
+const ACCESS_EVENT_EVENT: &str = "device-access-event";
+const EVENT_STREAM_STATUS_EVENT: &str = "device-event-stream-status";
+/// Devices send heartbeat alerts every 10-30 s; this much silence means a dead connection.
+const EVENT_STREAM_IDLE_SECS: u64 = 90;
+const EVENT_STREAM_RETRY_MIN_MS: u64 = 1000;
+const EVENT_STREAM_RETRY_MAX_MS: u64 = 60_000;
+const MAX_RECENT_ACCESS_EVENTS: usize = 200;
+
+struct EventStreamControl {
+    stop: tokio::sync::watch::Sender<bool>,
+    status: Mutex<EventStreamStatus>,
+}
+
+impl EventStreamControl {
+    fn status(&self) -> EventStreamStatus {
+        self.status.lock().map(|status| status.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// Update the status and mirror it to the UI.
+    fn report(&self, app: &AppHandle, update: impl FnOnce(&mut EventStreamStatus)) {
+        let status = match self.status.lock() {
+            Ok(mut status) => {
+                update(&mut status);
+                status.clone()
+            }
+            Err(_) => return,
+        };
+        let _ = app.emit_all(EVENT_STREAM_STATUS_EVENT, status);
+    }
+}
+
+/// Subscriptions with a live task in this process, by local device id.
+fn event_stream_controls() -> &'static Mutex<HashMap<String, Arc<EventStreamControl>>> {
+    static CONTROLS: OnceLock<Mutex<HashMap<String, Arc<EventStreamControl>>>> = OnceLock::new();
+    CONTROLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn recent_access_events() -> &'static Mutex<HashMap<String, VecDeque<AccessEvent>>> {
+    static EVENTS: OnceLock<Mutex<HashMap<String, VecDeque<AccessEvent>>>> = OnceLock::new();
+    EVENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn remember_access_event(event: &AccessEvent) {
+    let Ok(mut recent) = recent_access_events().lock() else {
+        return;
+    };
+    let events = recent.entry(event.device_id.clone()).or_default();
+    if events.len() >= MAX_RECENT_ACCESS_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(AccessEvent {
+        picture_base64: None,
+        ..event.clone()
+    });
+}
+
+enum EventStreamEnd {
+    /// Stream closed or broke; worth reconnecting.
+    Lost(String),
+    /// Reconnecting cannot help (credentials rejected, device removed).
+    Fatal(String),
+}
+
+/// One connection: open the stream and forward events until it ends.
+async fn forward_device_events(app: &AppHandle, control: &EventStreamControl, device: DeviceConfig) -> EventStreamEnd {
+    let client = HikvisionClient::for_event_stream(device);
+    let mut alerts = match client.open_alert_stream().await {
+        Ok(alerts) => alerts,
+        Err(err @ HikvisionError::Unauthorized(_)) => return EventStreamEnd::Fatal(err.to_string()),
+        Err(err) => return EventStreamEnd::Lost(err.to_string()),
+    };
+    control.report(app, |status| {
+        status.state = EventStreamState::Connected;
+        status.error = None;
+        status.retry_in_ms = None;
+    });
+    let idle = std::time::Duration::from_secs(EVENT_STREAM_IDLE_SECS);
+    loop {
+        match alerts.next_event(idle).await {
+            Ok(Some(event)) => {
+                remember_access_event(&event);
+                let _ = app.emit_all(ACCESS_EVENT_EVENT, &event);
+                if let Ok(mut status) = control.status.lock() {
+                    status.received += 1;
+                }
+            }
+            Ok(None) => return EventStreamEnd::Lost("device closed the event stream".to_string()),
+            Err(HikvisionError::Timeout) => {
+                return EventStreamEnd::Lost(format!("no data for {} s", EVENT_STREAM_IDLE_SECS))
+            }
+            Err(err) => return EventStreamEnd::Lost(err.to_string()),
+        }
+    }
+}
+
+async fn run_event_stream(app: AppHandle, device_id: String, control: Arc<EventStreamControl>) {
+    let mut stop = control.stop.subscribe();
+    let mut retry_ms = EVENT_STREAM_RETRY_MIN_MS;
+    let error = loop {
+        // Reloaded for every attempt so edited addresses and rotated passwords are picked up.
+        let Some(device) = get_device_by_id(&device_id) else {
+            break Some("device was removed".to_string());
+        };
+        control.report(&app, |status| status.state = EventStreamState::Connecting);
+        let end = tokio::select! {
+            _ = stop.wait_for(|stop| *stop) => break None,
+            end = forward_device_events(&app, &control, device) => end,
+        };
+        let reason = match end {
+            EventStreamEnd::Fatal(reason) => break Some(reason),
+            EventStreamEnd::Lost(reason) => reason,
+        };
+        // A connection that got as far as CONNECTED starts the backoff over.
+        if control.status().state == EventStreamState::Connected {
+            retry_ms = EVENT_STREAM_RETRY_MIN_MS;
+        }
+        control.report(&app, |status| {
+            status.state = EventStreamState::Reconnecting;
+            status.error = Some(reason);
+            status.retry_in_ms = Some(retry_ms);
+        });
+        tokio::select! {
+            _ = stop.wait_for(|stop| *stop) => break None,
+            _ = tokio::time::sleep(std::time::Duration::from_millis(retry_ms)) => {}
+        }
+        retry_ms = (retry_ms * 2).min(EVENT_STREAM_RETRY_MAX_MS);
+    };
+
+    if let Ok(mut controls) = event_stream_controls().lock() {
+        if controls.get(&device_id).is_some_and(|live| Arc::ptr_eq(live, &control)) {
+            controls.remove(&device_id);
+        }
+    }
+    control.report(&app, |status| {
+        status.state = EventStreamState::Stopped;
+        status.retry_in_ms = None;
+        if error.is_some() {
+            status.error = error;
+        }
+    });
+}
+
+/// Subscribe to the access events of a device. Events arrive as `device-access-event`,
+/// subscription state as `device-event-stream-status`. Starting a running subscription
+/// returns its current status.
+#[tauri::command]
+pub async fn start_event_stream(app: AppHandle, device_id: String) -> Result<EventStreamStatus, CommandError> {
+    let device = get_device_by_id(&device_id).ok_or_else(|| device_not_found(&device_id))?;
+    ensure_credentials_valid(&device)?;
+    let mut controls = event_stream_controls()
+        .lock()
+        .map_err(|_| CommandError::storage("event stream registry is unavailable"))?;
+    if let Some(live) = controls.get(&device_id) {
+        return Ok(live.status());
+    }
+    let control = Arc::new(EventStreamControl {
+        stop: tokio::sync::watch::channel(false).0,
+        status: Mutex::new(EventStreamStatus {
+            device_id: device_id.clone(),
+            state: EventStreamState::Connecting,
+            error: None,
+            retry_in_ms: None,
+            received: 0,
+        }),
+    });
+    controls.insert(device_id.clone(), control.clone());
+    drop(controls);
+    let status = control.status();
+    tauri::async_runtime::spawn(run_event_stream(app, device_id, control));
+    Ok(status)
+}
+
+/// End the subscription of a device. Returns false when none was running.
+#[tauri::command]
+pub async fn stop_event_stream(device_id: String) -> Result<bool, CommandError> {
+    let control = event_stream_controls()
+        .lock()
+        .map_err(|_| CommandError::storage("event stream registry is unavailable"))?
+        .remove(&device_id);
+    match control {
+        Some(control) => {
+            control.stop.send_replace(true);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub async fn list_event_streams() -> Result<Vec<EventStreamStatus>, CommandError> {
+    let controls = event_stream_controls()
+        .lock()
+        .map_err(|_| CommandError::storage("event stream registry is unavailable"))?;
+    let mut statuses: Vec<EventStreamStatus> = controls.values().map(|control| control.status()).collect();
+    statuses.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+    Ok(statuses)
+}
+
+/// Events received by the subscriptions of this session, oldest first, without captures.
+#[tauri::command]
+pub async fn list_recent_access_events(
+    device_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<AccessEvent>, CommandError> {
+    let recent = recent_access_events()
+        .lock()
+        .map_err(|_| CommandError::storage("access event buffer is unavailable"))?;
+    let mut events: Vec<AccessEvent> = recent
+        .iter()
+        .filter(|(id, _)| device_id.as_deref().is_none_or(|wanted| wanted == id.as_str()))
+        .flat_map(|(_, events)| events.iter().cloned())
+        .collect();
+    events.sort_by(|a, b| a.time.cmp(&b.time));
+    let limit = limit.unwrap_or(MAX_RECENT_ACCESS_EVENTS as u32) as usize;
+    Ok(events.split_off(events.len().saturating_sub(limit)))
+}
@@ -4,7 +4,9 @@ pub async fn probe_device_connection(
     port: u16,
     username: String,
     password: String,
-) -> Result<DeviceConnectionResult, String> {
+    scheme: Option<String>,
+) -> Result<DeviceConnectionResult, CommandError> {
+    let scheme = normalize_device_scheme(scheme.as_deref()).map_err(CommandError::invalid_input)?;
     let now = Utc::now();
     let expires = now + Duration::days(30);
     let device = DeviceConfig {
@@ -14,9 +16,19 @@ pub async fn probe_device_connection(
         port,
         username: username.trim().to_string(),
         password,
+        password_ref: None,
+        pending_password_ref: None,
         credentials_updated_at: Some(now.to_rfc3339()),
         credentials_expires_at: Some(expires.to_rfc3339()),
+        credentials_lifetime_days: None,
         device_id: None,
+        serial_number: None,
+        scheme: Some(scheme),
+        tls_fingerprint: None,
+        door_count: 1,
+        labels: Vec::new(),
+        direction: DeviceDirection::Both,
+        school_id: None,
     };
 
     let client = HikvisionClient::new(device);
@@ -24,35 +36,29 @@ pub async fn probe_device_connection(
 }
 
 #[tauri::command]
-pub async fn get_device_capabilities(device_id: String) -> Result<Value, String> {
-    let device = get_device_by_id(&device_id).ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+pub async fn get_device_capabilities(device_id: String) -> Result<Value, CommandError> {
+    let device = load_active_device(&device_id)?;
     let client = HikvisionClient::new(device);
     Ok(client.probe_capabilities().await)
 }
 
 #[tauri::command]
-pub async fn get_device_configuration(device_id: String) -> Result<Value, String> {
-    let device = get_device_by_id(&device_id).ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+pub async fn get_device_configuration(device_id: String) -> Result<Value, CommandError> {
+    let device = load_active_device(&device_id)?;
     let client = HikvisionClient::new(device);
 
     let time = client
         .get_isapi_json("ISAPI/System/time?format=json")
         .await
-        .unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
     let ntp = client
         .get_isapi_json("ISAPI/System/Network/ntpServers?format=json")
         .await
-        .unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
     let network = client
         .get_isapi_json("ISAPI/System/Network/interfaces?format=json")
         .await
-        .unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
 
     Ok(serde_json::json!({
         "time": time,
@@ -66,21 +72,21 @@ pub async fn update_device_configuration(
     device_id: String,
     config_type: String,
     payload: Value,
-) -> Result<Value, String> {
-    let device = get_device_by_id(&device_id).ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+) -> Result<Value, CommandError> {
+    let device = load_active_device(&device_id)?;
     let client = HikvisionClient::new(device);
 
     let path = match config_type.as_str() {
         "time" => "ISAPI/System/time?format=json",
         "ntpServers" => "ISAPI/System/Network/ntpServers?format=json",
         "networkInterfaces" => "ISAPI/System/Network/interfaces?format=json",
-        _ => return Err("Unsupported configType".to_string()),
+        _ => {
+            return Err(CommandError::invalid_input("Unsupported configType")
+                .with_detail("configType", config_type))
+        }
     };
     if !payload.is_object() {
-        return Err("payload must be JSON object".to_string());
+        return Err(CommandError::invalid_input("payload must be JSON object"));
     }
 
     let caps = client.probe_capabilities().await;
@@ -90,14 +96,15 @@ pub async fn update_device_configuration(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
     if !supported {
-        return Err(format!("{} not supported on this device", config_type));
+        return Err(CommandError::new(ErrorCode::DeviceUnsupported)
+            .with_detail("configType", config_type));
     }
 
     // Snapshot before write for safer rollback workflows.
     let before = client
         .get_isapi_json(path)
         .await
-        .unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
 
     let after = client.put_isapi_json(path, payload).await?;
     Ok(serde_json::json!({
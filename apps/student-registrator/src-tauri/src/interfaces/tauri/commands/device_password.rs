@@ -0,0 +1,179 @@
+/// Logins with a just-changed password are retried: some firmware restarts its web service
+/// or keeps old sessions for a moment after `Security/users` is updated.
+const PASSWORD_VERIFY_ATTEMPTS: u32 = 4;
+const PASSWORD_VERIFY_DELAY_MS: u64 = 1500;
+
+async fn verify_device_login(device: &DeviceConfig) -> DeviceConnectionResult {
+    let mut result = HikvisionClient::new(device.clone()).test_connection().await;
+    for _ in 1..PASSWORD_VERIFY_ATTEMPTS {
+        if result.ok {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(PASSWORD_VERIFY_DELAY_MS)).await;
+        result = HikvisionClient::new(device.clone()).test_connection().await;
+    }
+    result
+}
+
+/// Put `old_password` back on the device, logging in with the rotated one, and check it.
+async fn restore_device_password(rotated: &DeviceConfig, user_id: &str, old_password: &str) -> Result<(), String> {
+    HikvisionClient::new(rotated.clone())
+        .change_login_password(user_id, old_password)
+        .await
+        .map_err(|err| err.to_string())?;
+    let mut original = rotated.clone();
+    original.password = old_password.to_string();
+    let check = verify_device_login(&original).await;
+    if check.ok {
+        Ok(())
+    } else {
+        Err(check.message.unwrap_or_else(|| "old password rejected after rollback".to_string()))
+    }
+}
+
+/// Make `password` the device password and drop the pending one.
+fn save_rotated_password(
+    device_id: &str,
+    password: &str,
+    connection: Option<&DeviceConnectionResult>,
+    policy: &CredentialPolicy,
+) -> Result<DeviceConfig, String> {
+    with_devices_mut(|devices| {
+        let stored = devices
+            .iter_mut()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| "device was removed during rotation".to_string())?;
+        stored.password = password.to_string();
+        stored.pending_password_ref = None;
+        if let Some(connection) = connection {
+            apply_connection_identity(stored, connection);
+        }
+        policy.renew(stored, Utc::now());
+        Ok(stored.clone())
+    })?
+}
+
+/// The device keeps its current password; forget the pending one.
+fn discard_pending_password(device_id: &str) -> Result<(), String> {
+    with_devices_mut(|devices| {
+        if let Some(stored) = devices.iter_mut().find(|d| d.id == device_id) {
+            stored.pending_password_ref = None;
+        }
+    })
+}
+
+/// Finish a rotation that was interrupted after the pending password was saved: whichever of
+/// the stored and the pending password the device accepts is kept. Fails, leaving both saved,
+/// when the device takes neither (e.g. it is offline).
+async fn settle_pending_password(device: DeviceConfig) -> Result<DeviceConfig, String> {
+    let Some(pending) = load_pending_password(&device)? else {
+        return Ok(device);
+    };
+    if HikvisionClient::new(device.clone()).test_connection().await.ok {
+        discard_pending_password(&device.id)?;
+        return get_device_by_id(&device.id).ok_or_else(|| "device was removed".to_string());
+    }
+    let mut rotated = device.clone();
+    rotated.password = pending.clone();
+    let connection = HikvisionClient::new(rotated).test_connection().await;
+    if !connection.ok {
+        return Err(connection
+            .message
+            .unwrap_or_else(|| "device accepts neither the stored nor the pending password".to_string()));
+    }
+    save_rotated_password(&device.id, &pending, Some(&connection), &load_credential_policy())
+}
+
+/// Settle rotations interrupted by a crash or a closed app, in the background on startup.
+pub fn settle_pending_device_passwords() {
+    tauri::async_runtime::spawn(async {
+        let devices = match load_devices() {
+            Ok(devices) => devices,
+            Err(err) => {
+                eprintln!("pending device passwords not checked: {}", err);
+                return;
+            }
+        };
+        for device in devices.into_iter().filter(|d| d.pending_password_ref.is_some()) {
+            let device_id = device.id.clone();
+            if let Err(err) = settle_pending_password(device).await {
+                eprintln!("device {}: pending password not settled: {}", device_id, err);
+            }
+        }
+    });
+}
+
+/// Replace the device login password with a generated one.
+///
+/// The new password is saved as pending first, then set through `ISAPI/Security/users`,
+/// checked with a fresh login and promoted. If the check or the save fails the old password is
+/// put back on the device. When even that fails and the old password no longer works, the new
+/// one is saved so the device stays reachable (`details.savedPassword = "new"`). A rotation cut
+/// short in between leaves the pending password; startup and the next rotation keep whichever
+/// password the device accepts.
+#[tauri::command]
+pub async fn rotate_device_password(device_id: String) -> Result<DeviceConfig, CommandError> {
+    let device = get_device_by_id(&device_id).ok_or_else(|| device_not_found(&device_id))?;
+    let rotation_error = |stage: &str| {
+        CommandError::new(ErrorCode::PasswordRotationFailed)
+            .with_detail("deviceId", device_id.clone())
+            .with_detail("stage", stage)
+    };
+    let device = settle_pending_password(device)
+        .await
+        .map_err(|reason| rotation_error("pending").with_reason(reason))?;
+    if device.password.is_empty() {
+        return Err(CommandError::invalid_input("device has no stored password").with_detail("deviceId", device_id));
+    }
+
+    let client = HikvisionClient::new(device.clone());
+    let user_id = client
+        .find_security_user_id()
+        .await
+        .map_err(|err| rotation_error("lookup").with_reason(err.to_string()))?;
+    let new_password = generate_device_password(&device.username);
+    save_pending_password(&device_id, &new_password)
+        .map_err(|reason| rotation_error("prepare").with_reason(reason))?;
+    if let Err(err) = client.change_login_password(&user_id, &new_password).await {
+        // The request may still have reached the device; keep the pending password unless the
+        // old one still works.
+        if verify_device_login(&device).await.ok {
+            let _ = discard_pending_password(&device_id);
+        }
+        return Err(rotation_error("change").with_reason(err.to_string()));
+    }
+
+    let mut rotated = device.clone();
+    rotated.password = new_password.clone();
+    let verification = verify_device_login(&rotated).await;
+    let policy = load_credential_policy();
+    let (stage, reason) = if !verification.ok {
+        ("verify", verification.message.unwrap_or_else(|| "login with the new password failed".to_string()))
+    } else {
+        match save_rotated_password(&device_id, &new_password, Some(&verification), &policy) {
+            Ok(saved) => return Ok(saved),
+            Err(err) => ("save", err),
+        }
+    };
+
+    let mut error = rotation_error(stage).with_reason(reason);
+    match restore_device_password(&rotated, &user_id, &device.password).await {
+        Ok(()) => {
+            let _ = discard_pending_password(&device_id);
+            error = error.with_detail("rolledBack", true);
+        }
+        Err(rollback_error) => {
+            error = error.with_detail("rollbackError", rollback_error);
+            // The change may never have reached the device; then there is nothing to undo.
+            if verify_device_login(&device).await.ok {
+                let _ = discard_pending_password(&device_id);
+                error = error.with_detail("rolledBack", true);
+            } else {
+                error = error.with_detail("rolledBack", false);
+                let saved = save_rotated_password(&device_id, &new_password, None, &policy);
+                error = error.with_detail("savedPassword", if saved.is_ok() { "new" } else { "pending" });
+            }
+        }
+    }
+    Err(error)
+}
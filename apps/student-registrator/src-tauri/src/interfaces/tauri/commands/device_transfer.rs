@@ -0,0 +1,82 @@
+fn bundle_error(file_path: &str, reason: String) -> CommandError {
+    CommandError::new(ErrorCode::DeviceBundleUnreadable)
+        .with_detail("filePath", file_path)
+        .with_reason(reason)
+}
+
+/// Write the devices (all, or `device_ids`) with their passwords to a passphrase-encrypted
+/// bundle for another installation. Returns how many devices were exported.
+#[tauri::command]
+pub async fn export_devices(
+    file_path: String,
+    passphrase: String,
+    device_ids: Option<Vec<String>>,
+) -> Result<usize, CommandError> {
+    let mut devices = load_devices().map_err(CommandError::storage)?;
+    if let Some(device_ids) = device_ids.as_ref() {
+        if let Some(missing) = device_ids.iter().find(|id| !devices.iter().any(|d| &d.id == *id)) {
+            return Err(device_not_found(missing));
+        }
+        devices.retain(|d| device_ids.contains(&d.id));
+    }
+    let count = devices.len();
+
+    // PBKDF2 takes a noticeable moment; keep it off the async runtime.
+    let content = tokio::task::spawn_blocking(move || seal_device_bundle(&devices, &passphrase))
+        .await
+        .map_err(|e| CommandError::storage(e.to_string()))?
+        .map_err(CommandError::invalid_input)?;
+    // A half-written bundle would only fail later, on the other machine.
+    write_atomic(Path::new(file_path.trim()), content.as_bytes())
+        .map_err(|reason| CommandError::storage(reason).with_detail("filePath", file_path.clone()))?;
+    Ok(count)
+}
+
+/// Merge a bundle from `export_devices` into the local devices by dedupe key. Matches with
+/// different settings are skipped unless `mode` is `OVERWRITE`; every device gets an entry in
+/// the report. `dry_run` only reports.
+#[tauri::command]
+pub async fn import_devices(
+    file_path: String,
+    passphrase: String,
+    mode: Option<DeviceImportMode>,
+    dry_run: Option<bool>,
+) -> Result<DeviceImportReport, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let mode = mode.unwrap_or_default();
+    let content = std::fs::read_to_string(file_path.trim())
+        .map_err(|e| CommandError::invalid_input(e.to_string()).with_detail("filePath", file_path.clone()))?;
+    let incoming = tokio::task::spawn_blocking(move || open_device_bundle(&content, &passphrase))
+        .await
+        .map_err(|e| CommandError::storage(e.to_string()))?
+        .map_err(|reason| bundle_error(&file_path, reason))?;
+
+    let max_local_devices = get_max_local_devices();
+    let mut new_id = || Uuid::new_v4().to_string();
+    let mut report = if dry_run {
+        let mut devices = load_devices().map_err(CommandError::storage)?;
+        merge_imported_devices(&mut devices, incoming, mode, max_local_devices, &mut new_id)
+    } else {
+        let policy = load_credential_policy();
+        with_devices_mut(|devices| {
+            let report = merge_imported_devices(devices, incoming, mode, max_local_devices, &mut new_id);
+            // Bundle devices keep their own credential dates; only undated ones get a lifetime.
+            let imported: HashSet<&str> = report
+                .devices
+                .iter()
+                .filter(|entry| entry.status == "ADDED" || entry.status == "UPDATED")
+                .filter_map(|entry| entry.local_id.as_deref())
+                .collect();
+            for device in devices
+                .iter_mut()
+                .filter(|d| imported.contains(d.id.as_str()) && d.credentials_expires_at.is_none())
+            {
+                policy.renew(device, Utc::now());
+            }
+            report
+        })
+        .map_err(CommandError::storage)?
+    };
+    report.dry_run = dry_run;
+    Ok(report)
+}
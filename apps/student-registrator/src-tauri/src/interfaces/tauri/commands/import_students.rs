@@ -0,0 +1,314 @@
+/// Student row that passed validation and can go through `register_student`.
+struct ValidatedImportStudent {
+    full_name: String,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    father_name: Option<String>,
+    gender: &'static str,
+    class_id: Option<String>,
+    parent_phone: Option<String>,
+    face_image_base64: String,
+    warnings: Vec<String>,
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_students_from_file(
+    file_path: String,
+    dry_run: Option<bool>,
+    class_ids: Option<HashMap<String, String>>,
+    target_device_ids: Option<Vec<String>>,
+    backend_url: Option<String>,
+    backend_token: Option<String>,
+    school_id: Option<String>,
+    device_concurrency: Option<u32>,
+) -> Result<StudentImportReport, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let path = PathBuf::from(file_path.trim());
+    let rows = read_student_file(&path)
+        .map_err(|reason| CommandError::invalid_input(reason).with_detail("filePath", file_path.clone()))?;
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    if !dry_run && load_devices().map_err(CommandError::storage)?.is_empty() {
+        return Err(CommandError::new(ErrorCode::NoDevicesConfigured));
+    }
+
+    let mut report = StudentImportReport {
+        dry_run,
+        total: rows.len(),
+        valid: 0,
+        invalid: 0,
+        registered: 0,
+        failed: 0,
+        rows: Vec::with_capacity(rows.len()),
+    };
+
+    for row in rows {
+        let class_name = row.class_name.clone().or_else(|| {
+            (row.sheet != "csv" && !row.sheet.to_lowercase().starts_with("sheet")).then(|| row.sheet.clone())
+        });
+        let mut row_report = StudentImportRowReport {
+            sheet: row.sheet.clone(),
+            row: row.row,
+            name: import_full_name(&row),
+            class_name: class_name.clone(),
+            status: "VALID".to_string(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            employee_no: None,
+            provisioning_id: None,
+            error: None,
+        };
+
+        let student = match validate_import_row(&row, class_name.as_deref(), class_ids.as_ref(), &base_dir) {
+            Ok(student) => student,
+            Err(errors) => {
+                row_report.status = "INVALID".to_string();
+                row_report.errors = errors;
+                report.invalid += 1;
+                report.rows.push(row_report);
+                continue;
+            }
+        };
+        report.valid += 1;
+        row_report.warnings = student.warnings.clone();
+        if dry_run {
+            report.rows.push(row_report);
+            continue;
+        }
+
+        match register_student(
+            student.full_name,
+            student.first_name,
+            student.last_name,
+            student.father_name,
+            student.gender.to_string(),
+            student.face_image_base64,
+            student.parent_phone,
+            student.class_id,
+            target_device_ids.clone(),
+            backend_url.clone(),
+            backend_token.clone(),
+            school_id.clone(),
+            device_concurrency,
+            // Already checked by `validate_import_row`.
+            Some(true),
+        )
+        .await
+        {
+            Ok(result) => {
+                row_report.status = "REGISTERED".to_string();
+                row_report.employee_no = Some(result.employee_no);
+                row_report.provisioning_id = result.provisioning_id;
+                report.registered += 1;
+            }
+            Err(err) => {
+                row_report.status = "FAILED".to_string();
+                row_report.error = serde_json::to_value(&err).ok();
+                report.failed += 1;
+            }
+        }
+        report.rows.push(row_report);
+    }
+
+    Ok(report)
+}
+
+fn import_full_name(row: &StudentFileRow) -> String {
+    let last = row.last_name.clone().unwrap_or_default();
+    let first = row.first_name.clone().unwrap_or_default();
+    let combined = format!("{} {}", last, first).trim().to_string();
+    if combined.is_empty() {
+        row.full_name.clone().unwrap_or_default()
+    } else {
+        combined
+    }
+}
+
+fn normalize_import_gender(value: &str) -> Option<&'static str> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "male" | "m" | "erkak" | "o'g'il" | "мужской" | "м" => Some("male"),
+        "2" | "female" | "f" | "ayol" | "qiz" | "женский" | "ж" => Some("female"),
+        _ => None,
+    }
+}
+
+fn is_valid_phone(value: &str) -> bool {
+    let digits = value.chars().filter(|c| c.is_ascii_digit()).count();
+    let allowed = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | ' ' | '-' | '(' | ')'));
+    allowed && (7..=15).contains(&digits)
+}
+
+/// Check one row; on failure returns every problem found so the user can fix them at once.
+fn validate_import_row(
+    row: &StudentFileRow,
+    class_name: Option<&str>,
+    class_ids: Option<&HashMap<String, String>>,
+    base_dir: &Path,
+) -> Result<ValidatedImportStudent, Vec<String>> {
+    let mut errors = Vec::new();
+
+    let full_name = import_full_name(row);
+    if full_name.is_empty() {
+        errors.push("Ism kiritilmagan".to_string());
+    }
+
+    let gender = match row.gender.as_deref() {
+        Some(value) => {
+            let gender = normalize_import_gender(value);
+            if gender.is_none() {
+                errors.push(format!("Jins noto'g'ri: {} (1/2, male/female, erkak/ayol)", value));
+            }
+            gender
+        }
+        None => {
+            errors.push("Jins kiritilmagan".to_string());
+            None
+        }
+    };
+
+    if let Some(phone) = row.parent_phone.as_deref() {
+        if !is_valid_phone(phone) {
+            errors.push(format!("Telefon raqami noto'g'ri: {}", phone));
+        }
+    }
+
+    let class_id = match (class_name, class_ids) {
+        (Some(name), Some(map)) => {
+            let found = map
+                .iter()
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case(name.trim()))
+                .map(|(_, id)| id.clone());
+            if found.is_none() {
+                errors.push(format!("Sinf topilmadi: {}", name));
+            }
+            found
+        }
+        // A class name is not a backend id; without a mapping the row cannot be placed.
+        (Some(name), None) => {
+            errors.push(format!("Sinf biriktirilmagan: {} (classIds da yo'q)", name));
+            None
+        }
+        (None, _) => None,
+    };
+
+    let image = match (&row.embedded_image, row.photo_path.as_deref()) {
+        (Some(bytes), _) => Some(bytes.clone()),
+        (None, Some(photo_path)) => {
+            let photo = PathBuf::from(photo_path);
+            let photo = if photo.is_absolute() { photo } else { base_dir.join(photo) };
+            match std::fs::read(&photo) {
+                Ok(bytes) => Some(bytes),
+                Err(err) => {
+                    errors.push(format!("Rasmni o'qib bo'lmadi ({}): {}", photo.display(), err));
+                    None
+                }
+            }
+        }
+        (None, None) => {
+            errors.push("Rasm yo'q (rasm yo'li yoki jadvalga qo'yilgan rasm kerak)".to_string());
+            None
+        }
+    };
+    // Normalize here so a dry run reports undecodable photos and the real run uploads JPEGs.
+    let image = image.and_then(|bytes| match prepare_face_image(&bytes, MAX_FACE_IMAGE_BYTES) {
+        Ok(face) => Some(face.bytes),
+        Err(FaceImageError::TooLarge { size, max }) => {
+            errors.push(format!(
+                "Rasm juda katta: {} KB (max {} KB)",
+                size / 1024,
+                max / 1024
+            ));
+            None
+        }
+        Err(err) => {
+            errors.push(format!("Rasm JPG, PNG yoki WebP bo'lishi kerak ({})", err));
+            None
+        }
+    });
+    let mut warnings = Vec::new();
+    if let Some(Ok(report)) = image.as_deref().map(assess_face_image) {
+        for issue in report.issues {
+            errors.push(format!("Rasm sifati: {}", issue.message));
+        }
+        warnings.extend(report.warnings.into_iter().map(|warning| format!("Rasm: {}", warning.message)));
+    }
+
+    match (gender, image) {
+        (Some(gender), Some(image)) if errors.is_empty() => Ok(ValidatedImportStudent {
+            full_name,
+            first_name: row.first_name.clone(),
+            last_name: row.last_name.clone(),
+            father_name: row.father_name.clone(),
+            gender,
+            class_id,
+            parent_phone: row.parent_phone.clone(),
+            face_image_base64: STANDARD.encode(image),
+            warnings,
+        }),
+        _ => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod import_students_tests {
+    use super::{validate_import_row, StudentFileRow};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    /// Passport-style photo: skin-toned oval on a checkered background.
+    fn test_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let photo = image::RgbImage::from_fn(width, height, |x, y| {
+            let dx = (x as f32 - width as f32 / 2.0) / (width as f32 * 0.22);
+            let dy = (y as f32 - height as f32 * 0.45) / (height as f32 * 0.25);
+            if dx * dx + dy * dy <= 1.0 {
+                image::Rgb([224, 172, 140])
+            } else if (x / 4 + y / 4) % 2 == 0 {
+                image::Rgb([60, 100, 200])
+            } else {
+                image::Rgb([10, 20, 40])
+            }
+        });
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(photo)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn import_row_validation_maps_class_and_reports_all_problems() {
+        let row = StudentFileRow {
+            sheet: "5-A".to_string(),
+            row: 10,
+            full_name: Some("Aliyev Vali".to_string()),
+            gender: Some("1".to_string()),
+            parent_phone: Some("+998 90 123-45-67".to_string()),
+            embedded_image: Some(test_jpeg(300, 400)),
+            ..Default::default()
+        };
+        let classes = HashMap::from([("5-a".to_string(), "class-1".to_string())]);
+        let student = validate_import_row(&row, Some("5-A"), Some(&classes), Path::new(".")).unwrap();
+        assert_eq!(student.gender, "male");
+        assert_eq!(student.class_id.as_deref(), Some("class-1"));
+        let unmapped = validate_import_row(&row, Some("5-A"), None, Path::new(".")).err().unwrap();
+        assert!(unmapped.iter().any(|error| error.starts_with("Sinf biriktirilmagan")));
+
+        let bad = StudentFileRow {
+            gender: Some("x".to_string()),
+            embedded_image: None,
+            ..row
+        };
+        let errors = validate_import_row(&bad, Some("9-Z"), Some(&classes), Path::new(".")).err().unwrap();
+        assert_eq!(errors.len(), 3);
+
+        let unreadable = StudentFileRow {
+            embedded_image: Some(vec![0xFF, 0xD8, 0xFF, 0xE0]),
+            ..bad
+        };
+        let errors = validate_import_row(&unreadable, Some("5-A"), Some(&classes), Path::new(".")).err().unwrap();
+        assert!(errors.iter().any(|error| error.starts_with("Rasm JPG, PNG yoki WebP")));
+    }
+}
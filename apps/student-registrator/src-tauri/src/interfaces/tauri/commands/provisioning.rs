@@ -3,12 +3,15 @@ pub async fn get_provisioning(
     provisioning_id: String,
     backend_url: Option<String>,
     backend_token: Option<String>,
-) -> Result<Value, String> {
+) -> Result<Value, CommandError> {
     let backend_url = backend_url.filter(|v| !v.trim().is_empty())
-        .ok_or("backendUrl is required")?;
+        .ok_or_else(|| CommandError::invalid_input("backendUrl is required"))?;
     let backend_token = backend_token.filter(|v| !v.trim().is_empty());
     let client = ApiClient::new(backend_url, backend_token);
-    client.get_provisioning(&provisioning_id).await
+    client
+        .get_provisioning(&provisioning_id)
+        .await
+        .map_err(CommandError::backend)
 }
 
 #[tauri::command]
@@ -17,9 +20,9 @@ pub async fn retry_provisioning(
     backend_url: Option<String>,
     backend_token: Option<String>,
     device_ids: Option<Vec<String>>,
-) -> Result<Value, String> {
+) -> Result<Value, CommandError> {
     let backend_url = backend_url.filter(|v| !v.trim().is_empty())
-        .ok_or("backendUrl is required")?;
+        .ok_or_else(|| CommandError::invalid_input("backendUrl is required"))?;
     let backend_token = backend_token.filter(|v| !v.trim().is_empty());
     let client = ApiClient::new(backend_url, backend_token);
     let requested_device_ids = device_ids.unwrap_or_default();
@@ -27,10 +30,14 @@ pub async fn retry_provisioning(
     // 1) Reset failed links to PENDING/PROCESSING on backend.
     let retry_result = client
         .retry_provisioning(&provisioning_id, requested_device_ids.clone())
-        .await?;
+        .await
+        .map_err(CommandError::backend)?;
 
     // 2) Re-check connectivity for target devices right away.
-    let provisioning = client.get_provisioning(&provisioning_id).await?;
+    let provisioning = client
+        .get_provisioning(&provisioning_id)
+        .await
+        .map_err(CommandError::backend)?;
     let employee_no = provisioning
         .get("student")
         .and_then(|s| s.get("deviceStudentId"))
@@ -64,7 +71,7 @@ pub async fn retry_provisioning(
             .unwrap_or_default();
     }
 
-    let mut local_devices = load_devices();
+    let mut local_devices = load_devices().map_err(CommandError::storage)?;
     let mut local_changed = false;
     let mut checked = 0usize;
     let mut failed = 0usize;
@@ -142,11 +149,8 @@ pub async fn retry_provisioning(
         let local_clone = local_devices[index].clone();
         let test = HikvisionClient::new(local_clone).test_connection().await;
         if test.ok {
-            if let Some(found_id) = test.device_id {
-                if local_devices[index].device_id.as_deref() != Some(found_id.as_str()) {
-                    local_devices[index].device_id = Some(found_id);
-                    local_changed = true;
-                }
+            if apply_connection_identity(&mut local_devices[index], &test) {
+                local_changed = true;
             }
             continue;
         }
@@ -171,7 +175,7 @@ pub async fn retry_provisioning(
     }
 
     if local_changed {
-        let _ = save_devices(&local_devices);
+        save_device_identity(&local_devices);
     }
 
     let final_provisioning = client
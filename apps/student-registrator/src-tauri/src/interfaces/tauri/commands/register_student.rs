@@ -1,3 +1,44 @@
+/// Decode an operator-supplied photo and return it as an uploadable JPEG.
+fn normalize_face_base64(face_image_base64: &str) -> Result<Vec<u8>, CommandError> {
+    let bytes = STANDARD
+        .decode(face_image_base64.trim())
+        .map_err(|err| CommandError::new(ErrorCode::InvalidImage).with_reason(err.to_string()))?;
+    let face = prepare_face_image(&bytes, MAX_FACE_IMAGE_BYTES).map_err(face_image_error)?;
+    Ok(face.bytes)
+}
+
+/// Reject a photo that fails the offline quality check; the report goes to `details.quality`.
+fn ensure_face_quality(face: &[u8]) -> Result<(), CommandError> {
+    let report = assess_face_image(face).map_err(face_image_error)?;
+    if report.ok {
+        return Ok(());
+    }
+    let reason = report
+        .issues
+        .iter()
+        .map(|issue| issue.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    Err(CommandError::new(ErrorCode::FaceQualityRejected)
+        .with_reason(reason)
+        .with_detail("quality", serde_json::to_value(&report).unwrap_or(Value::Null)))
+}
+
+#[tauri::command]
+pub async fn check_face_quality(face_image_base64: String) -> Result<FaceQualityReport, CommandError> {
+    let face = normalize_face_base64(&face_image_base64)?;
+    assess_face_image(&face).map_err(face_image_error)
+}
+
+fn face_image_error(err: FaceImageError) -> CommandError {
+    match err {
+        FaceImageError::TooLarge { size, max } => CommandError::new(ErrorCode::FaceTooLarge)
+            .with_detail("size", size)
+            .with_detail("maxBytes", max),
+        other => CommandError::new(ErrorCode::InvalidImage).with_reason(other.to_string()),
+    }
+}
+
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 pub async fn register_student(
@@ -13,17 +54,20 @@ pub async fn register_student(
     backend_url: Option<String>,
     backend_token: Option<String>,
     school_id: Option<String>,
-) -> Result<RegisterResult, String> {
-    if face_image_base64.len() > (MAX_FACE_IMAGE_BYTES * 4 / 3) + 256 {
-        return Err(format!(
-            "Face image is too large. Max {} KB.",
-            MAX_FACE_IMAGE_BYTES / 1024
-        ));
+    device_concurrency: Option<u32>,
+    skip_face_quality_check: Option<bool>,
+) -> Result<RegisterResult, CommandError> {
+    // Normalize once so the backend and every device get the same small JPEG.
+    let face = normalize_face_base64(&face_image_base64)?;
+    // Catch a bad photo here, before any device or the backend has been touched.
+    if !skip_face_quality_check.unwrap_or(false) {
+        ensure_face_quality(&face)?;
     }
+    let face_image_base64 = STANDARD.encode(face);
 
-    let mut devices = load_devices();
+    let mut devices = load_devices().map_err(CommandError::storage)?;
     if devices.is_empty() {
-        return Err("No devices configured".to_string());
+        return Err(CommandError::new(ErrorCode::NoDevicesConfigured));
     }
 
     let prepared = prepare_register_student(
@@ -47,15 +91,16 @@ pub async fn register_student(
         &prepared,
         &gender,
         &face_image_base64,
+        get_device_concurrency(device_concurrency),
     )
     .await;
 
     if outcome.devices_changed {
-        let _ = save_devices(&devices);
+        save_device_identity(&devices);
     }
 
-    if let Some(message) = outcome.abort_error {
-        let rollback_reason = format!("Rolled back due to failure: {}", message);
+    if let Some(error) = outcome.abort_error {
+        let rollback_reason = format!("Rolled back due to failure: {}", error);
         let mut rollback_errors: Vec<String> = Vec::new();
         let mut finalize_error: Option<String> = None;
 
@@ -101,21 +146,14 @@ pub async fn register_student(
             }
         }
 
-        if let Some(err) = finalize_error {
-            if rollback_errors.is_empty() {
-                return Err(format!("{}. Finalize failure xatosi: {}", message, err));
-            }
-            return Err(format!(
-                "{}. Rollback errors: {}. Finalize failure xatosi: {}",
-                message,
-                rollback_errors.join("; "),
-                err
-            ));
+        let mut error = error;
+        if !rollback_errors.is_empty() {
+            error = error.with_detail("rollbackErrors", rollback_errors);
         }
-        if rollback_errors.is_empty() {
-            return Err(message);
+        if let Some(err) = finalize_error {
+            error = error.with_detail("finalizeError", err);
         }
-        return Err(format!("{}. Rollback errors: {}", message, rollback_errors.join("; ")));
+        return Err(error);
     }
 
     Ok(RegisterResult {
@@ -124,3 +162,42 @@ pub async fn register_student(
         results: outcome.results,
     })
 }
+
+#[cfg(test)]
+mod register_student_tests {
+    use super::register_student;
+    use crate::shared::error::ErrorCode;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    #[tokio::test]
+    async fn register_rejects_poor_photo_before_provisioning() {
+        let mut dark = Vec::new();
+        image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(300, 400, image::Rgb([15, 15, 15])))
+            .write_to(&mut std::io::Cursor::new(&mut dark), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let err = register_student(
+            "Aliyev Vali".to_string(),
+            None,
+            None,
+            None,
+            "male".to_string(),
+            STANDARD.encode(dark),
+            None,
+            None,
+            None,
+            Some("http://127.0.0.1:1".to_string()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .err()
+        .unwrap();
+
+        assert_eq!(err.code, ErrorCode::FaceQualityRejected);
+        let issues = &err.details.unwrap()["quality"]["issues"];
+        assert!(issues.as_array().unwrap().iter().any(|issue| issue["code"] == "TOO_DARK"));
+    }
+}
@@ -1,261 +1,387 @@
 struct RegisterDeviceProcessOutcome {
     results: Vec<RegisterDeviceResult>,
     successful_devices: Vec<SuccessfulDeviceEntry>,
-    abort_error: Option<String>,
+    abort_error: Option<CommandError>,
     devices_changed: bool,
 }
 
+/// Result of running the register pipeline on a single device.
+struct RegisterDeviceStep {
+    result: RegisterDeviceResult,
+    /// Device with a refreshed identity (device_id / pinned TLS fingerprint), if it changed.
+    updated_device: Option<DeviceConfig>,
+    success: Option<SuccessfulDeviceEntry>,
+    error: Option<CommandError>,
+}
+
 async fn process_register_student_devices(
     devices: &mut [DeviceConfig],
     prepared: &RegisterStudentPreparation,
     gender: &str,
     face_image_base64: &str,
+    concurrency: usize,
 ) -> RegisterDeviceProcessOutcome {
     let mut results = Vec::new();
     let mut successful_devices: Vec<SuccessfulDeviceEntry> = Vec::new();
-    let mut abort_error: Option<String> = None;
+    let mut abort_error: Option<CommandError> = None;
     let mut devices_changed = false;
 
-    for device in devices.iter_mut() {
-        if abort_error.is_some() {
-            break;
-        }
-        if prepared.explicit_db_only {
-            continue;
-        }
-
-        let selected_set: Option<&HashSet<String>> = if let Some(requested) = prepared.requested_target_backend_ids.as_ref() {
-            Some(requested)
-        } else if !prepared.provisioned_target_backend_ids.is_empty() {
-            Some(&prepared.provisioned_target_backend_ids)
-        } else {
-            None
+    if prepared.explicit_db_only {
+        return RegisterDeviceProcessOutcome {
+            results,
+            successful_devices,
+            abort_error,
+            devices_changed,
         };
+    }
 
-        let selected_by_backend_id = selected_set.is_none_or(|ids| {
-            device
-                .backend_id
-                .as_ref()
-                .map(|id| ids.contains(id))
-                .unwrap_or(false)
-        });
+    let selected_indices: Vec<usize> = devices
+        .iter()
+        .enumerate()
+        .filter(|(_, device)| is_device_selected_for_register(device, prepared))
+        .map(|(index, _)| index)
+        .collect();
 
-        if !selected_by_backend_id && selected_set.is_some() {
-            let selected_by_legacy_device_id = selected_set
-                .and_then(|ids| {
-                    device
-                        .device_id
-                        .as_ref()
-                        .and_then(|id| prepared.backend_device_map.get(id))
-                        .map(|backend_id| ids.contains(backend_id))
-                })
-                .unwrap_or(false);
-            if !selected_by_legacy_device_id {
-                continue;
-            }
-        }
-
-        if is_credentials_expired(device) {
-            let external_device_id = device.device_id.as_deref();
-            let backend_device_id = device
-                .backend_id
-                .as_deref()
-                .or_else(|| external_device_id.and_then(|id| prepared.backend_device_map.get(id).map(|s| s.as_str())));
-            let connection = DeviceConnectionResult {
-                ok: false,
-                message: Some("Ulanish sozlamalari muddati tugagan".to_string()),
-                device_id: device.device_id.clone(),
-            };
-            if let (Some(api), Some(pid)) = (prepared.api_client.as_ref(), prepared.provisioning_id.as_ref()) {
-                let device_display_name = device_label(device);
-                let device_name = Some(device_display_name.as_str());
-                let device_location = Some(device.host.as_str());
-                if let Err(err) = api
-                    .report_device_result(
-                        pid,
-                        backend_device_id,
-                        external_device_id,
-                        device_name,
-                        None,
-                        device_location,
-                        "FAILED",
-                        &prepared.employee_no,
-                        connection.message.as_deref(),
-                    )
-                    .await
-                {
-                    abort_error = Some(format!("Backend report failed: {}", err));
+    // Devices run concurrently but are yielded in their original order. Once any device fails,
+    // devices that have not started yet are skipped; in-flight ones finish and are rolled back
+    // by the caller like in the sequential flow.
+    let aborted = AtomicBool::new(false);
+    let snapshot: Vec<DeviceConfig> = devices.to_vec();
+    let steps: Vec<(usize, Option<RegisterDeviceStep>)> = stream::iter(selected_indices)
+        .map(|index| {
+            let device = snapshot[index].clone();
+            let aborted = &aborted;
+            async move {
+                if aborted.load(Ordering::SeqCst) {
+                    return (index, None);
                 }
+                let step = process_register_student_device(device, prepared, gender, face_image_base64).await;
+                if step.error.is_some() {
+                    aborted.store(true, Ordering::SeqCst);
+                }
+                (index, Some(step))
             }
-            results.push(RegisterDeviceResult {
-                device_id: device.id.clone(),
-                device_name: device_label(device),
-                connection,
-                user_create: None,
-                face_upload: None,
-            });
-            if abort_error.is_none() {
-                abort_error = Some(format!(
-                    "Qurilma {}: Ulanish sozlamalari muddati tugagan",
-                    device_label(device)
-                ));
-            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    for (index, step) in steps {
+        let Some(step) = step else {
             continue;
+        };
+        if let Some(updated) = step.updated_device {
+            devices[index] = updated;
+            devices_changed = true;
         }
-
-        let client = HikvisionClient::new(device.clone());
-        let connection = client.test_connection().await;
-        if connection.ok {
-            if let Some(device_id) = connection.device_id.clone() {
-                if device.device_id.as_deref() != Some(device_id.as_str()) {
-                    device.device_id = Some(device_id);
-                    devices_changed = true;
-                }
-            }
+        results.push(step.result);
+        if let Some(success) = step.success {
+            successful_devices.push(success);
+        }
+        if abort_error.is_none() {
+            abort_error = step.error;
         }
-        let external_device_id = connection.device_id.clone().or(device.device_id.clone());
+    }
+
+    RegisterDeviceProcessOutcome {
+        results,
+        successful_devices,
+        abort_error,
+        devices_changed,
+    }
+}
+
+fn is_device_selected_for_register(device: &DeviceConfig, prepared: &RegisterStudentPreparation) -> bool {
+    let selected_set: Option<&HashSet<String>> = if let Some(requested) = prepared.requested_target_backend_ids.as_ref() {
+        Some(requested)
+    } else if !prepared.provisioned_target_backend_ids.is_empty() {
+        Some(&prepared.provisioned_target_backend_ids)
+    } else {
+        None
+    };
+
+    let Some(ids) = selected_set else {
+        return true;
+    };
+    let selected_by_backend_id = device
+        .backend_id
+        .as_ref()
+        .map(|id| ids.contains(id))
+        .unwrap_or(false);
+    if selected_by_backend_id {
+        return true;
+    }
+    device
+        .device_id
+        .as_ref()
+        .and_then(|id| prepared.backend_device_map.get(id))
+        .map(|backend_id| ids.contains(backend_id))
+        .unwrap_or(false)
+}
+
+async fn process_register_student_device(
+    mut device: DeviceConfig,
+    prepared: &RegisterStudentPreparation,
+    gender: &str,
+    face_image_base64: &str,
+) -> RegisterDeviceStep {
+    if is_credentials_expired(&device) {
+        let external_device_id = device.device_id.clone();
         let backend_device_id = device
             .backend_id
             .clone()
             .or_else(|| external_device_id.as_ref().and_then(|id| prepared.backend_device_map.get(id).cloned()));
-        let device_display_name = device_label(device);
-        let device_name = Some(device_display_name.as_str());
-        let device_location = Some(device.host.as_str());
+        let connection = DeviceConnectionResult {
+            ok: false,
+            message: Some("Ulanish sozlamalari muddati tugagan".to_string()),
+            device_id: device.device_id.clone(),
+            serial_number: device.serial_number.clone(),
+            tls_fingerprint: None,
+        };
+        let report_error = report_register_device_result(
+            prepared,
+            &device,
+            backend_device_id.as_deref(),
+            external_device_id.as_deref(),
+            "FAILED",
+            connection.message.as_deref(),
+        )
+        .await;
+        return RegisterDeviceStep {
+            result: RegisterDeviceResult {
+                device_id: device.id.clone(),
+                device_name: device_label(&device),
+                connection,
+                user_create: None,
+                face_upload: None,
+            },
+            updated_device: None,
+            success: None,
+            error: Some(report_error.unwrap_or_else(|| {
+                CommandError::credentials_expired().with_detail("device", device_label(&device))
+            })),
+        };
+    }
 
-        if !connection.ok {
-            let connection_message = connection.message.clone();
-            if let (Some(api), Some(pid)) = (prepared.api_client.as_ref(), prepared.provisioning_id.as_ref()) {
-                if let Err(err) = api
-                    .report_device_result(
-                        pid,
-                        backend_device_id.as_deref(),
-                        external_device_id.as_deref(),
-                        device_name,
-                        None,
-                        device_location,
-                        "FAILED",
-                        &prepared.employee_no,
-                        connection.message.as_deref(),
-                    )
-                    .await
-                {
-                    abort_error = Some(format!("Backend report failed: {}", err));
-                }
-            }
-            results.push(RegisterDeviceResult {
+    let client = HikvisionClient::new(device.clone());
+    let connection = client.test_connection().await;
+    let updated_device = apply_connection_identity(&mut device, &connection).then(|| device.clone());
+    let external_device_id = connection.device_id.clone().or(device.device_id.clone());
+    let backend_device_id = device
+        .backend_id
+        .clone()
+        .or_else(|| external_device_id.as_ref().and_then(|id| prepared.backend_device_map.get(id).cloned()));
+
+    if !connection.ok {
+        let report_error = report_register_device_result(
+            prepared,
+            &device,
+            backend_device_id.as_deref(),
+            external_device_id.as_deref(),
+            "FAILED",
+            connection.message.as_deref(),
+        )
+        .await;
+        let reason = connection.message.clone().unwrap_or_else(|| "Ulanishda xato".to_string());
+        return RegisterDeviceStep {
+            result: RegisterDeviceResult {
                 device_id: device.id.clone(),
-                device_name: device_label(device),
+                device_name: device_label(&device),
                 connection,
                 user_create: None,
                 face_upload: None,
-            });
-            if abort_error.is_none() {
-                let reason = connection_message.unwrap_or_else(|| "Ulanishda xato".to_string());
-                abort_error = Some(format!("Qurilma {}: {}", device_label(device), reason));
-            }
-            continue;
-        }
+            },
+            updated_device,
+            success: None,
+            error: Some(report_error.unwrap_or_else(|| {
+                CommandError::new(ErrorCode::DeviceOffline)
+                    .with_detail("device", device_label(&device))
+                    .with_reason(reason)
+            })),
+        };
+    }
 
-        let user_create = client
-            .create_user(
-                &prepared.employee_no,
-                &prepared.full_name,
-                gender,
-                &prepared.begin_time,
-                &prepared.end_time,
-            )
-            .await;
+    let user_create = client
+        .create_user(
+            &prepared.employee_no,
+            &prepared.full_name,
+            gender,
+            &prepared.begin_time,
+            &prepared.end_time,
+        )
+        .await;
 
-        if !user_create.ok {
-            if let (Some(api), Some(pid)) = (prepared.api_client.as_ref(), prepared.provisioning_id.as_ref()) {
-                if let Err(err) = api
-                    .report_device_result(
-                        pid,
-                        backend_device_id.as_deref(),
-                        external_device_id.as_deref(),
-                        device_name,
-                        None,
-                        device_location,
-                        "FAILED",
-                        &prepared.employee_no,
-                        user_create.error_msg.as_deref(),
-                    )
-                    .await
-                {
-                    abort_error = Some(format!("Backend report failed: {}", err));
-                }
-            }
-            results.push(RegisterDeviceResult {
+    if !user_create.ok {
+        let report_error = report_register_device_result(
+            prepared,
+            &device,
+            backend_device_id.as_deref(),
+            external_device_id.as_deref(),
+            "FAILED",
+            user_create.error_msg.as_deref(),
+        )
+        .await;
+        let create_error = action_error(&user_create)
+            .with_detail("device", device_label(&device))
+            .with_detail("step", "createUser");
+        return RegisterDeviceStep {
+            result: RegisterDeviceResult {
                 device_id: device.id.clone(),
-                device_name: device_label(device),
+                device_name: device_label(&device),
                 connection,
                 user_create: Some(user_create),
                 face_upload: None,
-            });
-            if abort_error.is_none() {
-                abort_error = Some(format!(
-                    "Qurilma {}: Qurilmada foydalanuvchi yaratishda xato",
-                    device_label(device)
-                ));
-            }
-            continue;
-        }
+            },
+            updated_device,
+            success: None,
+            error: Some(report_error.unwrap_or(create_error)),
+        };
+    }
 
-        let face_upload = client
-            .upload_face(&prepared.employee_no, &prepared.full_name, gender, face_image_base64)
-            .await;
+    let face_upload = client
+        .upload_face(&prepared.employee_no, &prepared.full_name, gender, face_image_base64)
+        .await;
 
-        if let (Some(api), Some(pid)) = (prepared.api_client.as_ref(), prepared.provisioning_id.as_ref()) {
-            let status = if face_upload.ok { "SUCCESS" } else { "FAILED" };
-            if let Err(err) = api
-                .report_device_result(
-                    pid,
-                    backend_device_id.as_deref(),
-                    external_device_id.as_deref(),
-                    device_name,
-                    None,
-                    device_location,
-                    status,
-                    &prepared.employee_no,
-                    face_upload.error_msg.as_deref(),
-                )
-                .await
-            {
-                abort_error = Some(format!("Backend report failed: {}", err));
-            }
+    let status = if face_upload.ok { "SUCCESS" } else { "FAILED" };
+    let report_error = report_register_device_result(
+        prepared,
+        &device,
+        backend_device_id.as_deref(),
+        external_device_id.as_deref(),
+        status,
+        face_upload.error_msg.as_deref(),
+    )
+    .await;
+
+    let mut error = report_error;
+    let success = if face_upload.ok {
+        Some((
+            device.clone(),
+            backend_device_id,
+            external_device_id,
+            device_label(&device),
+            device.host.clone(),
+        ))
+    } else {
+        let _ = client.delete_user(&prepared.employee_no).await;
+        if error.is_none() {
+            error = Some(
+                action_error(&face_upload)
+                    .with_detail("device", device_label(&device))
+                    .with_detail("step", "uploadFace"),
+            );
         }
+        None
+    };
 
-        results.push(RegisterDeviceResult {
+    RegisterDeviceStep {
+        result: RegisterDeviceResult {
             device_id: device.id.clone(),
-            device_name: device_label(device),
+            device_name: device_label(&device),
             connection,
             user_create: Some(user_create),
-            face_upload: Some(face_upload.clone()),
-        });
+            face_upload: Some(face_upload),
+        },
+        updated_device,
+        success,
+        error,
+    }
+}
 
-        if face_upload.ok {
-            successful_devices.push((
-                device.clone(),
-                backend_device_id,
-                external_device_id,
-                device_display_name.clone(),
-                device.host.clone(),
-            ));
-        } else {
-            let _ = client.delete_user(&prepared.employee_no).await;
-            if abort_error.is_none() {
-                abort_error = Some(format!(
-                    "Qurilma {}: Qurilmaga rasm yuklashda xato",
-                    device_label(device)
-                ));
-            }
+/// Report one device outcome to the backend provisioning, if provisioning is active.
+async fn report_register_device_result(
+    prepared: &RegisterStudentPreparation,
+    device: &DeviceConfig,
+    backend_device_id: Option<&str>,
+    external_device_id: Option<&str>,
+    status: &str,
+    last_error: Option<&str>,
+) -> Option<CommandError> {
+    let (Some(api), Some(pid)) = (prepared.api_client.as_ref(), prepared.provisioning_id.as_ref()) else {
+        return None;
+    };
+    let device_display_name = device_label(device);
+    api.report_device_result(
+        pid,
+        backend_device_id,
+        external_device_id,
+        Some(device_display_name.as_str()),
+        None,
+        Some(device.host.as_str()),
+        status,
+        &prepared.employee_no,
+        last_error,
+    )
+    .await
+    .err()
+    .map(backend_report_error)
+}
+
+fn backend_report_error(err: String) -> CommandError {
+    CommandError::backend(format!("Backend report failed: {}", err))
+}
+
+#[cfg(test)]
+mod register_student_devices_tests {
+    use super::{process_register_student_devices, RegisterStudentPreparation};
+    use crate::shared::error::ErrorCode;
+    use crate::types::DeviceConfig;
+    use std::collections::{HashMap, HashSet};
+
+    fn test_preparation() -> RegisterStudentPreparation {
+        RegisterStudentPreparation {
+            full_name: "Test Student".to_string(),
+            employee_no: "1234567890".to_string(),
+            provisioning_id: None,
+            api_client: None,
+            backend_device_map: HashMap::new(),
+            requested_target_backend_ids: None,
+            explicit_db_only: false,
+            provisioned_target_backend_ids: HashSet::new(),
+            begin_time: "2026-01-01T00:00:00".to_string(),
+            end_time: "2036-01-01T00:00:00".to_string(),
         }
     }
 
-    RegisterDeviceProcessOutcome {
-        results,
-        successful_devices,
-        abort_error,
-        devices_changed,
+    fn test_device(id: &str, port: u16, expires_at: &str) -> DeviceConfig {
+        DeviceConfig {
+            port,
+            credentials_expires_at: Some(expires_at.to_string()),
+            ..crate::domain::entities::device::test_device(id)
+        }
+    }
+
+    fn closed_port() -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn register_fan_out_keeps_device_order_and_aborts() {
+        let future = "2999-01-01T00:00:00Z";
+        let mut devices = vec![
+            test_device("a", closed_port(), future),
+            test_device("b", closed_port(), future),
+            test_device("c", closed_port(), future),
+        ];
+        let prepared = test_preparation();
+        let outcome = process_register_student_devices(&mut devices, &prepared, "male", "", 3).await;
+
+        let ids: Vec<&str> = outcome.results.iter().map(|r| r.device_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert!(outcome.successful_devices.is_empty());
+        assert_eq!(outcome.abort_error.unwrap().code, ErrorCode::DeviceOffline);
+    }
+
+    #[tokio::test]
+    async fn register_fan_out_skips_devices_not_started_before_abort() {
+        let past = "2000-01-01T00:00:00Z";
+        let mut devices = vec![test_device("a", closed_port(), past), test_device("b", closed_port(), past)];
+        let prepared = test_preparation();
+        let outcome = process_register_student_devices(&mut devices, &prepared, "male", "", 1).await;
+
+        assert_eq!(outcome.results.len(), 1);
+        assert_eq!(outcome.results[0].device_id, "a");
+        assert_eq!(outcome.abort_error.unwrap().code, ErrorCode::CredentialsExpired);
     }
 }
@@ -25,7 +25,7 @@ async fn prepare_register_student(
     backend_url: Option<String>,
     backend_token: Option<String>,
     school_id: Option<String>,
-) -> Result<RegisterStudentPreparation, String> {
+) -> Result<RegisterStudentPreparation, CommandError> {
     let backend_url = backend_url.filter(|v| !v.trim().is_empty());
     let backend_token = backend_token.filter(|v| !v.trim().is_empty());
     let school_id = school_id.filter(|v| !v.trim().is_empty());
@@ -42,7 +42,7 @@ async fn prepare_register_student(
     };
 
     if backend_url.is_some() && school_id.is_none() {
-        return Err("schoolId is required when backendUrl is set".to_string());
+        return Err(CommandError::invalid_input("schoolId is required when backendUrl is set"));
     }
 
     let requested_target_backend_ids: Option<HashSet<String>> = target_device_ids.as_ref().map(|ids| {
@@ -81,7 +81,7 @@ async fn prepare_register_student(
                 &request_id,
             )
             .await
-            .map_err(|e| format!("Backend provisioning failed: {}", e))?;
+            .map_err(|e| CommandError::backend(format!("Backend provisioning failed: {}", e)))?;
 
         if provisioning.device_student_id.chars().all(|c| c.is_ascii_digit()) {
             employee_no = provisioning.device_student_id;
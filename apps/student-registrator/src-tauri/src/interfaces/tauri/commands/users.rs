@@ -1,7 +1,7 @@
 #[tauri::command]
-pub async fn fetch_users(device_id: String, offset: Option<i32>, limit: Option<i32>) -> Result<UserInfoSearchResponse, String> {
+pub async fn fetch_users(device_id: String, offset: Option<i32>, limit: Option<i32>) -> Result<UserInfoSearchResponse, CommandError> {
     let device = get_device_by_id(&device_id)
-        .ok_or("Device not found")?;
+        .ok_or_else(|| device_not_found(&device_id))?;
     
     let client = HikvisionClient::new(device);
     let result = client.search_users(offset.unwrap_or(0), limit.unwrap_or(30)).await;
@@ -10,9 +10,9 @@ pub async fn fetch_users(device_id: String, offset: Option<i32>, limit: Option<i
 }
 
 #[tauri::command]
-pub async fn delete_user(device_id: String, employee_no: String) -> Result<bool, String> {
+pub async fn delete_user(device_id: String, employee_no: String) -> Result<bool, CommandError> {
     let device = get_device_by_id(&device_id)
-        .ok_or("Device not found")?;
+        .ok_or_else(|| device_not_found(&device_id))?;
     
     let client = HikvisionClient::new(device);
     let result = client.delete_user(&employee_no).await;
@@ -20,27 +20,25 @@ pub async fn delete_user(device_id: String, employee_no: String) -> Result<bool,
     if result.ok {
         Ok(true)
     } else {
-        Err(result.error_msg.unwrap_or("Delete failed".to_string()))
+        Err(action_error(&result).with_detail("employeeNo", employee_no))
     }
 }
 
 #[tauri::command]
-pub async fn get_user_face(device_id: String, employee_no: String) -> Result<serde_json::Value, String> {
+pub async fn get_user_face(device_id: String, employee_no: String) -> Result<serde_json::Value, CommandError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
 
-    let device = get_device_by_id(&device_id)
-        .ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+    let device = load_active_device(&device_id)?;
 
     let client = HikvisionClient::new(device);
     let user = client
         .get_user_by_employee_no(&employee_no)
         .await
-        .ok_or("Foydalanuvchi topilmadi")?;
+        .ok_or_else(|| CommandError::new(ErrorCode::UserNotFound).with_detail("employeeNo", employee_no.clone()))?;
 
-    let face_url = user.face_url.ok_or("Qurilmada rasm topilmadi")?;
+    let face_url = user
+        .face_url
+        .ok_or_else(|| CommandError::new(ErrorCode::FaceNotFound).with_detail("employeeNo", employee_no.clone()))?;
     let face_bytes = client.fetch_face_image(&face_url).await?;
     let image_base64 = STANDARD.encode(face_bytes);
 
@@ -57,14 +55,10 @@ pub async fn get_user_face_by_url(
     device_id: String,
     employee_no: String,
     face_url: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
 
-    let device = get_device_by_id(&device_id)
-        .ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+    let device = load_active_device(&device_id)?;
 
     let client = HikvisionClient::new(device);
     let face_bytes = client.fetch_face_image(&face_url).await?;
@@ -88,35 +82,39 @@ pub async fn recreate_user(
     new_employee_no: bool,
     reuse_existing_face: bool,
     face_image_base64: Option<String>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD};
     
     let device = get_device_by_id(&device_id)
-        .ok_or("Device not found")?;
+        .ok_or_else(|| device_not_found(&device_id))?;
     
     let client = HikvisionClient::new(device);
     
     // Test connection
     let connection = client.test_connection().await;
     if !connection.ok {
-        return Err(connection.message.unwrap_or("Device offline".to_string()));
+        let mut err = CommandError::new(ErrorCode::DeviceOffline).with_detail("deviceId", device_id);
+        if let Some(message) = connection.message {
+            err = err.with_reason(message);
+        }
+        return Err(err);
     }
 
     // Get face image - either from existing user or from provided base64
     let face_data: String = if reuse_existing_face && face_image_base64.is_none() {
         // Fetch existing face from device
         let existing_user = client.get_user_by_employee_no(&employee_no).await
-            .ok_or("User not found on device")?;
+            .ok_or_else(|| CommandError::new(ErrorCode::UserNotFound).with_detail("employeeNo", employee_no.clone()))?;
         
         let face_url = existing_user.face_url
-            .ok_or("Existing user has no face to reuse")?;
+            .ok_or_else(|| CommandError::new(ErrorCode::FaceNotFound).with_detail("employeeNo", employee_no.clone()))?;
         
         let face_bytes = client.fetch_face_image(&face_url).await?;
         STANDARD.encode(&face_bytes)
     } else if let Some(base64) = face_image_base64 {
         base64
     } else {
-        return Err("Face image is required".to_string());
+        return Err(CommandError::invalid_input("Face image is required"));
     };
 
     // Determine new employee number
@@ -129,7 +127,7 @@ pub async fn recreate_user(
     // Delete old user
     let delete_result = client.delete_user(&employee_no).await;
     if !delete_result.ok {
-        return Err(format!("Delete failed: {}", delete_result.error_msg.unwrap_or_default()));
+        return Err(action_error(&delete_result).with_detail("step", "delete"));
     }
 
     // Create new user
@@ -148,7 +146,7 @@ pub async fn recreate_user(
     ).await;
 
     if !create_result.ok {
-        return Err(format!("Create failed: {}", create_result.error_msg.unwrap_or_default()));
+        return Err(action_error(&create_result).with_detail("step", "create"));
     }
 
     // Upload face
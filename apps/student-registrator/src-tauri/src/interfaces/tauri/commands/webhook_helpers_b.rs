@@ -156,22 +156,83 @@ fn pick_primary_webhook_url(urls: &[String], direction: &str) -> Option<String>
 fn replace_xml_url_tags(xml: &str, target_url: &str) -> (String, usize) {
     let tags = ["url", "URL", "httpUrl", "HttpUrl", "HTTPUrl", "address", "Address"];
     let mut out = xml.to_string();
+    let total = tags
+        .iter()
+        .map(|tag| replace_xml_tag_values(&mut out, tag, target_url))
+        .sum();
+    (out, total)
+}
+
+/// Replace the text of every `<tag>..</tag>` element.
+fn replace_xml_tag_values(xml: &mut String, tag: &str, value: &str) -> usize {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut start = 0usize;
     let mut total = 0usize;
-    for tag in tags {
-        let open = format!("<{}>", tag);
-        let close = format!("</{}>", tag);
-        let mut start = 0usize;
-        while let Some(open_pos_rel) = out[start..].find(&open) {
-            let open_pos = start + open_pos_rel;
-            let value_start = open_pos + open.len();
-            let Some(close_pos_rel) = out[value_start..].find(&close) else { break };
-            let close_pos = value_start + close_pos_rel;
-            out.replace_range(value_start..close_pos, target_url);
-            total += 1;
-            start = value_start + target_url.len() + close.len();
+    while let Some(open_pos_rel) = xml[start..].find(&open) {
+        let value_start = start + open_pos_rel + open.len();
+        let Some(close_pos_rel) = xml[value_start..].find(&close) else { break };
+        let close_pos = value_start + close_pos_rel;
+        xml.replace_range(value_start..close_pos, value);
+        total += 1;
+        start = value_start + value.len() + close.len();
+    }
+    total
+}
+
+/// Point one XML `HttpHostNotification` element at `address` over plain HTTP.
+fn set_xml_host_address(element: &mut String, address: SocketAddrV4) {
+    replace_xml_tag_values(element, "protocolType", "HTTP");
+    replace_xml_tag_values(element, "addressingFormatType", "ipaddress");
+    replace_xml_tag_values(element, "ipAddress", &address.ip().to_string());
+    replace_xml_tag_values(element, "portNo", &address.port().to_string());
+}
+
+/// Text of the first `<tag>..</tag>` element.
+fn xml_tag_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(xml[start..end].trim())
+}
+
+/// Byte range of the XML `HttpHostNotification` element a sync writes: the one with id 1,
+/// else the first, like `extract_primary_http_host_notification` picks for JSON. `None` when
+/// the text has no such element.
+fn primary_xml_host_range(xml: &str) -> Option<std::ops::Range<usize>> {
+    const OPEN: &str = "<HttpHostNotification";
+    const CLOSE: &str = "</HttpHostNotification>";
+    let mut elements = Vec::new();
+    let mut start = 0usize;
+    while let Some(open_rel) = xml[start..].find(OPEN) {
+        let open = start + open_rel;
+        let after = open + OPEN.len();
+        // `<HttpHostNotificationList>` shares the prefix.
+        if !xml[after..].starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            start = after;
+            continue;
         }
+        let Some(close_rel) = xml[after..].find(CLOSE) else { break };
+        let end = after + close_rel + CLOSE.len();
+        elements.push(open..end);
+        start = end;
     }
-    (out, total)
+    elements
+        .iter()
+        .find(|range| xml_tag_value(&xml[(*range).clone()], "id") == Some("1"))
+        .or(elements.first())
+        .cloned()
+}
+
+/// Whether slot `host_id` now points at `address`.
+async fn http_host_has_address(client: &HikvisionClient, host_id: &str, address: SocketAddrV4) -> bool {
+    client.list_http_hosts().await.is_ok_and(|slots| {
+        slots.iter().any(|slot| {
+            slot.host.id == host_id
+                && slot.host.address == address.ip().to_string()
+                && slot.host.port == address.port()
+        })
+    })
 }
 
 fn normalize_http_hosts_put_path(path: &str) -> String {
@@ -203,3 +264,30 @@ fn normalize_target_url_for_device(target_url: &str) -> String {
     }
 }
 
+
+#[cfg(test)]
+mod webhook_helpers_b_tests {
+    use super::*;
+
+    #[test]
+    fn xml_sync_rewrites_only_the_primary_http_host() {
+        let xml = "<HttpHostNotificationList version=\"2.0\">\
+            <HttpHostNotification><id>2</id><url>/other</url><ipAddress>10.0.0.9</ipAddress><portNo>80</portNo></HttpHostNotification>\
+            <HttpHostNotification>\n<id>1</id><url>/old</url><ipAddress>10.0.0.1</ipAddress><portNo>80</portNo></HttpHostNotification>\
+            </HttpHostNotificationList>";
+        let range = primary_xml_host_range(xml).unwrap();
+        assert!(xml[range.clone()].contains("<id>1</id>"));
+
+        let (mut element, replaced) = replace_xml_url_tags(&xml[range.clone()], "/webhook/s1/in");
+        set_xml_host_address(&mut element, SocketAddrV4::new([192, 168, 1, 20].into(), 18090));
+        let updated = format!("{}{}{}", &xml[..range.start], element, &xml[range.end..]);
+
+        assert_eq!(replaced, 1);
+        assert!(updated.contains("<id>2</id><url>/other</url><ipAddress>10.0.0.9</ipAddress><portNo>80</portNo>"));
+        let primary = &updated[primary_xml_host_range(&updated).unwrap()];
+        assert_eq!(xml_tag_value(primary, "url"), Some("/webhook/s1/in"));
+        assert_eq!(xml_tag_value(primary, "ipAddress"), Some("192.168.1.20"));
+        assert_eq!(xml_tag_value(primary, "portNo"), Some("18090"));
+        assert!(primary_xml_host_range("<ResponseStatus/>").is_none());
+    }
+}
@@ -0,0 +1,140 @@
+// ============ Webhook Hosts ============
+//
+// Every `HttpHostNotification` slot of a device as a typed `HttpHost`, so entries and exits can
+// post to separate hosts. `get/sync_device_webhook_config` still work on the primary slot only.
+
+fn http_host_not_found(host_id: &str) -> CommandError {
+    CommandError::invalid_input(format!("http host {} not found", host_id)).with_detail("hostId", host_id)
+}
+
+/// Device refusals become `WEBHOOK_SYNC_FAILED`; connection problems keep their own code.
+fn http_host_write_error(err: HikvisionError, device_id: &str, host_id: &str) -> CommandError {
+    let error = match err {
+        HikvisionError::DeviceStatus { .. } | HikvisionError::Http { .. } | HikvisionError::Parse(_) => {
+            CommandError::new(ErrorCode::WebhookSyncFailed).with_reason(err.to_string())
+        }
+        other => CommandError::from(other),
+    };
+    error.with_detail("deviceId", device_id).with_detail("hostId", host_id)
+}
+
+fn checked_http_host(host: HttpHost) -> Result<HttpHost, CommandError> {
+    let host = host.normalized();
+    host.validate().map_err(CommandError::invalid_input)?;
+    Ok(host)
+}
+
+/// Saved slot must carry what was written; firmware drops fields it does not support silently.
+fn ensure_http_host_applied(expected: &HttpHost, saved: HttpHost, device_id: &str) -> Result<HttpHost, CommandError> {
+    let applied = saved.url == expected.url
+        && saved.port == expected.port
+        && saved.address.eq_ignore_ascii_case(&expected.address);
+    if applied {
+        return Ok(saved);
+    }
+    Err(CommandError::new(ErrorCode::WebhookSyncFailed)
+        .with_detail("deviceId", device_id)
+        .with_detail("hostId", expected.id.clone())
+        .with_detail("expected", serde_json::json!(expected))
+        .with_detail("actual", serde_json::json!(saved)))
+}
+
+/// `err` with the outcome of undoing a write the device did not apply: `restored`, and
+/// `restoreError` when the undo failed too.
+fn with_rollback(err: CommandError, rollback: Result<(), HikvisionError>) -> CommandError {
+    match rollback {
+        Ok(()) => err.with_detail("restored", true),
+        Err(rollback_err) => err
+            .with_detail("restored", false)
+            .with_detail("restoreError", rollback_err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_device_http_hosts(device_id: String) -> Result<Vec<HttpHost>, CommandError> {
+    let device = load_active_device(&device_id)?;
+    let hosts = HikvisionClient::new(device)
+        .list_http_hosts()
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id))?;
+    Ok(hosts.into_iter().map(|slot| slot.host).collect())
+}
+
+/// Create a slot. An empty `host.id` takes the first free id; the device may still refuse when
+/// all its slots are used. When the device does not apply the fields, the new slot is deleted.
+#[tauri::command]
+pub async fn add_device_http_host(device_id: String, host: HttpHost) -> Result<HttpHost, CommandError> {
+    let mut host = checked_http_host(host)?;
+    let device = load_active_device(&device_id)?;
+    let client = HikvisionClient::new(device);
+    let existing: Vec<HttpHost> = client
+        .list_http_hosts()
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id.clone()))?
+        .into_iter()
+        .map(|slot| slot.host)
+        .collect();
+    if host.id.is_empty() {
+        host.id = next_http_host_id(&existing);
+    } else if existing.iter().any(|slot| slot.id == host.id) {
+        return Err(CommandError::invalid_input(format!("http host {} already exists", host.id)).with_detail("hostId", host.id));
+    }
+    let saved = client
+        .save_http_host(&host, None)
+        .await
+        .map_err(|err| http_host_write_error(err, &device_id, &host.id))?;
+    match ensure_http_host_applied(&host, saved.host, &device_id) {
+        Ok(applied) => Ok(applied),
+        Err(err) => Err(with_rollback(err, client.delete_http_host(&host.id).await)),
+    }
+}
+
+/// Replace the typed fields of slot `host.id`; other fields of the slot are kept. When the device
+/// does not apply them, the slot is restored as it was read.
+#[tauri::command]
+pub async fn update_device_http_host(device_id: String, host: HttpHost) -> Result<HttpHost, CommandError> {
+    let host = checked_http_host(host)?;
+    if host.id.is_empty() {
+        return Err(CommandError::invalid_input("host.id is required"));
+    }
+    let device = load_active_device(&device_id)?;
+    let client = HikvisionClient::new(device);
+    let current = client
+        .list_http_hosts()
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id.clone()))?
+        .into_iter()
+        .find(|slot| slot.host.id == host.id)
+        .ok_or_else(|| http_host_not_found(&host.id))?;
+    let saved = client
+        .save_http_host(&host, Some(&current))
+        .await
+        .map_err(|err| http_host_write_error(err, &device_id, &host.id))?;
+    match ensure_http_host_applied(&host, saved.host, &device_id) {
+        Ok(applied) => Ok(applied),
+        // Half-applied slots post to the wrong place; put the one read before back.
+        Err(err) => Err(with_rollback(err, client.restore_http_host(&current).await)),
+    }
+}
+
+/// `false` when the device has no such slot.
+#[tauri::command]
+pub async fn delete_device_http_host(device_id: String, host_id: String) -> Result<bool, CommandError> {
+    let host_id = host_id.trim().to_string();
+    let device = load_active_device(&device_id)?;
+    let client = HikvisionClient::new(device);
+    let exists = client
+        .list_http_hosts()
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id.clone()))?
+        .iter()
+        .any(|slot| slot.host.id == host_id);
+    if !exists {
+        return Ok(false);
+    }
+    client
+        .delete_http_host(&host_id)
+        .await
+        .map_err(|err| http_host_write_error(err, &device_id, &host_id))?;
+    Ok(true)
+}
@@ -0,0 +1,204 @@
+// ============ Webhook Relay ============
+//
+// Optional relay mode for schools the VPS cannot reach: devices post their webhooks to a
+// listener in this app, which stores them and forwards them to the backend with retries (see
+// `infrastructure::relay::webhook_relay`). The config is kept in the `webhookRelay` setting and
+// an enabled relay is started again with the app.
+
+const WEBHOOK_RELAY_SETTING: &str = "webhookRelay";
+const DEFAULT_RELAY_PORT: u16 = 18090;
+
+fn webhook_relay() -> &'static Mutex<Option<WebhookRelay>> {
+    static RELAY: OnceLock<Mutex<Option<WebhookRelay>>> = OnceLock::new();
+    RELAY.get_or_init(|| Mutex::new(None))
+}
+
+fn relay_not_running() -> CommandError {
+    CommandError::new(ErrorCode::RelayFailed).with_reason("relay is not running")
+}
+
+fn current_relay_status() -> RelayStatus {
+    if let Some(status) = webhook_relay()
+        .lock()
+        .ok()
+        .and_then(|relay| relay.as_ref().map(WebhookRelay::status))
+    {
+        return status;
+    }
+    let (pending, failed) = app_store().and_then(relay_queue_counts).unwrap_or_default();
+    RelayStatus {
+        pending,
+        failed,
+        ..Default::default()
+    }
+}
+
+fn normalize_backend_url(backend_url: &str) -> Result<String, CommandError> {
+    let backend_url = backend_url.trim().trim_end_matches('/').to_string();
+    match reqwest::Url::parse(&backend_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.query().is_none() => Ok(backend_url),
+        _ => Err(CommandError::invalid_input("backendUrl must be an http(s) base URL without query")),
+    }
+}
+
+async fn launch_webhook_relay(config: &RelayConfig) -> Result<RelayStatus, CommandError> {
+    let previous = webhook_relay().lock().ok().and_then(|mut relay| relay.take());
+    if let Some(previous) = previous {
+        previous.shutdown().await;
+    }
+    let store = app_store().map_err(CommandError::storage)?;
+    let listen = SocketAddr::from((Ipv4Addr::UNSPECIFIED, config.listen_port));
+    let mut options = RelayOptions::new(listen, &config.backend_url);
+    options.routes = config.routes.iter().cloned().collect();
+    let relay = WebhookRelay::start(store, options)
+        .await
+        .map_err(|reason| {
+            CommandError::new(ErrorCode::RelayFailed)
+                .with_detail("listenPort", config.listen_port)
+                .with_reason(reason)
+        })?;
+    let status = relay.status();
+    if let Ok(mut slot) = webhook_relay().lock() {
+        *slot = Some(relay);
+    }
+    Ok(status)
+}
+
+/// Start the relay on app start when it was left enabled.
+pub fn resume_webhook_relay() {
+    let config = match load_setting::<RelayConfig>(WEBHOOK_RELAY_SETTING) {
+        Ok(Some(config)) if config.enabled => config,
+        Ok(_) => return,
+        Err(err) => {
+            eprintln!("webhook relay config not loaded: {}", err);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = launch_webhook_relay(&config).await {
+            eprintln!("webhook relay not resumed: {}", err);
+        }
+    });
+}
+
+/// Listen for device webhooks on `listen_port` (all interfaces) and forward them to
+/// `backend_url`, the backend base URL the device paths (`/webhook/<schoolId>/in?..`) are
+/// appended to. A running relay is restarted with the new settings; synced device routes are kept.
+#[tauri::command]
+pub async fn start_webhook_relay(backend_url: String, listen_port: Option<u16>) -> Result<RelayStatus, CommandError> {
+    let routes = load_setting::<RelayConfig>(WEBHOOK_RELAY_SETTING)
+        .map_err(CommandError::storage)?
+        .map(|config| config.routes)
+        .unwrap_or_default();
+    let config = RelayConfig {
+        enabled: true,
+        listen_port: listen_port.unwrap_or(DEFAULT_RELAY_PORT),
+        backend_url: normalize_backend_url(&backend_url)?,
+        routes,
+    };
+    if config.listen_port == 0 {
+        return Err(CommandError::invalid_input("listenPort must not be 0"));
+    }
+    let status = launch_webhook_relay(&config).await?;
+    save_setting(WEBHOOK_RELAY_SETTING, &config).map_err(CommandError::storage)?;
+    Ok(status)
+}
+
+/// Stop listening and forwarding; queued webhooks stay for the next start.
+#[tauri::command]
+pub async fn stop_webhook_relay() -> Result<RelayStatus, CommandError> {
+    let relay = webhook_relay().lock().ok().and_then(|mut relay| relay.take());
+    if let Some(relay) = relay {
+        relay.shutdown().await;
+    }
+    if let Some(mut config) = load_setting::<RelayConfig>(WEBHOOK_RELAY_SETTING).map_err(CommandError::storage)? {
+        config.enabled = false;
+        save_setting(WEBHOOK_RELAY_SETTING, &config).map_err(CommandError::storage)?;
+    }
+    Ok(current_relay_status())
+}
+
+#[tauri::command]
+pub fn get_webhook_relay_status() -> RelayStatus {
+    current_relay_status()
+}
+
+/// Queue the webhooks the backend refused again, e.g. after the school secret was fixed.
+#[tauri::command]
+pub fn retry_failed_relay_events() -> Result<RelayStatus, CommandError> {
+    let store = app_store().map_err(CommandError::storage)?;
+    requeue_failed_relay_webhooks(store).map_err(CommandError::storage)?;
+    if let Ok(relay) = webhook_relay().lock() {
+        if let Some(relay) = relay.as_ref() {
+            relay.wake();
+        }
+    }
+    Ok(current_relay_status())
+}
+
+async fn device_ipv4(device: &DeviceConfig) -> Option<Ipv4Addr> {
+    let host = device.host.trim();
+    if let Ok(ip) = host.parse() {
+        return Some(ip);
+    }
+    tokio::net::lookup_host((host, device.port))
+        .await
+        .ok()?
+        .find_map(|addr| match addr {
+            SocketAddr::V4(addr) => Some(*addr.ip()),
+            SocketAddr::V6(_) => None,
+        })
+}
+
+/// Let the relay accept webhooks posted to `route` and keep it in the setting for later starts.
+fn register_relay_route(route: &str) -> Result<(), CommandError> {
+    if !route.starts_with(RELAY_PATH_PREFIX) {
+        return Err(CommandError::invalid_input("targetUrl must be a /webhook/ path"));
+    }
+    if let Some(mut config) = load_setting::<RelayConfig>(WEBHOOK_RELAY_SETTING).map_err(CommandError::storage)? {
+        if !config.routes.iter().any(|known| known == route) {
+            config.routes.push(route.to_string());
+            save_setting(WEBHOOK_RELAY_SETTING, &config).map_err(CommandError::storage)?;
+        }
+    }
+    if let Ok(relay) = webhook_relay().lock() {
+        if let Some(relay) = relay.as_ref() {
+            relay.allow_route(route);
+        }
+    }
+    Ok(())
+}
+
+/// Point the device webhook at the running relay: `target_url` keeps its path and query (school
+/// id, direction, secret), the http host gets this PC's address on the device's network. The
+/// path is registered with the relay first so the device's first webhook is already accepted.
+#[tauri::command]
+pub async fn sync_device_webhook_to_relay(
+    device_id: String,
+    direction: String,
+    target_url: String,
+) -> Result<Value, CommandError> {
+    let port = webhook_relay()
+        .lock()
+        .ok()
+        .and_then(|relay| relay.as_ref().map(|relay| relay.local_addr().port()))
+        .ok_or_else(relay_not_running)?;
+    let device = load_active_device(&device_id)?;
+    let device_ip = device_ipv4(&device).await.ok_or_else(|| {
+        CommandError::new(ErrorCode::RelayFailed)
+            .with_detail("deviceId", device_id.clone())
+            .with_reason(format!("device host {} has no IPv4 address", device.host))
+    })?;
+    let local_ip = local_ipv4_towards(device_ip).map_err(|reason| {
+        CommandError::new(ErrorCode::RelayFailed)
+            .with_detail("deviceId", device_id.clone())
+            .with_reason(reason)
+    })?;
+    let relay_address = SocketAddrV4::new(local_ip, port);
+    register_relay_route(&normalize_target_url_for_device(&target_url))?;
+    let mut result = write_device_webhook(&device_id, &direction, &target_url, Some(relay_address)).await?;
+    if let Value::Object(map) = &mut result {
+        map.insert("relayAddress".to_string(), Value::from(relay_address.to_string()));
+    }
+    Ok(result)
+}
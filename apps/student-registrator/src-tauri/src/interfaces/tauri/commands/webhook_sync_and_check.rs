@@ -2,12 +2,9 @@
 pub async fn get_device_webhook_config(
     device_id: String,
     direction: String,
-) -> Result<Value, String> {
+) -> Result<Value, CommandError> {
     let normalized = normalize_direction(&direction)?;
-    let device = get_device_by_id(&device_id).ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+    let device = load_active_device(&device_id)?;
     let client = HikvisionClient::new(device);
     if let Ok((path, raw)) = read_device_webhook_config(&client).await {
         let scoped = extract_primary_http_host_notification(&raw)
@@ -52,7 +49,7 @@ pub async fn get_device_webhook_config(
             Err(err) => errors.push(format!("{} => {}", path, err)),
         }
     }
-    Err(format!("Webhookni qurilmadan o'qib bo'lmadi: {}", errors.join(" | ")))
+    Err(CommandError::new(ErrorCode::WebhookReadFailed).with_detail("attempts", errors))
 }
 
 #[tauri::command]
@@ -60,17 +57,25 @@ pub async fn sync_device_webhook_config(
     device_id: String,
     direction: String,
     target_url: String,
-) -> Result<Value, String> {
-    let normalized = normalize_direction(&direction)?;
-    let target_url = normalize_target_url_for_device(&target_url);
+) -> Result<Value, CommandError> {
+    write_device_webhook(&device_id, &direction, &target_url, None).await
+}
+
+/// Save `target_url` (its path and query) on the device's primary http host. `host` also points
+/// the http host at another address, e.g. the local webhook relay.
+async fn write_device_webhook(
+    device_id: &str,
+    direction: &str,
+    target_url: &str,
+    host: Option<SocketAddrV4>,
+) -> Result<Value, CommandError> {
+    let normalized = normalize_direction(direction)?;
+    let target_url = normalize_target_url_for_device(target_url);
     if target_url.is_empty() {
-        return Err("targetUrl bo'sh bo'lmasligi kerak".to_string());
+        return Err(CommandError::invalid_input("targetUrl bo'sh bo'lmasligi kerak"));
     }
 
-    let device = get_device_by_id(&device_id).ok_or("Device not found")?;
-    if is_credentials_expired(&device) {
-        return Err("Ulanish sozlamalari muddati tugagan".to_string());
-    }
+    let device = load_active_device(device_id)?;
 
     let client = HikvisionClient::new(device);
     if let Ok((_path, mut before_raw)) = read_device_webhook_config(&client).await {
@@ -79,9 +84,19 @@ pub async fn sync_device_webhook_config(
         before_raw = serde_json::json!({ "HttpHostNotification": notification.clone() });
         let before_urls = extract_webhook_urls_from_json(&before_raw);
 
+        // Typed fields only, as in `update_device_http_host`; the rest of the slot is kept.
         let mut updated_notification = notification.clone();
-        let replaced = replace_url_fields(&mut updated_notification, &target_url);
-        if replaced > 0 {
+        if let Value::Object(map) = &mut updated_notification {
+            map.entry("id").or_insert_with(|| Value::from(host_id.clone()));
+        }
+        if let Some(mut primary) = http_host_from_notification(&updated_notification) {
+            primary.url = target_url.clone();
+            if let Some(address) = host {
+                primary.protocol = HttpHostProtocol::Http;
+                primary.address = address.ip().to_string();
+                primary.port = address.port();
+            }
+            apply_http_host(&mut updated_notification, &primary);
             let target_cmp = normalize_target_url_for_device(&target_url);
 
             let mut write_attempt_errors: Vec<String> = Vec::new();
@@ -118,9 +133,12 @@ pub async fn sync_device_webhook_config(
                             continue;
                         }
                         let after_urls = read_http_host_urls(&client, &host_id).await;
-                        let is_applied = after_urls
+                        let mut is_applied = after_urls
                             .iter()
                             .any(|item| normalize_target_url_for_device(item) == target_cmp);
+                        if let (true, Some(address)) = (is_applied, host) {
+                            is_applied = http_host_has_address(&client, &host_id, address).await;
+                        }
                         if is_applied {
                             return Ok(serde_json::json!({
                                 "ok": true,
@@ -128,7 +146,7 @@ pub async fn sync_device_webhook_config(
                                 "path": put_path,
                                 "format": "json",
                                 "attempt": attempt_name,
-                                "replacedFields": replaced,
+                                "replacedFields": 1,
                                 "beforeUrls": before_urls,
                                 "afterUrls": after_urls,
                                 "raw": put_result
@@ -146,12 +164,10 @@ pub async fn sync_device_webhook_config(
                 }
             }
 
-            return Err(format!(
-                "Qurilma URLni saqlamadi. hostId={}, kutilgan={}, urinishlar={}",
-                host_id,
-                target_cmp,
-                write_attempt_errors.join(" || ")
-            ));
+            return Err(CommandError::new(ErrorCode::WebhookSyncFailed)
+                .with_detail("hostId", host_id)
+                .with_detail("expectedUrl", target_cmp)
+                .with_detail("attempts", write_attempt_errors));
         }
     }
 
@@ -163,15 +179,43 @@ pub async fn sync_device_webhook_config(
                 if before_urls.is_empty() {
                     continue;
                 }
-                let (updated, replaced) = replace_xml_url_tags(&text, &target_url);
+                // Only the primary slot is written, as in the JSON path; other slots keep
+                // their targets.
+                let range = primary_xml_host_range(&text).unwrap_or(0..text.len());
+                let (mut element, replaced) = replace_xml_url_tags(&text[range.clone()], &target_url);
                 if replaced == 0 {
                     continue;
                 }
+                if let Some(address) = host {
+                    set_xml_host_address(&mut element, address);
+                }
+                let updated = format!("{}{}{}", &text[..range.start], element, &text[range.end..]);
                 let put_path = normalize_http_hosts_put_path(path);
-                let after_text = client
+                let put_text = client
                     .put_isapi_raw(put_path.as_str(), updated, Some("application/xml"))
                     .await?;
-                let after_urls = extract_urls_from_text(&after_text);
+
+                // Read the slot back: the device may answer OK and keep its old target.
+                let after_text = client.get_isapi_raw(path).await.unwrap_or_default();
+                let after_element = primary_xml_host_range(&after_text)
+                    .map(|range| &after_text[range])
+                    .unwrap_or(&after_text);
+                let after_urls = extract_urls_from_text(after_element);
+                let url_applied = after_urls
+                    .iter()
+                    .any(|item| normalize_target_url_for_device(item) == target_url);
+                let address_applied = host.is_none_or(|address| {
+                    xml_tag_value(after_element, "ipAddress") == Some(address.ip().to_string().as_str())
+                        && xml_tag_value(after_element, "portNo") == Some(address.port().to_string().as_str())
+                });
+                if !(url_applied && address_applied) {
+                    errors.push(format!(
+                        "{} => applied=false, after={}",
+                        put_path,
+                        if after_urls.is_empty() { "-".to_string() } else { after_urls.join(" | ") }
+                    ));
+                    continue;
+                }
                 return Ok(serde_json::json!({
                     "ok": true,
                     "direction": normalized,
@@ -181,25 +225,19 @@ pub async fn sync_device_webhook_config(
                     "beforeUrls": before_urls,
                     "afterUrls": after_urls,
                     "raw": {
-                        "text": after_text
+                        "text": put_text
                     }
                 }));
             }
             Err(err) => errors.push(format!("{} => {}", path, err)),
         }
     }
-    Err(format!("Webhook sync qilib bo'lmadi: {}", errors.join(" | ")))
+    Err(CommandError::new(ErrorCode::WebhookSyncFailed).with_detail("attempts", errors))
 }
 
 #[tauri::command]
-pub async fn check_student_on_device(device_id: String, employee_no: String) -> Result<Value, String> {
-    let mut devices = load_devices();
-    let index = devices
-        .iter()
-        .position(|d| d.id == device_id)
-        .ok_or("Device not found")?;
-
-    let device = devices[index].clone();
+pub async fn check_student_on_device(device_id: String, employee_no: String) -> Result<Value, CommandError> {
+    let mut device = get_device_by_id(&device_id).ok_or_else(|| device_not_found(&device_id))?;
     if is_credentials_expired(&device) {
         return Ok(serde_json::json!({
             "deviceId": device_id,
@@ -224,11 +262,8 @@ pub async fn check_student_on_device(device_id: String, employee_no: String) ->
         }));
     }
 
-    if let Some(found_id) = connection.device_id.clone() {
-        if devices[index].device_id.as_deref() != Some(found_id.as_str()) {
-            devices[index].device_id = Some(found_id);
-            let _ = save_devices(&devices);
-        }
+    if apply_connection_identity(&mut device, &connection) {
+        save_device_identity(std::slice::from_ref(&device));
     }
 
     let user = client.get_user_by_employee_no(employee_no.as_str()).await;
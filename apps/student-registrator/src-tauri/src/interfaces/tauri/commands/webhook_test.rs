@@ -0,0 +1,144 @@
+// ============ Webhook Test ============
+//
+// `test_device_webhook` asks the device to post a test message to one of its http hosts
+// (`httpHosts/<id>/test`). With `capture`, the slot points at a temporary listener in this app
+// for the duration of the test and is put back afterwards, so the report shows exactly what
+// the device sends.
+
+const WEBHOOK_TEST_TIMEOUT_MS: u64 = 5000;
+const MAX_WEBHOOK_TEST_TIMEOUT_MS: u64 = 30_000;
+
+fn http_host_target(host: &HttpHost) -> String {
+    let scheme = match host.protocol {
+        HttpHostProtocol::Http => "http",
+        HttpHostProtocol::Https => "https",
+    };
+    format!("{}://{}:{}{}", scheme, host.address, host.port, host.url)
+}
+
+fn elapsed_ms(since: std::time::Instant, until: std::time::Instant) -> u64 {
+    until.saturating_duration_since(since).as_millis() as u64
+}
+
+/// Trigger the test and, with `capture`, wait for the post it causes.
+async fn run_webhook_test(
+    client: &HikvisionClient,
+    slot: &DeviceHttpHost,
+    capture: Option<&mut WebhookCapture>,
+    timeout: std::time::Duration,
+    report: &mut WebhookTestReport,
+) {
+    let started = std::time::Instant::now();
+    match client.test_http_host(slot).await {
+        Ok(outcome) => {
+            report.latency_ms = Some(elapsed_ms(started, std::time::Instant::now()));
+            report.reachable = outcome.ok;
+            if !outcome.ok {
+                report.error = outcome.description.clone();
+            }
+            report.device_result = outcome.description;
+        }
+        Err(err) => report.error = Some(err.to_string()),
+    }
+    let Some(capture) = capture else {
+        return;
+    };
+    match capture.next_request(timeout.saturating_sub(started.elapsed())).await {
+        Some(request) => {
+            // The post arrived whatever the device said; its verdict stays in `device_result`.
+            report.reachable = true;
+            report.error = None;
+            report.captured = Some(CapturedWebhook {
+                method: request.method,
+                path: request.path,
+                content_type: request.content_type,
+                body: String::from_utf8_lossy(&request.body).into_owned(),
+                body_bytes: request.body.len(),
+                latency_ms: elapsed_ms(started, request.received_at),
+            });
+        }
+        None => {
+            report.reachable = false;
+            report
+                .error
+                .get_or_insert_with(|| format!("no request reached this PC within {} ms", timeout.as_millis()));
+        }
+    }
+}
+
+/// Send a test message from the device to http host `host_id` (default: the first slot).
+/// Without `capture` the report carries the device's own verdict and latency. With `capture`,
+/// the slot is pointed at this PC for the test, the post is recorded as received, and the slot
+/// is restored exactly as it was; a failed restore is reported in `restore_error`.
+#[tauri::command]
+pub async fn test_device_webhook(
+    device_id: String,
+    host_id: Option<String>,
+    capture: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<WebhookTestReport, CommandError> {
+    let device = load_active_device(&device_id)?;
+    let client = HikvisionClient::new(device.clone());
+    let slots = client
+        .list_http_hosts()
+        .await
+        .map_err(|err| CommandError::from(err).with_detail("deviceId", device_id.clone()))?;
+    let slot = match host_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => slots
+            .into_iter()
+            .find(|slot| slot.host.id == id)
+            .ok_or_else(|| http_host_not_found(id))?,
+        None => slots.into_iter().next().ok_or_else(|| {
+            CommandError::new(ErrorCode::WebhookReadFailed)
+                .with_detail("deviceId", device_id.clone())
+                .with_reason("device has no http host")
+        })?,
+    };
+    let timeout = std::time::Duration::from_millis(
+        timeout_ms
+            .unwrap_or(WEBHOOK_TEST_TIMEOUT_MS)
+            .clamp(500, MAX_WEBHOOK_TEST_TIMEOUT_MS),
+    );
+    let mut report = WebhookTestReport {
+        device_id: device_id.clone(),
+        host_id: slot.host.id.clone(),
+        target: http_host_target(&slot.host),
+        ..Default::default()
+    };
+    if !capture.unwrap_or(false) {
+        run_webhook_test(&client, &slot, None, timeout, &mut report).await;
+        return Ok(report);
+    }
+
+    let local_ip = match device_ipv4(&device).await {
+        Some(device_ip) => local_ipv4_towards(device_ip),
+        None => Err(format!("device host {} has no IPv4 address", device.host)),
+    }
+    .map_err(|reason| {
+        CommandError::new(ErrorCode::RelayFailed)
+            .with_detail("deviceId", device_id.clone())
+            .with_reason(reason)
+    })?;
+    let mut listener = WebhookCapture::start(SocketAddr::from((local_ip, 0)))
+        .await
+        .map_err(|reason| CommandError::new(ErrorCode::RelayFailed).with_reason(reason))?;
+    let capture_host = HttpHost {
+        protocol: HttpHostProtocol::Http,
+        address: local_ip.to_string(),
+        port: listener.local_addr().port(),
+        ..slot.host.clone()
+    };
+    report.target = http_host_target(&capture_host);
+    match client.save_http_host(&capture_host, Some(&slot)).await {
+        Ok(test_slot) => run_webhook_test(&client, &test_slot, Some(&mut listener), timeout, &mut report).await,
+        Err(err) => report.error = Some(format!("capture target not saved: {}", err)),
+    }
+    if let Err(err) = client.restore_http_host(&slot).await {
+        report.restore_error = Some(format!(
+            "slot still points at this PC, set it back to {}: {}",
+            http_host_target(&slot.host),
+            err
+        ));
+    }
+    Ok(report)
+}
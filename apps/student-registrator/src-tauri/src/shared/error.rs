@@ -0,0 +1,386 @@
+// Command error contract: every Tauri command rejects with `{ code, message, details }`.
+// `code` is stable and documented in ARCHITECTURE.md ("Command errors"), `message` is localized from
+// the table below, `details` carries the technical context (device, raw device reply, ...).
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+
+use crate::hikvision::HikvisionError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    CredentialsExpired,
+    CredentialsRenewalFailed,
+    DeviceNotFound,
+    DeviceLimitReached,
+    NoDevicesConfigured,
+    DeviceOffline,
+    DeviceTimeout,
+    DeviceUnauthorized,
+    DeviceTlsError,
+    DeviceRejected,
+    DeviceInvalidResponse,
+    DeviceUnsupported,
+    DeviceBundleUnreadable,
+    DiscoveryFailed,
+    ActivationFailed,
+    PasswordRotationFailed,
+    UserNotFound,
+    FaceNotFound,
+    FaceTooLarge,
+    InvalidImage,
+    FaceQualityRejected,
+    InvalidInput,
+    WebhookReadFailed,
+    WebhookSyncFailed,
+    BackendRejected,
+    RelayFailed,
+    StorageFailed,
+    JobNotFound,
+    JobStateConflict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Uz,
+    Ru,
+    En,
+}
+
+impl Locale {
+    pub fn parse(value: &str) -> Option<Self> {
+        let lower = value.trim().to_lowercase();
+        match lower.split(['-', '_']).next().unwrap_or("") {
+            "uz" => Some(Self::Uz),
+            "ru" => Some(Self::Ru),
+            "en" => Some(Self::En),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Uz => "uz",
+            Self::Ru => "ru",
+            Self::En => "en",
+        }
+    }
+}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::Uz);
+
+pub fn current_locale() -> Locale {
+    LOCALE.read().map(|locale| *locale).unwrap_or(Locale::Uz)
+}
+
+pub fn set_locale(locale: Locale) {
+    if let Ok(mut current) = LOCALE.write() {
+        *current = locale;
+    }
+}
+
+/// Localized text per code as (uz, ru, en).
+fn message_table(code: ErrorCode) -> (&'static str, &'static str, &'static str) {
+    match code {
+        ErrorCode::CredentialsExpired => (
+            "Ulanish sozlamalari muddati tugagan",
+            "Срок действия учетных данных подключения истек",
+            "Device credentials have expired",
+        ),
+        ErrorCode::CredentialsRenewalFailed => (
+            "Qurilma tekshiruvdan o'tmadi, ulanish muddati uzaytirilmadi",
+            "Устройство не прошло проверку, срок действия не продлен",
+            "Device could not be verified; credentials were not renewed",
+        ),
+        ErrorCode::DeviceNotFound => (
+            "Qurilma topilmadi",
+            "Устройство не найдено",
+            "Device not found",
+        ),
+        ErrorCode::DeviceLimitReached => (
+            "Qurilmalar soni chegarasiga yetildi",
+            "Достигнут лимит количества устройств",
+            "Maximum number of devices reached",
+        ),
+        ErrorCode::NoDevicesConfigured => (
+            "Hech qanday qurilma sozlanmagan",
+            "Нет настроенных устройств",
+            "No devices configured",
+        ),
+        ErrorCode::DeviceOffline => (
+            "Qurilmaga ulanib bo'lmadi",
+            "Не удалось подключиться к устройству",
+            "Device is unreachable",
+        ),
+        ErrorCode::DeviceTimeout => (
+            "Qurilma javob bermadi (vaqt tugadi)",
+            "Устройство не ответило (тайм-аут)",
+            "Device did not respond in time",
+        ),
+        ErrorCode::DeviceUnauthorized => (
+            "Qurilma login yoki parolni rad etdi",
+            "Устройство отклонило логин или пароль",
+            "Device rejected the username or password",
+        ),
+        ErrorCode::DeviceTlsError => (
+            "Qurilmaning TLS sertifikati tasdiqlanmadi",
+            "TLS-сертификат устройства не прошел проверку",
+            "Device TLS certificate could not be verified",
+        ),
+        ErrorCode::DeviceRejected => (
+            "Qurilma so'rovni rad etdi",
+            "Устройство отклонило запрос",
+            "Device rejected the request",
+        ),
+        ErrorCode::DeviceInvalidResponse => (
+            "Qurilmadan noto'g'ri javob keldi",
+            "Устройство вернуло некорректный ответ",
+            "Device returned an invalid response",
+        ),
+        ErrorCode::DeviceUnsupported => (
+            "Bu amal qurilmada qo'llab-quvvatlanmaydi",
+            "Операция не поддерживается устройством",
+            "Operation is not supported by the device",
+        ),
+        ErrorCode::DeviceBundleUnreadable => (
+            "Qurilmalar faylini ochib bo'lmadi: parol noto'g'ri yoki fayl buzilgan",
+            "Не удалось открыть файл устройств: неверный пароль или файл повреждён",
+            "Could not open the device bundle: wrong passphrase or damaged file",
+        ),
+        ErrorCode::DiscoveryFailed => (
+            "Tarmoqda qurilmalarni qidirib bo'lmadi",
+            "Не удалось выполнить поиск устройств в сети",
+            "Could not search the network for devices",
+        ),
+        ErrorCode::ActivationFailed => (
+            "Qurilmani aktivlashtirib bo'lmadi",
+            "Не удалось активировать устройство",
+            "Could not activate the device",
+        ),
+        ErrorCode::PasswordRotationFailed => (
+            "Qurilma parolini almashtirib bo'lmadi",
+            "Не удалось сменить пароль устройства",
+            "Could not rotate the device password",
+        ),
+        ErrorCode::UserNotFound => (
+            "Foydalanuvchi topilmadi",
+            "Пользователь не найден",
+            "User not found",
+        ),
+        ErrorCode::FaceNotFound => (
+            "Qurilmada rasm topilmadi",
+            "Фото на устройстве не найдено",
+            "No face image on the device",
+        ),
+        ErrorCode::FaceTooLarge => (
+            "Rasm hajmi juda katta",
+            "Размер фото слишком большой",
+            "Face image is too large",
+        ),
+        ErrorCode::InvalidImage => (
+            "Rasm formati noto'g'ri",
+            "Неверный формат изображения",
+            "Invalid image",
+        ),
+        ErrorCode::FaceQualityRejected => (
+            "Rasm sifati yetarli emas, qayta suratga oling",
+            "Недостаточное качество фото, переснимите",
+            "Photo quality is not good enough, please retake it",
+        ),
+        ErrorCode::InvalidInput => (
+            "Kiritilgan ma'lumot noto'g'ri",
+            "Неверные входные данные",
+            "Invalid input",
+        ),
+        ErrorCode::WebhookReadFailed => (
+            "Webhookni qurilmadan o'qib bo'lmadi",
+            "Не удалось прочитать webhook с устройства",
+            "Could not read the webhook from the device",
+        ),
+        ErrorCode::WebhookSyncFailed => (
+            "Webhookni qurilmaga saqlab bo'lmadi",
+            "Не удалось сохранить webhook на устройстве",
+            "Could not save the webhook on the device",
+        ),
+        ErrorCode::BackendRejected => (
+            "Server so'rovni rad etdi",
+            "Сервер отклонил запрос",
+            "Backend rejected the request",
+        ),
+        ErrorCode::RelayFailed => (
+            "Webhook relay ishlamayapti yoki ishga tushmadi",
+            "Webhook-ретранслятор не запущен или не смог запуститься",
+            "Webhook relay is not running or could not start",
+        ),
+        ErrorCode::StorageFailed => (
+            "Lokal saqlashda xato",
+            "Ошибка локального хранилища",
+            "Local storage error",
+        ),
+        ErrorCode::JobNotFound => (
+            "Vazifa topilmadi",
+            "Задача не найдена",
+            "Job not found",
+        ),
+        ErrorCode::JobStateConflict => (
+            "Vazifaning joriy holatida bu amalni bajarib bo'lmaydi",
+            "Действие недоступно в текущем состоянии задачи",
+            "Action is not allowed in the job's current state",
+        ),
+    }
+}
+
+pub fn localized_message(code: ErrorCode, locale: Locale) -> &'static str {
+    let (uz, ru, en) = message_table(code);
+    match locale {
+        Locale::Uz => uz,
+        Locale::Ru => ru,
+        Locale::En => en,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode) -> Self {
+        Self {
+            code,
+            message: localized_message(code, current_locale()).to_string(),
+            details: None,
+        }
+    }
+
+    /// Attach one `details` field, turning `details` into an object if needed.
+    pub fn with_detail(mut self, key: &str, value: impl Into<Value>) -> Self {
+        let mut map = match self.details.take() {
+            Some(Value::Object(map)) => map,
+            Some(other) => {
+                let mut map = Map::new();
+                map.insert("value".to_string(), other);
+                map
+            }
+            None => Map::new(),
+        };
+        map.insert(key.to_string(), value.into());
+        self.details = Some(Value::Object(map));
+        self
+    }
+
+    /// Shorthand for the technical reason text behind the error.
+    pub fn with_reason(self, reason: impl Into<String>) -> Self {
+        self.with_detail("reason", reason.into())
+    }
+
+    pub fn invalid_input(reason: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput).with_reason(reason)
+    }
+
+    pub fn storage(reason: impl Into<String>) -> Self {
+        Self::new(ErrorCode::StorageFailed).with_reason(reason)
+    }
+
+    pub fn backend(reason: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BackendRejected).with_reason(reason)
+    }
+
+    pub fn device_not_found() -> Self {
+        Self::new(ErrorCode::DeviceNotFound)
+    }
+
+    pub fn credentials_expired() -> Self {
+        Self::new(ErrorCode::CredentialsExpired)
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self
+            .details
+            .as_ref()
+            .and_then(|d| d.get("reason"))
+            .and_then(|r| r.as_str())
+        {
+            Some(reason) => write!(f, "{}: {}", self.message, reason),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<HikvisionError> for CommandError {
+    fn from(err: HikvisionError) -> Self {
+        let code = match &err {
+            HikvisionError::Unauthorized(_) => ErrorCode::DeviceUnauthorized,
+            HikvisionError::Timeout => ErrorCode::DeviceTimeout,
+            HikvisionError::ConnectionRefused(_) => ErrorCode::DeviceOffline,
+            HikvisionError::DeviceStatus { .. } | HikvisionError::Http { .. } => {
+                ErrorCode::DeviceRejected
+            }
+            HikvisionError::Parse(_) => ErrorCode::DeviceInvalidResponse,
+            HikvisionError::PayloadTooLarge { .. } => ErrorCode::FaceTooLarge,
+            HikvisionError::Tls(_) => ErrorCode::DeviceTlsError,
+            HikvisionError::Request(_) => ErrorCode::DeviceOffline,
+        };
+        let mut command_error = Self::new(code).with_reason(err.to_string());
+        if let HikvisionError::DeviceStatus {
+            status_code,
+            sub_status_code,
+            ..
+        } = &err
+        {
+            command_error = command_error
+                .with_detail("statusCode", *status_code)
+                .with_detail("subStatusCode", sub_status_code.clone());
+        }
+        command_error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_code_message_details() {
+        let err = CommandError {
+            code: ErrorCode::CredentialsExpired,
+            message: localized_message(ErrorCode::CredentialsExpired, Locale::En).to_string(),
+            details: None,
+        }
+        .with_detail("deviceId", "d1");
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "CREDENTIALS_EXPIRED");
+        assert_eq!(value["message"], "Device credentials have expired");
+        assert_eq!(value["details"]["deviceId"], "d1");
+    }
+
+    #[test]
+    fn locale_parsing_accepts_region_tags() {
+        assert_eq!(Locale::parse("ru-RU"), Some(Locale::Ru));
+        assert_eq!(Locale::parse(" EN "), Some(Locale::En));
+        assert_eq!(Locale::parse("uz_Latn"), Some(Locale::Uz));
+        assert_eq!(Locale::parse("de"), None);
+    }
+
+    #[test]
+    fn device_status_keeps_sub_status_in_details() {
+        let err: CommandError = HikvisionError::DeviceStatus {
+            http_status: Some(400),
+            status_code: Some(6),
+            status_string: None,
+            sub_status_code: Some("employeeNoAlreadyExist".to_string()),
+            error_msg: None,
+        }
+        .into();
+        assert_eq!(err.code, ErrorCode::DeviceRejected);
+        let details = err.details.unwrap();
+        assert_eq!(details["subStatusCode"], "employeeNoAlreadyExist");
+        assert_eq!(details["statusCode"], 6);
+    }
+}
@@ -1 +1,2 @@
 // Shared Rust helpers/configs can be placed here when extracted.
+pub mod error;
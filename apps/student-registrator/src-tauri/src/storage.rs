@@ -1,2 +1,7 @@
-pub use crate::infrastructure::storage::device_store::{get_device_by_id, load_devices, save_devices};
+pub use crate::infrastructure::storage::sqlite_devices::{
+    get_device_by_id, load_devices, load_pending_password, remove_device, save_pending_password, with_devices_mut,
+};
 
+pub use crate::infrastructure::storage::job_store::{create_job, get_job, load_job_backend_token, load_jobs, save_job};
+
+pub use crate::infrastructure::storage::sqlite_store::{load_setting, save_setting};
@@ -1,7 +1,11 @@
 #[allow(unused_imports)]
 pub use crate::domain::entities::{
-    DeviceActionResult, DeviceConfig, DeviceConnectionResult, ProvisioningStartResponse,
-    ProvisioningTargetDevice, RegisterDeviceResult, RegisterResult, UserInfoEntry,
-    UserInfoSearch, UserInfoSearchResponse,
+    AccessEvent, AccessEventFilter, AccessEventPage, CapturedWebhook, CloneJob, CloneJobCursor, CloneJobProgress, CloneJobSpec, CloneJobStatus, CredentialPolicy,
+    DeviceActionResult, DeviceConfig, DeviceConnectionResult, DeviceDirection, DeviceImportEntry,
+    DeviceImportMode, DeviceImportReport, DiscoveredDevice, EventBackfillMark, EventBackfillReport, EventStreamState, EventStreamStatus, ExpiringDevice,
+    FaceQualityIssue, FaceQualityIssueCode, FaceQualityReport, FaceRegion, HttpHost, HttpHostParameterFormat,
+    HttpHostProtocol, ProvisioningStartResponse,
+    ProvisioningTargetDevice, RegisterDeviceResult, RegisterResult, RelayConfig, RelayStatus, StudentImportReport,
+    StudentImportRowReport, UserInfoEntry, UserInfoSearch, UserInfoSearchResponse, WebhookTestReport,
 };
 
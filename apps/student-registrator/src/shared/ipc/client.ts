@@ -1,3 +1,72 @@
 import { invoke as tauriInvoke } from '@tauri-apps/api';
+import type { InvokeArgs } from '@tauri-apps/api/tauri';
 
-export const invoke = tauriInvoke;
+/** Stable error codes returned by Tauri commands (see ARCHITECTURE.md, "Command errors"). */
+export type TauriErrorCode =
+  | 'CREDENTIALS_EXPIRED'
+  | 'CREDENTIALS_RENEWAL_FAILED'
+  | 'DEVICE_NOT_FOUND'
+  | 'DEVICE_LIMIT_REACHED'
+  | 'NO_DEVICES_CONFIGURED'
+  | 'DEVICE_OFFLINE'
+  | 'DEVICE_TIMEOUT'
+  | 'DEVICE_UNAUTHORIZED'
+  | 'DEVICE_TLS_ERROR'
+  | 'DEVICE_REJECTED'
+  | 'DEVICE_INVALID_RESPONSE'
+  | 'DEVICE_UNSUPPORTED'
+  | 'DEVICE_BUNDLE_UNREADABLE'
+  | 'DISCOVERY_FAILED'
+  | 'ACTIVATION_FAILED'
+  | 'PASSWORD_ROTATION_FAILED'
+  | 'USER_NOT_FOUND'
+  | 'FACE_NOT_FOUND'
+  | 'FACE_TOO_LARGE'
+  | 'INVALID_IMAGE'
+  | 'FACE_QUALITY_REJECTED'
+  | 'INVALID_INPUT'
+  | 'WEBHOOK_READ_FAILED'
+  | 'WEBHOOK_SYNC_FAILED'
+  | 'BACKEND_REJECTED'
+  | 'RELAY_FAILED'
+  | 'STORAGE_FAILED'
+  | 'JOB_NOT_FOUND'
+  | 'JOB_STATE_CONFLICT';
+
+export class TauriCommandError extends Error {
+  readonly code: TauriErrorCode;
+  readonly details: Record<string, unknown> | null;
+
+  constructor(params: { code: TauriErrorCode; message: string; details?: Record<string, unknown> | null }) {
+    super(params.message);
+    this.name = 'TauriCommandError';
+    this.code = params.code;
+    this.details = params.details ?? null;
+  }
+}
+
+function isCommandErrorPayload(
+  value: unknown,
+): value is { code: TauriErrorCode; message: string; details?: Record<string, unknown> | null } {
+  return (
+    typeof value === 'object' &&
+    value !== null &&
+    typeof (value as { code?: unknown }).code === 'string' &&
+    typeof (value as { message?: unknown }).message === 'string'
+  );
+}
+
+export async function invoke<T>(cmd: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await tauriInvoke<T>(cmd, args);
+  } catch (error: unknown) {
+    if (isCommandErrorPayload(error)) {
+      throw new TauriCommandError(error);
+    }
+    throw error;
+  }
+}
+
+export function setTauriErrorLocale(locale: 'uz' | 'ru' | 'en'): Promise<string> {
+  return invoke<string>('set_error_locale', { locale });
+}
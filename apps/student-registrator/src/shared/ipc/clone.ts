@@ -1,3 +1,4 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { BACKEND_URL } from '../http/constants';
 import { getAuthToken, getAuthUser } from '../http/session';
 import { invoke } from './client';
@@ -8,6 +9,7 @@ export async function cloneStudentsToDevice(params: {
   maxStudents?: number;
 }): Promise<{
   ok: boolean;
+  jobId: string;
   device: string;
   processed: number;
   success: number;
@@ -33,6 +35,7 @@ export async function cloneDeviceToDevice(params: {
   limit?: number;
 }): Promise<{
   ok: boolean;
+  jobId: string;
   source: string;
   target: string;
   processed: number;
@@ -47,3 +50,71 @@ export async function cloneDeviceToDevice(params: {
     limit: params.limit,
   });
 }
+
+export type CloneJobStatus = 'QUEUED' | 'RUNNING' | 'PAUSED' | 'CANCELLED' | 'COMPLETED' | 'FAILED';
+
+export interface CloneJob {
+  id: string;
+  spec:
+    | { kind: 'DEVICE_TO_DEVICE'; sourceDeviceId: string; targetDeviceId: string; limit: number }
+    | { kind: 'BACKEND_TO_DEVICE'; backendDeviceId: string; schoolId: string; limit: number };
+  status: CloneJobStatus;
+  cursor: { page: number; offset: number };
+  processed: number;
+  success: number;
+  failed: number;
+  skipped: number;
+  errors: Array<{ studentId?: string; employeeNo?: string; name?: string; reason?: string }>;
+  current?: string | null;
+  labels: Record<string, string>;
+  error?: { code: string; message: string; details?: Record<string, unknown> | null } | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface CloneJobProgress {
+  jobId: string;
+  status: CloneJobStatus;
+  processed: number;
+  success: number;
+  failed: number;
+  skipped: number;
+  current?: string | null;
+}
+
+export async function startCloneStudentsJob(params: {
+  backendDeviceId: string;
+  maxStudents?: number;
+}): Promise<CloneJob> {
+  const token = getAuthToken();
+  const user = getAuthUser();
+  return invoke<CloneJob>('start_clone_students_job', {
+    backendDeviceId: params.backendDeviceId,
+    backendUrl: BACKEND_URL,
+    backendToken: token || '',
+    schoolId: user?.schoolId || '',
+    maxStudents: params.maxStudents,
+  });
+}
+
+export async function startCloneDeviceJob(params: {
+  sourceDeviceId: string;
+  targetDeviceId: string;
+  limit?: number;
+}): Promise<CloneJob> {
+  return invoke<CloneJob>('start_clone_device_job', {
+    sourceDeviceId: params.sourceDeviceId,
+    targetDeviceId: params.targetDeviceId,
+    limit: params.limit,
+  });
+}
+
+export const listCloneJobs = (): Promise<CloneJob[]> => invoke<CloneJob[]>('list_clone_jobs');
+export const getCloneJob = (jobId: string): Promise<CloneJob> => invoke<CloneJob>('get_clone_job', { jobId });
+export const pauseCloneJob = (jobId: string): Promise<CloneJob> => invoke<CloneJob>('pause_clone_job', { jobId });
+export const resumeCloneJob = (jobId: string): Promise<CloneJob> => invoke<CloneJob>('resume_clone_job', { jobId });
+export const cancelCloneJob = (jobId: string): Promise<CloneJob> => invoke<CloneJob>('cancel_clone_job', { jobId });
+
+export function onCloneJobProgress(handler: (progress: CloneJobProgress) => void): Promise<UnlistenFn> {
+  return listen<CloneJobProgress>('clone-job-progress', (event) => handler(event.payload));
+}
@@ -1,5 +1,14 @@
 import { invoke } from './client';
-import { DeviceConfig, DeviceConnectionResult, StudentDeviceLiveCheckResult } from './types';
+import {
+  CredentialPolicy,
+  DeviceConfig,
+  DeviceConnectionResult,
+  DeviceImportMode,
+  DeviceImportReport,
+  DiscoveredDevice,
+  ExpiringDevice,
+  StudentDeviceLiveCheckResult,
+} from './types';
 
 export async function fetchDevices(): Promise<DeviceConfig[]> {
   return invoke<DeviceConfig[]>('get_devices');
@@ -13,6 +22,11 @@ export async function createDevice(device: Omit<DeviceConfig, 'id'>): Promise<De
     username: device.username,
     password: device.password,
     deviceId: device.deviceId,
+    doorCount: device.doorCount ?? null,
+    labels: device.labels ?? null,
+    direction: device.direction ?? null,
+    schoolId: device.schoolId ?? null,
+    credentialsLifetimeDays: device.credentialsLifetimeDays ?? null,
   });
 }
 
@@ -25,6 +39,11 @@ export async function updateDevice(id: string, device: Omit<DeviceConfig, 'id'>)
     username: device.username,
     password: device.password,
     deviceId: device.deviceId,
+    doorCount: device.doorCount ?? null,
+    labels: device.labels ?? null,
+    direction: device.direction ?? null,
+    schoolId: device.schoolId ?? null,
+    credentialsLifetimeDays: device.credentialsLifetimeDays ?? null,
   });
 }
 
@@ -32,6 +51,91 @@ export async function deleteDevice(id: string): Promise<boolean> {
   return invoke<boolean>('delete_device', { id });
 }
 
+export async function getCredentialPolicy(): Promise<CredentialPolicy> {
+  return invoke<CredentialPolicy>('get_credential_policy');
+}
+
+export async function setCredentialPolicy(policy: CredentialPolicy): Promise<CredentialPolicy> {
+  return invoke<CredentialPolicy>('set_credential_policy', { policy });
+}
+
+/** Re-verifies the device with a connection test and only then extends its credentials. */
+export async function renewDeviceCredentials(deviceId: string, lifetimeDays?: number): Promise<DeviceConfig> {
+  return invoke<DeviceConfig>('renew_device_credentials', { deviceId, lifetimeDays: lifetimeDays ?? null });
+}
+
+/** Devices expiring within `withinDays` (default: policy `warnDays`), expired ones included. */
+export async function listExpiringDevices(withinDays?: number): Promise<ExpiringDevice[]> {
+  return invoke<ExpiringDevice[]>('list_expiring_devices', { withinDays: withinDays ?? null });
+}
+
+/**
+ * Set a generated admin password on the device, verify it and save it. On failure the old
+ * password is restored (`PASSWORD_ROTATION_FAILED`, `details.rolledBack`).
+ */
+export async function rotateDevicePassword(deviceId: string): Promise<DeviceConfig> {
+  return invoke<DeviceConfig>('rotate_device_password', { deviceId });
+}
+
+/** Write devices (all or `deviceIds`) with passwords to a passphrase-encrypted bundle. */
+export async function exportDevices(filePath: string, passphrase: string, deviceIds?: string[]): Promise<number> {
+  return invoke<number>('export_devices', { filePath, passphrase, deviceIds: deviceIds ?? null });
+}
+
+/** Merge a bundle from `exportDevices`; conflicting devices are skipped unless mode is OVERWRITE. */
+export async function importDevices(
+  filePath: string,
+  passphrase: string,
+  mode?: DeviceImportMode,
+  dryRun?: boolean,
+): Promise<DeviceImportReport> {
+  return invoke<DeviceImportReport>('import_devices', {
+    filePath,
+    passphrase,
+    mode: mode ?? null,
+    dryRun: dryRun ?? null,
+  });
+}
+
+/** Search the local network for Hikvision devices (SADP multicast). */
+export async function discoverDevices(timeoutMs?: number): Promise<DiscoveredDevice[]> {
+  return invoke<DiscoveredDevice[]>('discover_devices', { timeoutMs: timeoutMs ?? null });
+}
+
+/**
+ * Port-scan `cidr` (default: this PC's interface network, at most a /22) for Hikvision ISAPI devices when multicast
+ * discovery finds nothing. A device found on port 443 needs scheme `https`.
+ */
+export async function scanDevices(cidr?: string, timeoutMs?: number, concurrency?: number): Promise<DiscoveredDevice[]> {
+  return invoke<DiscoveredDevice[]>('scan_devices', {
+    cidr: cidr ?? null,
+    timeoutMs: timeoutMs ?? null,
+    concurrency: concurrency ?? null,
+  });
+}
+
+/**
+ * Activate a factory-new device (`DiscoveredDevice.activated === false`) with an admin password
+ * and add it. The password must be 8-16 characters mixing at least two character classes.
+ */
+export async function activateDevice(params: {
+  host: string;
+  port: number;
+  password: string;
+  scheme?: 'http' | 'https';
+  backendId?: string;
+  schoolId?: string;
+}): Promise<DeviceConfig> {
+  return invoke<DeviceConfig>('activate_device', {
+    host: params.host,
+    port: params.port,
+    password: params.password,
+    scheme: params.scheme ?? null,
+    backendId: params.backendId ?? null,
+    schoolId: params.schoolId ?? null,
+  });
+}
+
 export async function testDeviceConnection(deviceId: string): Promise<DeviceConnectionResult> {
   return invoke<DeviceConnectionResult>('test_device_connection', { deviceId });
 }
@@ -113,6 +217,64 @@ export async function syncDeviceWebhookConfig(params: {
   });
 }
 
+export interface HttpHost {
+  /** Device slot id; leave empty in `addDeviceHttpHost` to take the first free one. */
+  id: string;
+  protocol: 'HTTP' | 'HTTPS';
+  address: string;
+  port: number;
+  url: string;
+  parameterFormat: 'JSON' | 'XML';
+  heartbeatInterval?: number | null;
+  /** Subscribed event types; empty means every event. */
+  eventTypes: string[];
+}
+
+export const listDeviceHttpHosts = (deviceId: string): Promise<HttpHost[]> =>
+  invoke<HttpHost[]>('list_device_http_hosts', { deviceId });
+export const addDeviceHttpHost = (deviceId: string, host: HttpHost): Promise<HttpHost> =>
+  invoke<HttpHost>('add_device_http_host', { deviceId, host });
+export const updateDeviceHttpHost = (deviceId: string, host: HttpHost): Promise<HttpHost> =>
+  invoke<HttpHost>('update_device_http_host', { deviceId, host });
+export const deleteDeviceHttpHost = (deviceId: string, hostId: string): Promise<boolean> =>
+  invoke<boolean>('delete_device_http_host', { deviceId, hostId });
+
+export interface CapturedWebhook {
+  method: string;
+  path: string;
+  contentType?: string | null;
+  body: string;
+  bodyBytes: number;
+  latencyMs: number;
+}
+
+export interface WebhookTestReport {
+  deviceId: string;
+  hostId: string;
+  target: string;
+  reachable: boolean;
+  latencyMs?: number | null;
+  deviceResult?: string | null;
+  captured?: CapturedWebhook | null;
+  error?: string | null;
+  /** Set when the slot could not be restored after a capture test. */
+  restoreError?: string | null;
+}
+
+export async function testDeviceWebhook(params: {
+  deviceId: string;
+  hostId?: string;
+  capture?: boolean;
+  timeoutMs?: number;
+}): Promise<WebhookTestReport> {
+  return invoke<WebhookTestReport>('test_device_webhook', {
+    deviceId: params.deviceId,
+    hostId: params.hostId ?? null,
+    capture: params.capture ?? null,
+    timeoutMs: params.timeoutMs ?? null,
+  });
+}
+
 export async function checkStudentOnDevice(
   deviceId: string,
   employeeNo: string,
@@ -0,0 +1,100 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { invoke } from './client';
+
+export interface AccessEvent {
+  deviceId: string;
+  serialNo?: number | null;
+  time: string;
+  major: number;
+  minor: number;
+  employeeNo?: string | null;
+  name?: string | null;
+  doorNo?: number | null;
+  verifyMode?: string | null;
+  pictureBase64?: string | null;
+  pictureUrl?: string | null;
+}
+
+export type EventStreamState = 'CONNECTING' | 'CONNECTED' | 'RECONNECTING' | 'STOPPED';
+
+export interface EventStreamStatus {
+  deviceId: string;
+  state: EventStreamState;
+  error?: string | null;
+  retryInMs?: number | null;
+  received: number;
+}
+
+export const startEventStream = (deviceId: string): Promise<EventStreamStatus> =>
+  invoke<EventStreamStatus>('start_event_stream', { deviceId });
+export const stopEventStream = (deviceId: string): Promise<boolean> => invoke<boolean>('stop_event_stream', { deviceId });
+export const listEventStreams = (): Promise<EventStreamStatus[]> => invoke<EventStreamStatus[]>('list_event_streams');
+
+export async function listRecentAccessEvents(params: { deviceId?: string; limit?: number } = {}): Promise<AccessEvent[]> {
+  return invoke<AccessEvent[]>('list_recent_access_events', {
+    deviceId: params.deviceId ?? null,
+    limit: params.limit ?? null,
+  });
+}
+
+export function onAccessEvent(handler: (event: AccessEvent) => void): Promise<UnlistenFn> {
+  return listen<AccessEvent>('device-access-event', (event) => handler(event.payload));
+}
+
+export function onEventStreamStatus(handler: (status: EventStreamStatus) => void): Promise<UnlistenFn> {
+  return listen<EventStreamStatus>('device-event-stream-status', (event) => handler(event.payload));
+}
+
+export interface AccessEventPage {
+  events: AccessEvent[];
+  totalMatches: number;
+  nextPosition?: number | null;
+}
+
+export async function fetchAccessEvents(params: {
+  deviceId: string;
+  startTime?: string;
+  endTime?: string;
+  major?: number;
+  minor?: number;
+  offset?: number;
+  limit?: number;
+}): Promise<AccessEventPage> {
+  return invoke<AccessEventPage>('fetch_access_events', {
+    deviceId: params.deviceId,
+    startTime: params.startTime ?? null,
+    endTime: params.endTime ?? null,
+    major: params.major ?? null,
+    minor: params.minor ?? null,
+    offset: params.offset ?? null,
+    limit: params.limit ?? null,
+  });
+}
+
+export interface EventBackfillReport {
+  deviceId: string;
+  startTime: string;
+  endTime: string;
+  fetched: number;
+  skipped: number;
+  sent: number;
+  mark: { serialNo?: number | null; time?: string | null };
+  error?: string | null;
+}
+
+/** Replay missed face events to the school webhook (`WebhookInfo.inUrlWithSecret` / `outUrlWithSecret`). */
+export async function backfillEventsToBackend(params: {
+  deviceId: string;
+  webhookUrl: string;
+  startTime?: string;
+  endTime?: string;
+  reset?: boolean;
+}): Promise<EventBackfillReport> {
+  return invoke<EventBackfillReport>('backfill_events_to_backend', {
+    deviceId: params.deviceId,
+    webhookUrl: params.webhookUrl,
+    startTime: params.startTime ?? null,
+    endTime: params.endTime ?? null,
+    reset: params.reset ?? null,
+  });
+}
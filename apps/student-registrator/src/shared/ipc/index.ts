@@ -1,38 +1,103 @@
-export { invoke } from './client';
+export { invoke, setTauriErrorLocale, TauriCommandError } from './client';
+export type { TauriErrorCode } from './client';
 
 export type {
+  CredentialPolicy,
   DeviceConfig,
   DeviceConnectionResult,
+  DeviceImportEntry,
+  DeviceImportMode,
+  DeviceImportReport,
+  DiscoveredDevice,
+  ExpiringDevice,
+  FaceQualityIssueCode,
+  FaceQualityReport,
   LiveDeviceResult,
   LiveStatus,
   RecreateUserResult,
   RegisterResult,
+  StudentImportReport,
+  StudentImportRowReport,
   StudentDeviceLiveCheckResult,
   UserInfoEntry,
   UserInfoSearchResponse,
 } from './types';
 
 export {
+  addDeviceHttpHost,
   checkStudentOnDevice,
   createDevice,
   deleteDevice,
+  deleteDeviceHttpHost,
   fetchDevices,
+  getCredentialPolicy,
   getDeviceCapabilities,
   getDeviceConfiguration,
   getDeviceWebhookConfig,
   getTauriContractVersion,
+  activateDevice,
+  discoverDevices,
+  exportDevices,
+  importDevices,
+  listDeviceHttpHosts,
+  listExpiringDevices,
   probeDeviceConnection,
+  renewDeviceCredentials,
+  scanDevices,
+  rotateDevicePassword,
+  setCredentialPolicy,
   syncDeviceWebhookConfig,
   testDeviceConnection,
+  testDeviceWebhook,
   updateDevice,
   updateDeviceConfiguration,
+  updateDeviceHttpHost,
 } from './devices';
-export type { DeviceWebhookConfig } from './devices';
+export type { CapturedWebhook, DeviceWebhookConfig, HttpHost, WebhookTestReport } from './devices';
 
-export { registerStudent } from './students';
+export { checkFaceQuality, importStudentsFromFile, registerStudent } from './students';
 
 export { deleteUser, fetchUsers, getUserFace, getUserFaceByUrl, recreateUser } from './users';
 
 export { getProvisioning, retryProvisioning, syncStudentToDevices } from './provisioning';
 
-export { cloneDeviceToDevice, cloneStudentsToDevice } from './clone';
+export {
+  cancelCloneJob,
+  cloneDeviceToDevice,
+  cloneStudentsToDevice,
+  getCloneJob,
+  listCloneJobs,
+  onCloneJobProgress,
+  pauseCloneJob,
+  resumeCloneJob,
+  startCloneDeviceJob,
+  startCloneStudentsJob,
+} from './clone';
+export type { CloneJob, CloneJobProgress, CloneJobStatus } from './clone';
+
+export {
+  backfillEventsToBackend,
+  fetchAccessEvents,
+  listEventStreams,
+  listRecentAccessEvents,
+  onAccessEvent,
+  onEventStreamStatus,
+  startEventStream,
+  stopEventStream,
+} from './events';
+export type {
+  AccessEvent,
+  AccessEventPage,
+  EventBackfillReport,
+  EventStreamState,
+  EventStreamStatus,
+} from './events';
+
+export {
+  getWebhookRelayStatus,
+  retryFailedRelayEvents,
+  startWebhookRelay,
+  stopWebhookRelay,
+  syncDeviceWebhookToRelay,
+} from './relay';
+export type { RelayStatus } from './relay';
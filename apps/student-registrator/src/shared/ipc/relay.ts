@@ -0,0 +1,44 @@
+import { invoke } from './client';
+
+export interface RelayStatus {
+  running: boolean;
+  listenPort?: number | null;
+  backendUrl?: string | null;
+  pending: number;
+  failed: number;
+  delivered: number;
+  lastError?: string | null;
+  retryInMs?: number | null;
+}
+
+export async function startWebhookRelay(params: { backendUrl: string; listenPort?: number }): Promise<RelayStatus> {
+  return invoke<RelayStatus>('start_webhook_relay', {
+    backendUrl: params.backendUrl,
+    listenPort: params.listenPort ?? null,
+  });
+}
+
+export const stopWebhookRelay = (): Promise<RelayStatus> => invoke<RelayStatus>('stop_webhook_relay');
+export const getWebhookRelayStatus = (): Promise<RelayStatus> => invoke<RelayStatus>('get_webhook_relay_status');
+export const retryFailedRelayEvents = (): Promise<RelayStatus> => invoke<RelayStatus>('retry_failed_relay_events');
+
+export async function syncDeviceWebhookToRelay(params: {
+  deviceId: string;
+  direction: 'in' | 'out';
+  targetUrl: string;
+}): Promise<{
+  ok: boolean;
+  direction: 'in' | 'out';
+  path: string;
+  relayAddress: string;
+  replacedFields: number;
+  beforeUrls: string[];
+  afterUrls: string[];
+  raw: Record<string, unknown>;
+}> {
+  return invoke('sync_device_webhook_to_relay', {
+    deviceId: params.deviceId,
+    direction: params.direction,
+    targetUrl: params.targetUrl,
+  });
+}
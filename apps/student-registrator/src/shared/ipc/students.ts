@@ -2,7 +2,7 @@ import { appLogger } from '../../utils/logger';
 import { BACKEND_URL } from '../http/constants';
 import { getAuthToken, getAuthUser } from '../http/session';
 import { invoke } from './client';
-import { RegisterResult } from './types';
+import { FaceQualityReport, RegisterResult, StudentImportReport } from './types';
 
 export async function registerStudent(
   name: string,
@@ -15,6 +15,8 @@ export async function registerStudent(
     parentPhone?: string;
     classId?: string;
     targetDeviceIds?: string[];
+    /** Register even if the photo fails the quality check (operator override). */
+    skipFaceQualityCheck?: boolean;
   },
 ): Promise<RegisterResult> {
   const token = getAuthToken();
@@ -40,5 +42,33 @@ export async function registerStudent(
     backendUrl: BACKEND_URL,
     backendToken: token || '',
     schoolId: user?.schoolId || '',
+    skipFaceQualityCheck: options?.skipFaceQualityCheck,
+  });
+}
+
+/** Offline photo check (resolution, blur, lighting, single face) to run before registering. */
+export function checkFaceQuality(faceImageBase64: string): Promise<FaceQualityReport> {
+  return invoke<FaceQualityReport>('check_face_quality', { faceImageBase64 });
+}
+
+export async function importStudentsFromFile(
+  filePath: string,
+  options?: {
+    dryRun?: boolean;
+    classIds?: Record<string, string>;
+    targetDeviceIds?: string[];
+  },
+): Promise<StudentImportReport> {
+  const token = getAuthToken();
+  const user = getAuthUser();
+
+  return invoke<StudentImportReport>('import_students_from_file', {
+    filePath,
+    dryRun: options?.dryRun ?? false,
+    classIds: options?.classIds,
+    targetDeviceIds: options?.targetDeviceIds,
+    backendUrl: BACKEND_URL,
+    backendToken: token || '',
+    schoolId: user?.schoolId || '',
   });
 }
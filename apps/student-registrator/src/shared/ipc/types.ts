@@ -9,17 +9,93 @@ export interface DeviceConfig {
   port: number;
   username: string;
   password: string;
+  /** Where the password is kept (`keyring:<id>` or `file:<id>`); the local database never holds it. */
+  passwordRef?: string | null;
+  /** Password an unfinished rotation is putting on the device; set until it is confirmed or dropped. */
+  pendingPasswordRef?: string | null;
   // Legacy UI compatibility. Metadata source-of-truth is backend.
   deviceType?: string;
   deviceId?: string | null;
+  /** `serialNumber` from deviceInfo, filled on connect; matches discovery's `serialNumber`. */
+  serialNumber?: string | null;
   credentialsUpdatedAt?: string | null;
   credentialsExpiresAt?: string | null;
+  /** Overrides the school / default lifetime; send `0` to drop the override. */
+  credentialsLifetimeDays?: number | null;
+  doorCount?: number;
+  labels?: string[];
+  direction?: DeviceDirection;
+  /** School the device belongs to when one PC serves several schools. */
+  schoolId?: string | null;
+}
+
+export type DeviceDirection = 'IN' | 'OUT' | 'BOTH';
+
+/** Device lifetime wins over `schoolLifetimeDays[schoolId]`, which wins over `defaultLifetimeDays`. */
+export interface CredentialPolicy {
+  defaultLifetimeDays: number;
+  warnDays: number;
+  schoolLifetimeDays: Record<string, number>;
+}
+
+export interface ExpiringDevice {
+  id: string;
+  backendId?: string | null;
+  host: string;
+  port: number;
+  schoolId?: string | null;
+  expiresAt: string;
+  daysLeft: number;
+  expired: boolean;
+}
+
+export type DeviceImportMode = 'SKIP' | 'OVERWRITE';
+
+export interface DeviceImportEntry {
+  key: string;
+  backendId?: string | null;
+  host: string;
+  port: number;
+  status: 'ADDED' | 'UPDATED' | 'UNCHANGED' | 'SKIPPED' | 'REJECTED';
+  localId?: string | null;
+  /** Fields that differ between the bundle and the local device. */
+  conflicts: string[];
+  reason?: string | null;
+}
+
+export interface DeviceImportReport {
+  dryRun: boolean;
+  total: number;
+  added: number;
+  updated: number;
+  unchanged: number;
+  skipped: number;
+  rejected: number;
+  devices: DeviceImportEntry[];
+}
+
+/** Device found by `discover_devices`; `ipv4Address` / `httpPort` go to `createDevice` as host / port. */
+export interface DiscoveredDevice {
+  model?: string | null;
+  serialNumber?: string | null;
+  mac?: string | null;
+  ipv4Address: string;
+  subnetMask?: string | null;
+  httpPort?: number | null;
+  commandPort?: number | null;
+  firmwareVersion?: string | null;
+  /** `false`: factory-new, needs activation before it can be added. */
+  activated?: boolean | null;
+  dhcp?: boolean | null;
+  /** Local device id when this device is already configured. */
+  localDeviceId?: string | null;
 }
 
 export interface DeviceConnectionResult {
   ok: boolean;
   message?: string;
   deviceId?: string;
+  serialNumber?: string;
 }
 
 export type LiveStatus =
@@ -90,3 +166,49 @@ export interface RecreateUserResult {
   createResult: { ok: boolean; statusString?: string; errorMsg?: string };
   faceUpload: { ok: boolean; statusString?: string; errorMsg?: string };
 }
+
+export type FaceQualityIssueCode =
+  | 'LOW_RESOLUTION'
+  | 'BLURRY'
+  | 'TOO_DARK'
+  | 'TOO_BRIGHT'
+  | 'LOW_CONTRAST'
+  | 'NO_FACE'
+  | 'MULTIPLE_FACES';
+
+export interface FaceQualityReport {
+  ok: boolean;
+  width: number;
+  height: number;
+  sharpness: number;
+  brightness: number;
+  contrast: number;
+  faceCount: number;
+  face?: { x: number; y: number; width: number; height: number } | null;
+  issues: { code: FaceQualityIssueCode; message: string; value: number; threshold: number }[];
+  /** NO_FACE / MULTIPLE_FACES: shown to the operator, never block registration. */
+  warnings: { code: FaceQualityIssueCode; message: string; value: number; threshold: number }[];
+}
+
+export interface StudentImportRowReport {
+  sheet: string;
+  row: number;
+  name: string;
+  className?: string;
+  status: 'VALID' | 'INVALID' | 'REGISTERED' | 'FAILED';
+  errors: string[];
+  warnings: string[];
+  employeeNo?: string;
+  provisioningId?: string;
+  error?: { code: string; message: string; details?: Record<string, unknown> | null };
+}
+
+export interface StudentImportReport {
+  dryRun: boolean;
+  total: number;
+  valid: number;
+  invalid: number;
+  registered: number;
+  failed: number;
+  rows: StudentImportRowReport[];
+}
@@ -11,6 +11,12 @@ const DEFAULT_FACE_ENCODE: FaceEncodeOptions = {
   maxDimension: 640,
 };
 
+/**
+ * Photos up to this size are sent as-is: the Tauri side rotates (EXIF), crops to the face and
+ * re-encodes them for the device. Only bigger files are shrunk here to keep the IPC payload small.
+ */
+const PASS_THROUGH_MAX_BYTES = 8 * 1024 * 1024;
+
 async function fileToImageBitmap(file: File): Promise<ImageBitmap> {
   if ('createImageBitmap' in window) {
     return createImageBitmap(file);
@@ -53,24 +59,24 @@ async function canvasToJpegBlob(canvas: HTMLCanvasElement, quality: number): Pro
 }
 
 /**
- * Encodes a face image as base64 JPEG and tries to keep it under 200KB.
- * This helps Hikvision devices that enforce small face image size limits.
+ * Encodes a face image as base64 for the Tauri commands, which normalize it to a device JPEG.
+ * Very large files are pre-shrunk to a JPEG under maxBytes.
  */
 export async function fileToFaceBase64(
   file: File,
   options: Partial<FaceEncodeOptions> = {},
 ): Promise<string> {
   const { maxBytes, maxDimension } = { ...DEFAULT_FACE_ENCODE, ...options };
-  const allowedTypes = new Set(['image/jpeg', 'image/png']);
+  const allowedTypes = new Set(['image/jpeg', 'image/png', 'image/webp']);
   if (!allowedTypes.has(file.type)) {
-    throw new Error('Faqat JPG yoki PNG formatidagi rasm qabul qilinadi.');
+    throw new Error('Faqat JPG, PNG yoki WebP formatidagi rasm qabul qilinadi.');
   }
   if (file.size < 10 * 1024) {
     throw new Error('Rasm hajmi 10KB dan kichik bolmasligi kerak.');
   }
 
-  // Fast path: already small enough (roughly) -> send original.
-  if (file.size <= maxBytes) {
+  // Fast path: the Tauri side normalizes the original.
+  if (file.size <= Math.max(maxBytes, PASS_THROUGH_MAX_BYTES)) {
     return fileToBase64(file);
   }
 